@@ -0,0 +1,47 @@
+use std::process::Command;
+
+fn main() {
+    build_info();
+    #[cfg(feature = "grpc")]
+    grpc();
+}
+
+/// Exposes the git commit and the compiler version to `/info`, "unknown" when built outside a checkout.
+fn build_info() {
+    let output = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|stdout| stdout.trim().to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    println!("cargo:rustc-env=GIT_HASH={}", output("git", &["rev-parse", "HEAD"]));
+    println!("cargo:rustc-env=RUSTC_VERSION={}", output(&rustc, &["--version"]));
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+/// The Echo service is declared in Rust rather than a `.proto` file, so no `protoc` is needed.
+#[cfg(feature = "grpc")]
+fn grpc() {
+    let echo = tonic_build::manual::Service::builder()
+        .name("Echo")
+        .package("httpbin")
+        .method(
+            tonic_build::manual::Method::builder()
+                .name("echo")
+                .route_name("Echo")
+                .input_type("crate::grpc::EchoRequest")
+                .output_type("crate::grpc::EchoResponse")
+                .codec_path("tonic::codec::ProstCodec")
+                .build(),
+        )
+        .build();
+
+    tonic_build::manual::Builder::new().compile(&[echo]);
+}
@@ -0,0 +1,134 @@
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+
+use crate::{config::Config, data::ErrorDetail, listener::CountedClient};
+
+/// An IPv4 or IPv6 network, `10.0.0.0/8` or `2001:db8::/32`, a bare address is a network of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(cidr: &str) -> Result<Self, Self::Err> {
+        let (address, prefix) = cidr
+            .split_once('/')
+            .map_or((cidr, None), |(address, prefix)| (address, Some(prefix)));
+        let address: IpAddr = address.parse().map_err(|e| format!("invalid network `{cidr}`: {e}"))?;
+        let bits = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            None => bits,
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= bits)
+                .ok_or_else(|| format!("invalid network `{cidr}`: the prefix length must be at most {bits}"))?,
+        };
+        // host bits are ignored, `10.1.2.3/8` is `10.0.0.0/8`
+        let network = match address {
+            IpAddr::V4(address) => IpAddr::V4((u32::from(address) & mask(prefix, 32) as u32).into()),
+            IpAddr::V6(address) => IpAddr::V6((u128::from(address) & mask(prefix, 128)).into()),
+        };
+        Ok(Cidr { network, prefix })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// The `prefix` high bits of a `bits` wide address.
+fn mask(prefix: u8, bits: u8) -> u128 {
+    match prefix {
+        0 => 0,
+        prefix => (u128::MAX << (128 - u32::from(prefix))) >> (128 - u32::from(bits)),
+    }
+}
+
+impl Cidr {
+    /// IPv4-mapped IPv6 addresses, as dual-stack sockets report IPv4 clients, match the IPv4 networks.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => (u32::from(ip) & mask(self.prefix, 32) as u32) == u32::from(network),
+            (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(ip) & mask(self.prefix, 128)) == u128::from(network),
+            _ => false,
+        }
+    }
+}
+
+/// Whether `--allow-cidr` and `--deny-cidr` let `ip` in: a denied network wins over an allowed one, and
+/// with allowed networks only those are let in.
+pub fn allowed(config: &Config, ip: IpAddr) -> bool {
+    if config.deny_cidrs.iter().any(|cidr| cidr.contains(ip)) {
+        return false;
+    }
+    config.allow_cidrs.is_empty() || config.allow_cidrs.iter().any(|cidr| cidr.contains(ip))
+}
+
+/// The client behind `peer`: `peer` itself, unless it is one of the `--trusted-proxy` networks, then the
+/// rightmost `X-Forwarded-For` hop that is not, since every hop left of it could have been sent by the client.
+/// A hop that is not an address stops the walk at the proxy that added it.
+pub fn client_ip(config: &Config, peer: IpAddr, header_map: &HeaderMap) -> IpAddr {
+    let trusted = |ip: IpAddr| config.trusted_proxies.iter().any(|cidr| cidr.contains(ip));
+    if !trusted(peer) {
+        return peer;
+    }
+    let hops: Vec<&str> = header_map
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        let Ok(hop) = hop.trim().parse::<IpAddr>() else { break };
+        client = hop;
+        if !trusted(hop) {
+            break;
+        }
+    }
+    client
+}
+
+/// Answers 403 to the TCP clients `--allow-cidr` and `--deny-cidr` keep out. The address checked is the
+/// peer of the connection, or the client address of its PROXY header with `--accept-proxy-protocol`;
+/// `X-Forwarded-For` is only followed from `--trusted-proxy` peers, see [`client_ip`], and the connection then
+/// counts for that client in `/connections/by-ip`. Runs outside every other layer of `server()`, so a denied
+/// client reaches neither CORS preflights nor socket.io.
+pub async fn filter(config: Arc<Config>, request: Request, next: Next) -> Response {
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(peer)| client_ip(&config, peer.ip(), request.headers()));
+    if let (Some(client), Some(counted)) = (client, request.extensions().get::<CountedClient>()) {
+        counted.attribute(client);
+    }
+    match client {
+        Some(client) if !allowed(&config, client) => {
+            let detail = format!("client {} is not allowed", client.to_canonical());
+            (
+                StatusCode::FORBIDDEN,
+                ErasedJson::pretty(ErrorDetail::new(403, "Forbidden", detail)),
+            )
+                .into_response()
+        }
+        _ => next.run(request).await,
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    collections::BTreeMap,
+    net::IpAddr,
+    sync::{Arc, atomic::Ordering},
+};
+
+use axum::{
+    Extension,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_extra::{
+    headers::{Authorization, HeaderMapExt, authorization::Bearer},
+    response::ErasedJson,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::watch;
+
+use crate::{config::Config, data::ErrorDetail, listener};
+
+/// Handle used to ask the running servers to drain their connections and stop.
+#[derive(Clone)]
+pub struct Shutdown(Arc<watch::Sender<bool>>);
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Shutdown(Arc::new(watch::channel(false).0))
+    }
+}
+
+impl Shutdown {
+    pub fn trigger(&self) {
+        self.0.send_replace(true);
+    }
+
+    /// Resolves once triggered, also when it was triggered before waiting.
+    pub async fn wait(&self) {
+        self.0.subscribe().wait_for(|triggered| *triggered).await.ok();
+    }
+}
+
+/// Compares the SHA-256 digests rather than the tokens, so the time taken does not tell how much of the token a
+/// guess got right.
+fn token_matches(given: &str, token: &str) -> bool {
+    Sha256::digest(given) == Sha256::digest(token)
+}
+
+/// Checks the admin bearer token, the admin routes pretend not to exist when no token is configured.
+pub fn authorize(config: &Config, header_map: &HeaderMap) -> Result<(), Response> {
+    let Some(token) = &config.admin_token else {
+        return Err((StatusCode::NOT_FOUND, ErasedJson::pretty(ErrorDetail::new(404, "Not Found", ""))).into_response());
+    };
+    match header_map.typed_get::<Authorization<Bearer>>() {
+        Some(auth) if token_matches(auth.token(), token) => Ok(()),
+        _ => Err((
+            StatusCode::FORBIDDEN,
+            ErasedJson::pretty(ErrorDetail::new(403, "Forbidden", "missing or invalid admin token")),
+        )
+            .into_response()),
+    }
+}
+
+#[derive(Serialize)]
+struct ShuttingDown {
+    shutting_down: bool,
+}
+
+pub async fn shutdown(
+    Extension(config): Extension<Arc<Config>>,
+    Extension(shutdown): Extension<Shutdown>,
+    header_map: HeaderMap,
+) -> Response {
+    if let Err(response) = authorize(&config, &header_map) {
+        return response;
+    }
+    shutdown.trigger();
+    (StatusCode::ACCEPTED, ErasedJson::pretty(ShuttingDown { shutting_down: true })).into_response()
+}
+
+#[derive(Serialize)]
+struct ConnectionsByIp {
+    total: usize,
+    by_ip: BTreeMap<IpAddr, usize>,
+}
+
+/// The open TCP connections grouped by client address: the address of the PROXY header with
+/// `--accept-proxy-protocol` rather than of the proxy, and the `X-Forwarded-For` client of the latest request
+/// of a `--trusted-proxy` connection. Clients leave the list once their last connection closes.
+pub async fn connections_by_ip(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap) -> Response {
+    if let Err(response) = authorize(&config, &header_map) {
+        return response;
+    }
+    ErasedJson::pretty(ConnectionsByIp {
+        total: listener::ACTIVE.load(Ordering::Relaxed),
+        by_ip: listener::connections_by_ip(),
+    })
+    .into_response()
+}
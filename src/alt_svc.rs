@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use axum::{Extension, response::IntoResponse};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+
+use crate::config::Config;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Alternative {
+    pub protocol: String,
+    pub authority: String,
+    /// freshness in seconds, 24 hours when absent
+    pub ma: u64,
+    pub persist: bool,
+}
+
+#[derive(Serialize)]
+struct AltSvc {
+    alt_svc: Option<String>,
+    clear: bool,
+    alternatives: Vec<Alternative>,
+}
+
+fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Splits on `sep` outside of quoted strings.
+fn split_unquoted(s: &str, sep: char) -> Vec<&str> {
+    let (mut parts, mut start, mut quoted, mut escaped) = (Vec::new(), 0, false, false);
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == sep && !quoted => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn unquote(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push(chars.next()?),
+            '"' => return None,
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Parses an `Alt-Svc` field value as specified in RFC 7838 section 3, `Ok(None)` for `clear`.
+pub fn parse(value: &str) -> Result<Option<Vec<Alternative>>, String> {
+    if value.trim() == "clear" {
+        return Ok(None);
+    }
+    split_unquoted(value, ',')
+        .into_iter()
+        .map(|alternative| {
+            let mut params = split_unquoted(alternative, ';').into_iter();
+            let (protocol, authority) = params
+                .next()
+                .and_then(|it| it.trim().split_once('='))
+                .ok_or_else(|| format!("expected protocol-id=\"alt-authority\" in `{alternative}`"))?;
+            if !is_token(protocol) {
+                return Err(format!("invalid protocol-id `{protocol}`"));
+            }
+            let authority = unquote(authority).ok_or_else(|| format!("alt-authority `{authority}` must be a quoted string"))?;
+            if !authority.contains(':') {
+                return Err(format!("alt-authority `{authority}` must be [host]:port"));
+            }
+            let mut alternative = Alternative {
+                protocol: protocol.to_string(),
+                authority,
+                ma: 86400,
+                persist: false,
+            };
+            for param in params {
+                let (name, value) = param.trim().split_once('=').ok_or_else(|| format!("invalid parameter `{param}`"))?;
+                let value = if value.starts_with('"') {
+                    unquote(value).ok_or_else(|| format!("invalid quoted string `{value}`"))?
+                } else if is_token(value) {
+                    value.to_string()
+                } else {
+                    return Err(format!("invalid parameter value `{value}`"));
+                };
+                if !is_token(name) {
+                    return Err(format!("invalid parameter name `{name}`"));
+                }
+                match name {
+                    "ma" => {
+                        alternative.ma = value
+                            .parse()
+                            .map_err(|_| format!("`ma` must be a number of seconds, not `{value}`"))?
+                    }
+                    "persist" => alternative.persist = value == "1",
+                    _ => {}
+                }
+            }
+            Ok(alternative)
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+pub async fn alt_svc(Extension(config): Extension<Arc<Config>>) -> impl IntoResponse {
+    let value = config.alt_svc.as_ref().map(|it| it.to_str().unwrap_or_default().to_string());
+    let (clear, alternatives) = match value.as_deref().map(parse) {
+        Some(Ok(None)) => (true, Vec::new()),
+        Some(Ok(Some(alternatives))) => (false, alternatives),
+        // validated when the flag was parsed
+        _ => (false, Vec::new()),
+    };
+    ErasedJson::pretty(AltSvc {
+        alt_svc: value,
+        clear,
+        alternatives,
+    })
+}
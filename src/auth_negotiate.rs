@@ -0,0 +1,182 @@
+use axum::{
+    extract::Path,
+    http::{
+        HeaderMap, HeaderValue, Method, StatusCode, Uri,
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use md5::{Digest as _, Md5};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{auth_scheme, data::ErrorDetail};
+
+const REALM: &str = "rs-httpbin";
+pub const SCHEMES: &[&str] = &["digest", "bearer", "basic"];
+
+#[derive(Deserialize)]
+pub struct NegotiateParam {
+    /// the challenges to send and their order, comma separated, `digest,bearer,basic` by default
+    order: Option<String>,
+    /// all challenges in one comma joined `WWW-Authenticate` instead of one header each
+    #[serde(default)]
+    combined: bool,
+}
+
+#[derive(Serialize)]
+struct Negotiated {
+    authenticated: bool,
+    /// the scheme of the `Authorization` header, `unknown` when it has none, `null` without the header
+    scheme: Option<String>,
+    user: Option<String>,
+    offered: Vec<&'static str>,
+}
+
+fn challenge(scheme: &str) -> String {
+    match scheme {
+        "digest" => format!(
+            r#"Digest realm="{REALM}", qop="auth", algorithm=MD5, nonce="{}", opaque="{}""#,
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        ),
+        "bearer" => format!(r#"Bearer realm="{REALM}""#),
+        _ => format!(r#"Basic realm="{REALM}", charset="UTF-8""#),
+    }
+}
+
+/// Splits `a=1, b="x, y"` into its auth-params, unquoting the quoted values.
+fn auth_params(credentials: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut rest = credentials.trim();
+    while let Some((name, value)) = rest.split_once('=') {
+        let name = name.trim().trim_start_matches(',').trim().to_ascii_lowercase();
+        let value = value.trim_start();
+        let (value, after) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let mut unquoted = String::new();
+                let mut chars = quoted.char_indices();
+                let mut end = quoted.len();
+                while let Some((index, char)) = chars.next() {
+                    match char {
+                        '\\' => unquoted.extend(chars.next().map(|(_, char)| char)),
+                        '"' => {
+                            end = index + 1;
+                            break;
+                        }
+                        char => unquoted.push(char),
+                    }
+                }
+                (unquoted, &quoted[end..])
+            }
+            None => {
+                let end = value.find(',').unwrap_or(value.len());
+                (value[..end].trim().to_string(), &value[end..])
+            }
+        };
+        params.push((name, value));
+        rest = after.trim_start().trim_start_matches(',');
+    }
+    params
+}
+
+fn md5_hex(data: &str) -> String {
+    format!("{:x}", Md5::digest(data.as_bytes()))
+}
+
+/// Checks a `Digest` response of RFC 7616 with MD5, as `qop=auth` or without `qop` as in RFC 2069. The nonce is
+/// not checked against the one sent, so no state is kept between the challenge and the retry.
+fn digest(credentials: &str, method: &Method, uri: &Uri, user: &str, passwd: &str) -> Option<String> {
+    let params = auth_params(credentials);
+    let param = |name: &str| params.iter().find(|(it, _)| it == name).map(|(_, value)| value.as_str());
+    let username = param("username")?;
+    if username != user
+        || param("realm") != Some(REALM)
+        || !param("algorithm").unwrap_or("MD5").eq_ignore_ascii_case("MD5")
+        || Some(param("uri")?) != uri.path_and_query().map(|it| it.as_str())
+    {
+        return None;
+    }
+    let ha1 = md5_hex(&format!("{username}:{REALM}:{passwd}"));
+    let ha2 = md5_hex(&format!("{method}:{}", param("uri")?));
+    let expected = match param("qop") {
+        Some("auth") => md5_hex(&format!(
+            "{ha1}:{}:{}:{}:auth:{ha2}",
+            param("nonce")?,
+            param("nc")?,
+            param("cnonce")?
+        )),
+        Some(_) => return None,
+        None => md5_hex(&format!("{ha1}:{}:{ha2}", param("nonce")?)),
+    };
+    (param("response")? == expected).then(|| username.to_string())
+}
+
+/// Answers 401 with a `Digest`, a `Bearer` and a `Basic` challenge, in `order`, and accepts whichever of them the
+/// client answers with: Basic and Digest with `user` and `passwd`, Bearer with `passwd` as the token.
+pub async fn auth_negotiate(
+    Path((user, passwd)): Path<(String, String)>,
+    Query(p): Query<NegotiateParam>,
+    method: Method,
+    uri: Uri,
+    header_map: HeaderMap,
+) -> Response {
+    let mut offered = Vec::new();
+    for scheme in p.order.as_deref().unwrap_or("digest,bearer,basic").split(',') {
+        match SCHEMES.iter().find(|it| it.eq_ignore_ascii_case(scheme.trim())) {
+            Some(scheme) if !offered.contains(scheme) => offered.push(*scheme),
+            _ => {
+                let detail = format!("`order` must list some of {} once each", SCHEMES.join(", "));
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", detail)),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    let authorization = header_map
+        .get(AUTHORIZATION)
+        .and_then(|it| it.to_str().ok())
+        // a value without a scheme could be a bare secret, it is not echoed back
+        .map(|it| auth_scheme::split(it).unwrap_or((auth_scheme::UNKNOWN, "")));
+    let scheme = authorization.map(|(scheme, _)| scheme.to_string());
+    let authenticated_user = authorization.and_then(|(scheme, credentials)| {
+        let scheme = offered.iter().find(|it| it.eq_ignore_ascii_case(scheme))?;
+        let credentials = credentials.trim();
+        match *scheme {
+            "basic" => {
+                let decoded = String::from_utf8(BASE64_STANDARD.decode(credentials).ok()?).ok()?;
+                (decoded.split_once(':')? == (user.as_str(), passwd.as_str())).then(|| user.clone())
+            }
+            "bearer" => (credentials == passwd).then(|| user.clone()),
+            _ => digest(credentials, &method, &uri, &user, &passwd),
+        }
+    });
+
+    let body = ErasedJson::pretty(Negotiated {
+        authenticated: authenticated_user.is_some(),
+        scheme,
+        user: authenticated_user.clone(),
+        offered: offered.clone(),
+    });
+    if authenticated_user.is_some() {
+        return body.into_response();
+    }
+    let mut response = (StatusCode::UNAUTHORIZED, body).into_response();
+    let challenges = offered.iter().map(|scheme| challenge(scheme));
+    let challenges: Vec<_> = if p.combined {
+        vec![challenges.collect::<Vec<_>>().join(", ")]
+    } else {
+        challenges.collect()
+    };
+    for challenge in challenges {
+        if let Ok(value) = HeaderValue::from_str(&challenge) {
+            response.headers_mut().append(WWW_AUTHENTICATE, value);
+        }
+    }
+    response
+}
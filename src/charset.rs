@@ -0,0 +1,55 @@
+use axum::{
+    extract::Path,
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+
+use crate::data::ErrorDetail;
+
+/// The `/charset/{name}` names, with the label sent in `Content-Type` and text the charset can represent.
+pub const SAMPLES: &[(&str, &encoding_rs::Encoding, &str, &str)] = &[
+    (
+        "latin1",
+        encoding_rs::WINDOWS_1252,
+        "iso-8859-1",
+        "Größe, café, naïve, Ångström, señor, ½ ± ×\n",
+    ),
+    (
+        "windows-1251",
+        encoding_rs::WINDOWS_1251,
+        "windows-1251",
+        "Привет, мир. Это русский текст.\n",
+    ),
+    (
+        "shift_jis",
+        encoding_rs::SHIFT_JIS,
+        "shift_jis",
+        "こんにちは、世界。日本語のテキストです。ｶﾀｶﾅ\n",
+    ),
+    (
+        "euc-jp",
+        encoding_rs::EUC_JP,
+        "euc-jp",
+        "こんにちは、世界。日本語のテキストです。\n",
+    ),
+    ("gbk", encoding_rs::GBK, "gbk", "你好，世界。这是简体中文文本。\n"),
+    ("big5", encoding_rs::BIG5, "big5", "你好，世界。這是繁體中文文本。\n"),
+    ("euc-kr", encoding_rs::EUC_KR, "euc-kr", "안녕하세요, 세계. 한국어 텍스트입니다.\n"),
+];
+
+/// Sample text encoded in a legacy charset and labelled with it.
+pub async fn charset(Path(name): Path<String>) -> Response {
+    let name = name.to_ascii_lowercase();
+    let Some((_, encoding, label, text)) = SAMPLES.iter().find(|(it, ..)| *it == name) else {
+        let supported = SAMPLES.iter().map(|(it, ..)| *it).collect::<Vec<_>>().join(", ");
+        let detail = format!("unsupported charset `{name}`, expected one of {supported}");
+        return (
+            StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", detail)),
+        )
+            .into_response();
+    };
+    let (bytes, _, _) = encoding.encode(text);
+    ([(CONTENT_TYPE, format!("text/plain; charset={label}"))], bytes.into_owned()).into_response()
+}
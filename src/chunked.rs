@@ -0,0 +1,135 @@
+use std::{sync::Arc, time::Instant};
+
+use axum::{
+    Extension,
+    extract::Request,
+    http::{
+        HeaderName, StatusCode,
+        header::{CONTENT_LENGTH, TRANSFER_ENCODING},
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use futures_util::StreamExt as _;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{config::Config, data::ErrorDetail};
+
+#[derive(Serialize)]
+struct Upload {
+    transfer_encoding: Option<String>,
+    content_length: Option<String>,
+    bytes: usize,
+    /// data frames as handed over by hyper, not necessarily the chunks on the wire
+    frames: usize,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+struct Drained {
+    bytes: usize,
+    frames: usize,
+    /// from the handler to the end of the body
+    elapsed_ms: f64,
+    /// in decimal megabytes, 10^6 bytes, per second
+    mb_per_s: f64,
+}
+
+/// The 413 of a body past `--max-body-bytes`, for the handlers reading the body themselves.
+pub fn too_large(max_body_bytes: usize) -> Response {
+    let detail = format!("request body is larger than {max_body_bytes} bytes");
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        ErasedJson::pretty(ErrorDetail::new(413, "Payload Too Large", detail)),
+    )
+        .into_response()
+}
+
+/// Reads the body as it is dechunked and reports its size and digest, 413 past `--max-body-bytes`.
+pub async fn chunked_upload(Extension(config): Extension<Arc<Config>>, request: Request) -> Response {
+    let header = |name: HeaderName| {
+        request
+            .headers()
+            .get(name)
+            .map(|it| String::from_utf8_lossy(it.as_bytes()).into_owned())
+    };
+    let transfer_encoding = header(TRANSFER_ENCODING);
+    let content_length = header(CONTENT_LENGTH);
+
+    let mut body = request.into_body().into_data_stream();
+    let mut hasher = Sha256::new();
+    let (mut bytes, mut frames) = (0, 0);
+    while let Some(frame) = body.next().await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", e.to_string())),
+                )
+                    .into_response();
+            }
+        };
+        bytes += frame.len();
+        frames += 1;
+        if bytes > config.max_body_bytes {
+            return too_large(config.max_body_bytes);
+        }
+        hasher.update(&frame);
+    }
+
+    ErasedJson::pretty(Upload {
+        transfer_encoding,
+        content_length,
+        bytes,
+        frames,
+        sha256: format!("{:x}", hasher.finalize()),
+    })
+    .into_response()
+}
+
+/// Reads the body as fast as it comes and throws every frame away, then reports the upload throughput. A
+/// `Content-Length` past `--max-body-bytes` is answered 413 before reading anything.
+pub async fn drain(Extension(config): Extension<Arc<Config>>, request: Request) -> Response {
+    let started = Instant::now();
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|it| it.to_str().ok()?.parse::<usize>().ok());
+    if declared.is_some_and(|declared| declared > config.max_body_bytes) {
+        return too_large(config.max_body_bytes);
+    }
+
+    let mut body = request.into_body().into_data_stream();
+    let (mut bytes, mut frames) = (0, 0);
+    while let Some(frame) = body.next().await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", e.to_string())),
+                )
+                    .into_response();
+            }
+        };
+        bytes += frame.len();
+        frames += 1;
+        if bytes > config.max_body_bytes {
+            return too_large(config.max_body_bytes);
+        }
+    }
+
+    let elapsed = started.elapsed().as_secs_f64();
+    ErasedJson::pretty(Drained {
+        bytes,
+        frames,
+        elapsed_ms: elapsed * 1000.0,
+        mb_per_s: match elapsed > 0.0 {
+            true => bytes as f64 / 1e6 / elapsed,
+            false => 0.0,
+        },
+    })
+    .into_response()
+}
@@ -0,0 +1,149 @@
+use axum::{
+    http::{
+        HeaderMap, HeaderName, HeaderValue, Method, StatusCode,
+        header::{
+            ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, IF_UNMODIFIED_SINCE,
+            LAST_MODIFIED, RANGE,
+        },
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use jiff::Timestamp;
+use serde::Deserialize;
+
+use crate::{
+    data::ErrorDetail,
+    etag::{etag_for, if_match, none_match},
+    range::{Resolved, if_range_matches, resolve},
+};
+
+/// The default `Last-Modified` of the `/conditional` resource.
+pub const LAST_MODIFIED_DEFAULT: &str = "Thu, 01 Jan 2026 00:00:00 GMT";
+static X_PRECONDITION: HeaderName = HeaderName::from_static("x-precondition");
+
+#[derive(Deserialize)]
+pub struct ConditionalParam {
+    /// the opaque tag of the resource, its quoted `ETag` is the hash of the body by default
+    pub etag: Option<String>,
+    /// an HTTP-date, `Thu, 01 Jan 2026 00:00:00 GMT` by default
+    pub last_modified: Option<String>,
+}
+
+/// Which step of the evaluation decided the response, reported in `X-Precondition`.
+enum Outcome {
+    Failed(&'static str),
+    NotModified(&'static str),
+    /// the step that let the request through, the `Range` is served unless `If-Range` failed
+    Passed(&'static str, bool),
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|it| it.to_str().ok())
+}
+
+/// Dates that are not a valid HTTP-date are ignored, as RFC 9110 asks, and compare with a one second precision.
+fn date(headers: &HeaderMap, name: &HeaderName) -> Option<i64> {
+    header(headers, name)
+        .and_then(|value| crate::http_date::parse(value, Timestamp::now()).ok())
+        .map(|(_, timestamp)| timestamp.as_second())
+}
+
+/// RFC 9110 section 13.2.2: `If-Match`, or else `If-Unmodified-Since`, then `If-None-Match`, or else
+/// `If-Modified-Since` for GET and HEAD, then `If-Range` for a GET with a `Range`.
+fn evaluate(method: &Method, headers: &HeaderMap, validators: &HeaderMap, last_modified: i64) -> Outcome {
+    let etag = header(validators, &ETAG).unwrap_or_default();
+    let safe = method == Method::GET || method == Method::HEAD;
+    let mut step = "none";
+    if headers.contains_key(IF_MATCH) {
+        if !if_match(headers, etag) {
+            return Outcome::Failed("if-match");
+        }
+        step = "if-match";
+    } else if let Some(since) = date(headers, &IF_UNMODIFIED_SINCE) {
+        if last_modified > since {
+            return Outcome::Failed("if-unmodified-since");
+        }
+        step = "if-unmodified-since";
+    }
+
+    if headers.contains_key(IF_NONE_MATCH) {
+        if none_match(headers, etag) {
+            return match safe {
+                true => Outcome::NotModified("if-none-match"),
+                false => Outcome::Failed("if-none-match"),
+            };
+        }
+        step = "if-none-match";
+    } else if let Some(since) = date(headers, &IF_MODIFIED_SINCE).filter(|_| safe) {
+        if last_modified <= since {
+            return Outcome::NotModified("if-modified-since");
+        }
+        step = "if-modified-since";
+    }
+
+    if method != Method::GET || !headers.contains_key(RANGE) {
+        return Outcome::Passed(step, false);
+    }
+    match header(headers, &IF_RANGE) {
+        None => Outcome::Passed(step, true),
+        Some(if_range) => Outcome::Passed("if-range", if_range_matches(if_range, validators)),
+    }
+}
+
+/// A resource with an `ETag` and a `Last-Modified` evaluating every precondition header in the order of RFC
+/// 9110 section 13.2.2: 412 when `If-Match` or `If-Unmodified-Since` fails, 304 when `If-None-Match` or
+/// `If-Modified-Since` does for GET and HEAD (412 for other methods), and a `Range` only while `If-Range`
+/// matches. `X-Precondition` names the header that decided.
+pub async fn conditional(method: Method, headers: HeaderMap, Query(p): Query<ConditionalParam>) -> Response {
+    let body: Vec<u8> = (b'a'..=b'z').cycle().take(100).collect();
+    let etag = match p.etag {
+        Some(tag) if tag.is_empty() || !tag.bytes().all(|byte| byte.is_ascii_graphic() && byte != b'"') => {
+            return ErrorDetail::response(StatusCode::BAD_REQUEST, format!("invalid etag `{tag}`"));
+        }
+        Some(tag) => format!("\"{tag}\""),
+        None => etag_for(&body),
+    };
+    let last_modified = p.last_modified.as_deref().unwrap_or(LAST_MODIFIED_DEFAULT);
+    let last_modified = match crate::http_date::parse(last_modified, Timestamp::now()) {
+        Ok((_, timestamp)) => timestamp,
+        Err(e) => return ErrorDetail::response(StatusCode::BAD_REQUEST, format!("invalid last_modified `{last_modified}`: {e}")),
+    };
+    let mut validators = HeaderMap::new();
+    validators.insert(ETAG, HeaderValue::from_str(&etag).expect("checked"));
+    let http_date = last_modified.strftime("%a, %d %b %Y %H:%M:%S GMT").to_string();
+    validators.insert(LAST_MODIFIED, HeaderValue::from_str(&http_date).expect("a date"));
+
+    let (step, range) = match evaluate(&method, &headers, &validators, last_modified.as_second()) {
+        Outcome::Failed(step) => {
+            let detail = format!("the `{step}` precondition failed");
+            let error = ErasedJson::pretty(ErrorDetail::new(412, "Precondition Failed", detail));
+            let step = [(X_PRECONDITION.clone(), HeaderValue::from_static(step))];
+            return (StatusCode::PRECONDITION_FAILED, validators, step, error).into_response();
+        }
+        Outcome::NotModified(step) => {
+            let step = [(X_PRECONDITION.clone(), HeaderValue::from_static(step))];
+            return (StatusCode::NOT_MODIFIED, validators, step).into_response();
+        }
+        Outcome::Passed(step, range) => (step, range),
+    };
+    let mut headers_out = validators;
+    headers_out.insert(X_PRECONDITION.clone(), HeaderValue::from_static(step));
+    headers_out.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    headers_out.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+
+    let len = body.len();
+    match header(&headers, &RANGE).filter(|_| range).map(|range| resolve(range, len)) {
+        Some(Resolved::Partial(range)) => {
+            let content_range = format!("bytes {}-{}/{len}", range.start, range.end - 1);
+            headers_out.insert(CONTENT_RANGE, HeaderValue::from_str(&content_range).expect("digits"));
+            (StatusCode::PARTIAL_CONTENT, headers_out, body[range].to_vec()).into_response()
+        }
+        Some(Resolved::Unsatisfiable) => {
+            let content_range = format!("bytes */{len}");
+            headers_out.insert(CONTENT_RANGE, HeaderValue::from_str(&content_range).expect("digits"));
+            (StatusCode::RANGE_NOT_SATISFIABLE, headers_out).into_response()
+        }
+        Some(Resolved::Full) | None => (headers_out, body).into_response(),
+    }
+}
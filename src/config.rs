@@ -0,0 +1,323 @@
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use axum::http::{HeaderName, HeaderValue};
+
+const USAGE: &str = indoc::indoc! {"
+    Usage: rs-httpbin [OPTIONS]
+
+    Options:
+          --admin-token <TOKEN>             Enables the /admin routes, guarded by this bearer token
+          --add-header <NAME:VALUE>         Adds a static header to every response, repeatable
+          --force-header                    Lets --add-header override headers set by the endpoints
+          --request-id-header <NAME>        Header used to propagate and generate request ids [default: x-request-id]
+          --pretty                          Pretty-prints the JSON of the reflection endpoints unless `?pretty=false`
+          --grpc-port <PORT>                Serves the gRPC Echo and Health services on this port (grpc feature)
+          --max-header-bytes <BYTES>        Answers 431 when the request headers are larger [default: 32768]
+          --max-uri-length <BYTES>          Answers 414 when the request target, path and query, is longer [default: 8192]
+          --uds <PATH>                      Listens on this Unix domain socket instead of TCP (unix only)
+          --accept-proxy-protocol           Expects a PROXY protocol v1/v2 header on every TCP connection
+          --session-secret <SECRET>         Signs the /cookies/jar session cookie, at least 32 bytes [default: random]
+          --session-ttl <SECONDS>           Expires idle /cookies/jar sessions [default: 1800]
+          --max-connections <N>             Limits the concurrent TCP connections, see --connection-overflow
+          --connection-overflow <MODE>      `reject` with a 503, or `queue` in the listen backlog [default: reject]
+          --header-read-timeout <SECONDS>   Closes HTTP/1 connections slower to send their headers [default: 10]
+          --max-body-bytes <BYTES>          Answers 413 to /chunked-upload and /drain bodies larger than this [default: 10485760]
+          --http3                           Also serves HTTP/3 on the same UDP port, advertised with Alt-Svc (http3 feature)
+          --tls                             Serves HTTPS instead of HTTP on the TCP port, with a self-signed certificate (tls feature)
+          --alt-svc <VALUE>                 Adds this Alt-Svc header to every response, e.g. `h3=":443"; ma=3600`
+          --har-capacity <N>                Records the last N requests for /admin/har, buffering their bodies [default: 0, off]
+          --tcp-nodelay                     Disables Nagle's algorithm on accepted TCP connections, see /socket-opts
+          --accept-delay <SECONDS>          Waits this long before accepting each TCP connection, fractions allowed [default: 0]
+          --latency-profile <PATH>          Delays and fails routes as the rules of this JSON file say, reloaded on SIGHUP
+          --allow-cidr <NETWORK>            Only lets in TCP clients from this network, repeatable, e.g. `10.0.0.0/8`
+          --deny-cidr <NETWORK>             Answers 403 to TCP clients from this network, repeatable, wins over --allow-cidr
+          --trusted-proxy <NETWORK>         Follows `X-Forwarded-For` from peers in this network for the above, repeatable
+          --request-deadline <SECONDS>      Answers 504 to requests whose response is not ready in time, fractions allowed [default: off]
+          --max-request-deadline <SECONDS>  Caps the deadline a request asks for with `X-Request-Deadline` [default: 120]
+          --server-header <VALUE>           Sends this `Server` header on every response [default: none]
+          --date-header <VALUE>             Sends this `Date` instead of the current time, any value [default: the clock]
+          --compress-min-size <BYTES>       Compresses only responses of at least this size, or of unknown size [default: 256]
+          --keepalive-timeout <SECONDS>     Closes connections idle this many whole seconds between requests, 0 for never [default: 75]
+          --keepalive-max-requests <N>      Closes HTTP/1 connections after N requests [default: unlimited]
+          --serve-file <PATH@ROUTE>         Serves this file at this route with `ETag` and `Last-Modified`, repeatable
+          --fuzz                            Enables the /fuzz routes sending malformed responses for client robustness testing
+          --redirect-allowlist <HOSTS>      Restricts /redirect-to to these hosts, comma separated, repeatable, e.g. `*.example.com`
+          --allow-proxy <HOSTS>             Enables /proxy/{url} to these hosts, comma separated, repeatable (proxy feature)
+          --vhost <HOSTS=BEHAVIOR>          Answers these hosts, comma separated, with `httpbin`, `status:CODE`, `text:BODY` or `redirect:URL`, repeatable
+          --vhost-default <BEHAVIOR>        Answers hosts no `--vhost` lists like this [default: httpbin]
+          --schema-allowlist <HOSTS>        Lets /validate-json fetch `?schema=` URLs from these hosts, comma separated, repeatable (json-schema feature)
+      -h, --help                            Print help
+"};
+
+/// What happens to connections beyond `--max-connections`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    #[default]
+    Reject,
+    Queue,
+}
+
+/// Server configuration, parsed from the command line at startup.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub admin_token: Option<String>,
+    pub add_headers: Vec<(HeaderName, HeaderValue)>,
+    pub force_headers: bool,
+    pub request_id_header: HeaderName,
+    pub pretty: bool,
+    pub max_header_bytes: usize,
+    pub max_uri_length: usize,
+    pub uds: Option<PathBuf>,
+    pub accept_proxy_protocol: bool,
+    pub session_secret: Option<String>,
+    pub session_ttl: Duration,
+    pub max_connections: Option<usize>,
+    pub connection_overflow: Overflow,
+    pub header_read_timeout: Duration,
+    pub max_body_bytes: usize,
+    pub alt_svc: Option<HeaderValue>,
+    pub har_capacity: usize,
+    pub tcp_nodelay: bool,
+    pub accept_delay: Duration,
+    pub latency_profile: Option<crate::latency_profile::ProfileFile>,
+    pub allow_cidrs: Vec<crate::access::Cidr>,
+    pub deny_cidrs: Vec<crate::access::Cidr>,
+    pub trusted_proxies: Vec<crate::access::Cidr>,
+    pub request_deadline: Option<Duration>,
+    pub max_request_deadline: Duration,
+    pub server_header: Option<HeaderValue>,
+    pub date_header: Option<HeaderValue>,
+    pub compress_min_size: usize,
+    pub keepalive_timeout: Option<Duration>,
+    pub keepalive_max_requests: Option<u64>,
+    pub serve_files: Vec<crate::static_file::ServeFile>,
+    pub fuzz: bool,
+    pub redirect_allowlist: Vec<String>,
+    pub vhosts: Vec<crate::vhost::Vhost>,
+    pub vhost_default: crate::vhost::Behavior,
+    #[cfg(feature = "grpc")]
+    pub grpc_port: Option<u16>,
+    #[cfg(feature = "http3")]
+    pub http3: bool,
+    #[cfg(feature = "tls")]
+    pub tls: bool,
+    #[cfg(feature = "proxy")]
+    pub proxy_allowlist: Vec<String>,
+    #[cfg(feature = "json-schema")]
+    pub schema_allowlist: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            admin_token: None,
+            add_headers: Vec::new(),
+            force_headers: false,
+            request_id_header: HeaderName::from_static("x-request-id"),
+            pretty: false,
+            max_header_bytes: 32 * 1024,
+            max_uri_length: 8 * 1024,
+            uds: None,
+            accept_proxy_protocol: false,
+            session_secret: None,
+            session_ttl: Duration::from_secs(30 * 60),
+            max_connections: None,
+            connection_overflow: Overflow::Reject,
+            header_read_timeout: Duration::from_secs(10),
+            max_body_bytes: 10 * 1024 * 1024,
+            alt_svc: None,
+            har_capacity: 0,
+            tcp_nodelay: false,
+            accept_delay: Duration::ZERO,
+            latency_profile: None,
+            allow_cidrs: Vec::new(),
+            deny_cidrs: Vec::new(),
+            trusted_proxies: Vec::new(),
+            request_deadline: None,
+            max_request_deadline: Duration::from_secs(120),
+            server_header: None,
+            date_header: None,
+            compress_min_size: 256,
+            keepalive_timeout: Some(Duration::from_secs(75)),
+            keepalive_max_requests: None,
+            serve_files: Vec::new(),
+            fuzz: false,
+            redirect_allowlist: Vec::new(),
+            vhosts: Vec::new(),
+            vhost_default: Default::default(),
+            #[cfg(feature = "grpc")]
+            grpc_port: None,
+            #[cfg(feature = "http3")]
+            http3: false,
+            #[cfg(feature = "tls")]
+            tls: false,
+            #[cfg(feature = "proxy")]
+            proxy_allowlist: Vec::new(),
+            #[cfg(feature = "json-schema")]
+            schema_allowlist: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for `{flag}`\n\n{USAGE}"))
+            };
+
+            match flag.as_str() {
+                "--admin-token" => config.admin_token = Some(value()?),
+                "--add-header" => config.add_headers.push(parse_header(&value()?)?),
+                "--force-header" => config.force_headers = true,
+                "--request-id-header" => {
+                    let name = value()?;
+                    config.request_id_header = HeaderName::from_str(&name).map_err(|e| format!("invalid header name `{name}`: {e}"))?;
+                }
+                "--pretty" => config.pretty = true,
+                "--max-header-bytes" => config.max_header_bytes = parse_number(&flag, &value()?)?,
+                "--max-uri-length" => config.max_uri_length = parse_number(&flag, &value()?)?,
+                #[cfg(unix)]
+                "--uds" => config.uds = Some(value()?.into()),
+                #[cfg(not(unix))]
+                "--uds" => return Err("`--uds` is not supported on this platform".to_string()),
+                "--accept-proxy-protocol" => config.accept_proxy_protocol = true,
+                "--session-secret" => {
+                    let secret = value()?;
+                    if secret.len() < 32 {
+                        return Err(format!("`{flag}` must be at least 32 bytes long"));
+                    }
+                    config.session_secret = Some(secret);
+                }
+                "--session-ttl" => config.session_ttl = Duration::from_secs(parse_number(&flag, &value()?)?),
+                "--max-connections" => config.max_connections = Some(parse_number(&flag, &value()?)?),
+                "--connection-overflow" => {
+                    config.connection_overflow = match value()?.as_str() {
+                        "reject" => Overflow::Reject,
+                        "queue" => Overflow::Queue,
+                        other => return Err(format!("invalid value `{other}` for `{flag}`, expected `reject` or `queue`")),
+                    }
+                }
+                "--header-read-timeout" => config.header_read_timeout = parse_seconds(&flag, &value()?)?,
+                "--max-body-bytes" => config.max_body_bytes = parse_number(&flag, &value()?)?,
+                "--alt-svc" => {
+                    let alt_svc = value()?;
+                    crate::alt_svc::parse(&alt_svc).map_err(|e| format!("invalid value for `{flag}`: {e}"))?;
+                    config.alt_svc = Some(HeaderValue::from_str(&alt_svc).map_err(|e| format!("invalid value for `{flag}`: {e}"))?);
+                }
+                "--tcp-nodelay" => config.tcp_nodelay = true,
+                "--accept-delay" => config.accept_delay = parse_seconds(&flag, &value()?)?,
+                "--latency-profile" => {
+                    let path = PathBuf::from(value()?);
+                    let profile = crate::latency_profile::load(&path)?;
+                    config.latency_profile = Some(crate::latency_profile::ProfileFile { path, profile });
+                }
+                "--allow-cidr" => config.allow_cidrs.push(value()?.parse()?),
+                "--deny-cidr" => config.deny_cidrs.push(value()?.parse()?),
+                "--trusted-proxy" => config.trusted_proxies.push(value()?.parse()?),
+                "--request-deadline" => config.request_deadline = Some(parse_seconds(&flag, &value()?)?),
+                "--max-request-deadline" => config.max_request_deadline = parse_seconds(&flag, &value()?)?,
+                "--server-header" => config.server_header = Some(parse_header_value(&flag, &value()?)?),
+                "--date-header" => config.date_header = Some(parse_header_value(&flag, &value()?)?),
+                "--compress-min-size" => config.compress_min_size = parse_number(&flag, &value()?)?,
+                // whole seconds, as the `Keep-Alive: timeout=` it is advertised with
+                "--keepalive-timeout" => {
+                    config.keepalive_timeout = Some(Duration::from_secs(parse_number(&flag, &value()?)?)).filter(|it| !it.is_zero())
+                }
+                "--keepalive-max-requests" => match parse_number(&flag, &value()?)? {
+                    0 => return Err(format!("`{flag}` must be at least 1")),
+                    max => config.keepalive_max_requests = Some(max),
+                },
+                "--fuzz" => config.fuzz = true,
+                "--redirect-allowlist" => config.redirect_allowlist.extend(parse_hosts(&flag, &value()?)?),
+                #[cfg(feature = "proxy")]
+                "--allow-proxy" => config.proxy_allowlist.extend(parse_hosts(&flag, &value()?)?),
+                #[cfg(feature = "json-schema")]
+                "--schema-allowlist" => config.schema_allowlist.extend(parse_hosts(&flag, &value()?)?),
+                "--vhost" => config.vhosts.push(value()?.parse()?),
+                "--vhost-default" => config.vhost_default = value()?.parse()?,
+                "--serve-file" => config.serve_files.push(value()?.parse()?),
+                "--har-capacity" => config.har_capacity = parse_number(&flag, &value()?)?,
+                #[cfg(feature = "grpc")]
+                "--grpc-port" => config.grpc_port = Some(parse_number(&flag, &value()?)?),
+                #[cfg(feature = "http3")]
+                "--http3" => config.http3 = true,
+                #[cfg(feature = "tls")]
+                "--tls" => config.tls = true,
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                _ => return Err(format!("unknown argument `{flag}`\n\n{USAGE}")),
+            }
+        }
+
+        if config.har_capacity > 0 && config.admin_token.is_none() {
+            return Err("`--har-capacity` requires `--admin-token` to read the recording".to_string());
+        }
+
+        if config
+            .request_deadline
+            .is_some_and(|deadline| deadline > config.max_request_deadline)
+        {
+            return Err("`--request-deadline` cannot be longer than `--max-request-deadline`".to_string());
+        }
+
+        Ok(config)
+    }
+}
+
+fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| format!("invalid header `{header}`, expected NAME:VALUE"))?;
+    let name = HeaderName::from_str(name.trim()).map_err(|e| format!("invalid header name `{name}`: {e}"))?;
+    let value = HeaderValue::from_str(value.trim()).map_err(|e| format!("invalid header value `{value}`: {e}"))?;
+    Ok((name, value))
+}
+
+/// Comma separated hosts, `example.com` for that host only and `*.example.com` for its subdomains only.
+pub fn parse_hosts(flag: &str, value: &str) -> Result<Vec<String>, String> {
+    let mut hosts = Vec::new();
+    for host in value.split(',').map(|it| it.trim().trim_end_matches('.').to_ascii_lowercase()) {
+        let rest = host.strip_prefix("*.").unwrap_or(&host);
+        if rest.is_empty() || rest.contains('*') {
+            return Err(format!(
+                "invalid host `{host}` for `{flag}`, expected `example.com` or `*.example.com`"
+            ));
+        }
+        hosts.push(host);
+    }
+    Ok(hosts)
+}
+
+/// Whether a lowercase host is in a list of [`parse_hosts`].
+pub fn host_allowed(allowlist: &[String], host: &str) -> bool {
+    allowlist.iter().any(|entry| match entry.strip_prefix('*') {
+        Some(suffix) => host.ends_with(suffix),
+        None => host == entry,
+    })
+}
+
+fn parse_header_value(flag: &str, value: &str) -> Result<HeaderValue, String> {
+    HeaderValue::from_str(value).map_err(|e| format!("invalid value for `{flag}`: {e}"))
+}
+
+fn parse_seconds(flag: &str, value: &str) -> Result<Duration, String> {
+    let seconds: f64 = parse_number(flag, value)?;
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("invalid value for `{flag}`: {e}"))
+}
+
+fn parse_number<T: FromStr>(flag: &str, value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e| format!("invalid value `{value}` for `{flag}`: {e}"))
+}
@@ -1,9 +1,14 @@
 use std::{collections::BTreeMap, net::IpAddr};
 
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
 use derive_more::{Deref, DerefMut};
 use serde::{Deserialize, Serialize, Serializer};
 
-#[derive(Serialize, Deserialize, Default, Deref, DerefMut)]
+#[derive(Serialize, Deserialize, Default, Clone, Deref, DerefMut)]
 #[serde(transparent)]
 pub struct MyVec<T>
 where
@@ -24,7 +29,7 @@ where
     }
 }
 
-#[derive(Serialize, Default, Deref, DerefMut)]
+#[derive(Serialize, Default, Clone, Deref, DerefMut)]
 pub struct Headers {
     pub(crate) headers: BTreeMap<String, MyVec<String>>,
 }
@@ -35,9 +40,28 @@ pub struct Queries {
     pub inner: BTreeMap<String, MyVec<String>>,
 }
 
+/// The TLS session of an HTTP/3 connection, or of a TCP one with `--tls`.
+#[derive(Serialize, Clone, Debug)]
+pub struct TlsInfo {
+    pub version: &'static str,
+    pub alpn: Option<String>,
+    /// the SNI the client sent
+    pub server_name: Option<String>,
+}
+
+/// Credentials of a peer connected over a Unix domain socket.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct PeerCred {
+    pub pid: Option<i32>,
+    pub uid: u32,
+    pub gid: u32,
+}
+
 #[derive(Serialize)]
 pub struct Ip {
     pub origin: IpAddr,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer: Option<PeerCred>,
 }
 
 #[derive(Serialize)]
@@ -49,19 +73,29 @@ pub struct UserAgent {
 pub struct Http {
     pub method: String,
     pub uri: String,
+    /// how the request target was sent, proxies use the absolute-form
+    pub target_form: &'static str,
     #[serde(flatten)]
     pub headers: Headers,
     pub origin: Option<IpAddr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer: Option<PeerCred>,
     pub args: Queries,
     pub data: String,
     pub json: Option<serde_json::Value>,
     pub form: BTreeMap<String, MyVec<String>>,
     pub files: BTreeMap<String, MyVec<String>>,
+    /// how a compressed body was decoded before being reflected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<crate::decompress::Decoded>,
+    /// the `--vhost` the host matched, when there are any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vhost: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct SseData {
-    pub id: i32,
+    pub id: usize,
     pub timestamp: i64,
 }
 
@@ -81,4 +115,10 @@ impl ErrorDetail {
             detail: detail.to_string(),
         }
     }
+
+    /// The JSON error response of `status`, named after its canonical reason.
+    pub fn response(status: StatusCode, detail: impl ToString) -> Response {
+        let reason = status.canonical_reason().unwrap_or_default();
+        (status, ErasedJson::pretty(ErrorDetail::new(status.as_u16().into(), reason, detail))).into_response()
+    }
 }
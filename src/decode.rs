@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, StatusCode, Uri, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+
+use crate::{data::ErrorDetail, pretty::Pretty};
+
+const PREFIX: &str = "/decode-test";
+
+#[derive(Serialize)]
+struct Decoded {
+    path: String,
+    decoded: String,
+    query: Vec<(String, String)>,
+}
+
+#[derive(Serialize)]
+struct DecodeError {
+    #[serde(flatten)]
+    error: ErrorDetail,
+    /// `path` or `query`
+    component: &'static str,
+    sequence: String,
+    offset: usize,
+}
+
+/// Decodes `%XX` escapes strictly, where the extractors leave bad escapes as they are and replace
+/// invalid UTF-8: returns the offending sequence and its byte offset in `input` instead.
+pub fn decode(input: &str) -> Result<String, (String, usize)> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    // byte offset in `input` of every decoded byte, to locate invalid UTF-8
+    let mut offsets = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'%' {
+            decoded.push(bytes[i]);
+            offsets.push(i);
+            i += 1;
+            continue;
+        }
+        match bytes.get(i + 1..i + 3) {
+            Some(&[high, low]) if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                let hex = |digit: u8| (digit as char).to_digit(16).unwrap_or_default() as u8;
+                decoded.push((hex(high) << 4) | hex(low));
+                offsets.push(i);
+                i += 3;
+            }
+            _ => return Err((input[i..].chars().take(3).collect(), i)),
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|e| {
+        let bytes = e.as_bytes();
+        let start = e.utf8_error().valid_up_to();
+        let end = e.utf8_error().error_len().map_or(bytes.len(), |len| start + len);
+        let sequence = bytes[start..end].iter().map(|byte| format!("%{byte:02X}")).collect();
+        (sequence, offsets[start])
+    })
+}
+
+fn bad_request(component: &'static str, (sequence, offset): (String, usize)) -> Response {
+    let detail = format!("malformed percent-encoding `{sequence}` at offset {offset} of the {component}");
+    (
+        StatusCode::BAD_REQUEST,
+        ErasedJson::pretty(DecodeError {
+            error: ErrorDetail::new(400, "Bad Request", detail),
+            component,
+            sequence,
+            offset,
+        }),
+    )
+        .into_response()
+}
+
+/// Strictly decodes `application/x-www-form-urlencoded` pairs, `+` being a space, in the order they come in.
+/// Errors carry the byte offset in `input`.
+pub fn decode_pairs(input: &str) -> Result<Vec<(String, String)>, (String, usize)> {
+    let mut pairs = Vec::new();
+    let mut offset = 0;
+    for pair in input.split('&') {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let name_offset = offset;
+        let value_offset = offset + name.len() + 1;
+        offset += pair.len() + 1;
+        if pair.is_empty() {
+            continue;
+        }
+        let plus = |s: &str| s.replace('+', " ");
+        let name = decode(&plus(name)).map_err(|(sequence, at)| (sequence, name_offset + at))?;
+        let value = decode(&plus(value)).map_err(|(sequence, at)| (sequence, value_offset + at))?;
+        pairs.push((name, value));
+    }
+    Ok(pairs)
+}
+
+/// Strictly decodes the rest of the path and the query, 400 with the bad sequence when malformed.
+pub async fn decode_test(pretty: Pretty, uri: Uri) -> Response {
+    let path = uri.path().strip_prefix(PREFIX).unwrap_or(uri.path()).trim_start_matches('/');
+    let decoded = match decode(path) {
+        Ok(decoded) => decoded,
+        Err(e) => return bad_request("path", e),
+    };
+    let query = match decode_pairs(uri.query().unwrap_or_default()) {
+        Ok(query) => query,
+        Err(e) => return bad_request("query", e),
+    };
+
+    pretty
+        .json(Decoded {
+            path: path.to_string(),
+            decoded,
+            query,
+        })
+        .into_response()
+}
+
+#[derive(Serialize)]
+struct Form {
+    /// every pair in body order, repeated names included
+    pairs: Vec<(String, String)>,
+    form: BTreeMap<String, Vec<String>>,
+    /// the pairs encoded again as browsers do, with `+` for spaces and `%XX` for everything but `*-._`
+    reencoded: String,
+    /// whether `reencoded` is the body byte for byte
+    roundtrip: bool,
+}
+
+/// Strictly decodes an `application/x-www-form-urlencoded` body and encodes it again, to check a client's form
+/// encoder against the WHATWG serializer. Malformed escapes and invalid UTF-8 are answered 400 with their offset
+/// in the body, other content types 415.
+pub async fn form(pretty: Pretty, header_map: HeaderMap, body: Bytes) -> Response {
+    let form_urlencoded = header_map
+        .get(CONTENT_TYPE)
+        .and_then(|it| it.to_str().ok())
+        .and_then(|it| it.parse::<mime::Mime>().ok())
+        .is_some_and(|it| it.essence_str() == mime::APPLICATION_WWW_FORM_URLENCODED.essence_str());
+    if !form_urlencoded {
+        return (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErasedJson::pretty(ErrorDetail::new(
+                415,
+                "Unsupported Media Type",
+                "expected an `application/x-www-form-urlencoded` body",
+            )),
+        )
+            .into_response();
+    }
+    let body = match std::str::from_utf8(&body) {
+        Ok(body) => body,
+        Err(e) => {
+            let at = e.valid_up_to();
+            let end = e.error_len().map_or(body.len(), |len| at + len);
+            let sequence = body[at..end].iter().map(|byte| format!("%{byte:02X}")).collect();
+            return bad_request("body", (sequence, at));
+        }
+    };
+    let pairs = match decode_pairs(body) {
+        Ok(pairs) => pairs,
+        Err(e) => return bad_request("body", e),
+    };
+
+    let mut form = BTreeMap::<String, Vec<String>>::new();
+    for (name, value) in &pairs {
+        form.entry(name.clone()).or_default().push(value.clone());
+    }
+    let reencoded = form_urlencoded::Serializer::new(String::new()).extend_pairs(&pairs).finish();
+    pretty
+        .json(Form {
+            roundtrip: reencoded == body,
+            pairs,
+            form,
+            reencoded,
+        })
+        .into_response()
+}
@@ -0,0 +1,117 @@
+use std::io::Read;
+
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, StatusCode, header::CONTENT_ENCODING},
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+
+use crate::data::ErrorDetail;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    Gzip,
+    /// `deflate` as specified, a zlib stream
+    Zlib,
+    /// `deflate` as some clients send it, without the zlib header
+    Deflate,
+    Br,
+}
+
+/// How a compressed request body was decoded.
+#[derive(Serialize, Debug)]
+pub struct Decoded {
+    /// the `Content-Encoding` of the request
+    pub declared: String,
+    /// what the body turned out to be, regardless of the label
+    pub detected: Codec,
+    /// size of the body before decoding
+    pub encoded_bytes: usize,
+}
+
+enum Error {
+    TooLarge,
+    Invalid,
+}
+
+fn decode_as(codec: Codec, body: &[u8], limit: usize) -> Result<Vec<u8>, Error> {
+    let reader: Box<dyn Read + '_> = match codec {
+        Codec::Gzip => Box::new(flate2::read::MultiGzDecoder::new(body)),
+        Codec::Zlib => Box::new(flate2::read::ZlibDecoder::new(body)),
+        Codec::Deflate => Box::new(flate2::read::DeflateDecoder::new(body)),
+        Codec::Br => Box::new(brotli::Decompressor::new(body, 4096)),
+    };
+    let mut decoded = Vec::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|_| Error::Invalid)?;
+    if decoded.len() > limit {
+        return Err(Error::TooLarge);
+    }
+    Ok(decoded)
+}
+
+/// The codecs to try, those the body announces with its header first. Brotli has no magic number and goes
+/// before raw deflate, which accepts more malformed input.
+fn candidates(body: &[u8]) -> Vec<Codec> {
+    let mut candidates = Vec::new();
+    match body {
+        [0x1f, 0x8b, ..] => candidates.push(Codec::Gzip),
+        [cmf, flg, ..] if cmf & 0x0f == 8 && ((u16::from(*cmf) << 8) | u16::from(*flg)) % 31 == 0 => candidates.push(Codec::Zlib),
+        _ => {}
+    }
+    for codec in [Codec::Gzip, Codec::Zlib, Codec::Br, Codec::Deflate] {
+        if !candidates.contains(&codec) {
+            candidates.push(codec);
+        }
+    }
+    candidates
+}
+
+/// Decodes a body labelled `gzip`, `x-gzip`, `deflate` or `br` with whichever of these codecs it is actually
+/// compressed with, up to `limit` decoded bytes. Other and stacked content codings are left as they are.
+pub fn decode(header_map: &HeaderMap, body: Bytes, limit: usize) -> Result<(Bytes, Option<Decoded>), Response> {
+    let Some(declared) = header_map
+        .get(CONTENT_ENCODING)
+        .and_then(|it| it.to_str().ok())
+        .map(|it| it.trim().to_ascii_lowercase())
+        .filter(|it| matches!(it.as_str(), "gzip" | "x-gzip" | "deflate" | "br"))
+    else {
+        return Ok((body, None));
+    };
+    if body.is_empty() {
+        return Ok((body, None));
+    }
+
+    for detected in candidates(&body) {
+        match decode_as(detected, &body, limit) {
+            Ok(decoded) => {
+                let decoded_by = Decoded {
+                    declared,
+                    detected,
+                    encoded_bytes: body.len(),
+                };
+                return Ok((decoded.into(), Some(decoded_by)));
+            }
+            Err(Error::TooLarge) => {
+                let detail = format!("the body decodes to more than {limit} bytes");
+                return Err((
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    ErasedJson::pretty(ErrorDetail::new(413, "Payload Too Large", detail)),
+                )
+                    .into_response());
+            }
+            Err(Error::Invalid) => {}
+        }
+    }
+    let detail = format!("the body is labelled `{declared}` but is not gzip, zlib, deflate or brotli compressed");
+    Err((
+        StatusCode::BAD_REQUEST,
+        ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", detail)),
+    )
+        .into_response())
+}
@@ -0,0 +1,78 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::{
+        HeaderMap, HeaderValue, Method, StatusCode,
+        header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+
+use crate::data::ErrorDetail;
+
+/// Strong entity tag for a body: the quoted 64-bit FNV-1a hash, so it is stable across restarts and builds.
+pub fn etag_for(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("\"{hash:016x}\"")
+}
+
+/// `If-None-Match` uses the weak comparison, so `W/"x"` matches `"x"`.
+pub fn none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// `If-Match` uses the strong comparison, a weak `W/"x"` never matches.
+pub fn if_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// Tags successful responses with [`etag_for`] their body and answers a matching `If-None-Match` with 304 for
+/// GET and HEAD, 412 for other methods, as RFC 9110 section 13.1.2 asks.
+pub async fn etag(request: Request, next: Next) -> Response {
+    let safe = request.method() == Method::GET || request.method() == Method::HEAD;
+    let request_headers = request.headers().clone();
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK || response.headers().contains_key(ETAG) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let etag = etag_for(&bytes);
+    parts
+        .headers
+        .insert(ETAG, HeaderValue::from_str(&etag).expect("hex is a valid header value"));
+
+    match none_match(&request_headers, &etag) {
+        true if safe => {
+            parts.status = StatusCode::NOT_MODIFIED;
+            parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::empty())
+        }
+        true => {
+            let error = ErrorDetail::new(412, "Precondition Failed", "the `if-none-match` precondition failed");
+            let etag = [(ETAG, parts.headers[ETAG].clone())];
+            (StatusCode::PRECONDITION_FAILED, etag, ErasedJson::pretty(error)).into_response()
+        }
+        false => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
@@ -0,0 +1,104 @@
+use std::{error::Error, sync::Arc};
+
+use axum::{
+    Extension,
+    body::Body,
+    extract::Request,
+    http::{
+        HeaderMap, StatusCode,
+        header::{CONNECTION, EXPECT},
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use axum_valid::Garde;
+use futures_util::{FutureExt as _, StreamExt as _};
+use garde::Validate;
+use http_body_util::{LengthLimitError, Limited};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, data::ErrorDetail};
+
+#[derive(Serialize)]
+struct ExpectContinue {
+    expect: Option<String>,
+    /// whether the body was only sent after the interim `100 Continue`
+    waited_for_continue: Option<bool>,
+    bytes: usize,
+}
+
+/// 413 when the body passed `--max-body-bytes`, 400 when reading it failed otherwise, e.g. the client went away.
+fn body_error(e: axum::Error, max_body_bytes: usize) -> Response {
+    let e = e.into_inner();
+    let too_large = std::iter::successors(Some(&*e as &dyn Error), |it| it.source()).any(|it| it.is::<LengthLimitError>());
+    let (status, detail) = match too_large {
+        true => (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("request body is larger than {max_body_bytes} bytes"),
+        ),
+        false => (StatusCode::BAD_REQUEST, format!("failed to read the request body: {e}")),
+    };
+    ErrorDetail::response(status, detail)
+}
+
+/// hyper writes the interim `100 Continue` the first time a handler reads the body of a request with
+/// `Expect: 100-continue`, so the body of a client that waits is never ready before that first read.
+pub async fn expect_100(Extension(config): Extension<Arc<Config>>, request: Request) -> Response {
+    let expect = request
+        .headers()
+        .get(EXPECT)
+        .map(|it| String::from_utf8_lossy(it.as_bytes()).to_string());
+    let expects_continue = expect.as_deref().is_some_and(|it| it.eq_ignore_ascii_case("100-continue"));
+
+    let mut body = Body::new(Limited::new(request.into_body(), config.max_body_bytes)).into_data_stream();
+    let (first, waited) = match body.next().now_or_never() {
+        Some(first) => (first, false),
+        None => (body.next().await, true),
+    };
+
+    let mut bytes = 0;
+    let mut chunk = first;
+    while let Some(data) = chunk {
+        match data {
+            Ok(data) => bytes += data.len(),
+            Err(e) => return body_error(e, config.max_body_bytes),
+        }
+        chunk = body.next().await;
+    }
+
+    ErasedJson::pretty(ExpectContinue {
+        expect,
+        waited_for_continue: expects_continue.then_some(waited),
+        bytes,
+    })
+    .into_response()
+}
+
+#[derive(Deserialize, Validate, Debug)]
+pub struct RejectParam {
+    #[garde(range(min = 400, max = 599))]
+    status: Option<u16>,
+}
+
+#[derive(Serialize)]
+struct Rejected {
+    expect: Option<String>,
+    status: u16,
+}
+
+/// Answers with the final `status` (417 Expectation Failed by default) without reading the body, so a
+/// client sending `Expect: 100-continue` never gets the interim `100 Continue` and should not send its body.
+/// The connection is closed afterwards, as the unread body may still be on its way.
+pub async fn reject_upload(header_map: HeaderMap, Garde(Query(p)): Garde<Query<RejectParam>>) -> Response {
+    let status = StatusCode::from_u16(p.status.unwrap_or(417)).unwrap_or(StatusCode::EXPECTATION_FAILED);
+    let expect = header_map.get(EXPECT).map(|it| String::from_utf8_lossy(it.as_bytes()).to_string());
+    (
+        status,
+        [(CONNECTION, "close")],
+        ErasedJson::pretty(Rejected {
+            expect,
+            status: status.as_u16(),
+        }),
+    )
+        .into_response()
+}
@@ -0,0 +1,83 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::Path,
+    http::{StatusCode, header::RETRY_AFTER},
+    response::{IntoResponse, Response},
+};
+use axum_client_ip::InsecureClientIp;
+use axum_extra::{extract::Query, response::ErasedJson};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::data::ErrorDetail;
+
+/// A key that has not been seen for this long starts failing again.
+pub const TTL: Duration = Duration::from_secs(60);
+/// Keys tracked at once, a request with another key is answered 429 until some expire.
+pub const CAPACITY: usize = 10_000;
+
+static STATE: LazyLock<Arc<Mutex<HashMap<String, Attempts>>>> = LazyLock::new(Default::default);
+
+struct Attempts {
+    count: u32,
+    last_seen: Instant,
+}
+
+#[derive(Deserialize)]
+pub struct FlakyParam {
+    /// tracks attempts per key instead of per client IP
+    pub key: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Flaky {
+    key: String,
+    attempt: u32,
+    failures: u32,
+    healthy: bool,
+}
+
+/// Fails the first `failures` requests of a client with 503, then succeeds until the key expires.
+pub async fn flaky(
+    Path(failures): Path<u32>,
+    Query(FlakyParam { key }): Query<FlakyParam>,
+    InsecureClientIp(origin): InsecureClientIp,
+) -> Response {
+    // the failure count is part of the key, so `/flaky/2` and `/flaky/5` do not share attempts
+    let key = format!("{failures}:{}", key.unwrap_or_else(|| origin.to_string()));
+    let now = Instant::now();
+
+    let mut state = STATE.lock().await;
+    state.retain(|_, attempts| now.duration_since(attempts.last_seen) < TTL);
+    if state.len() >= CAPACITY && !state.contains_key(&key) {
+        let detail = format!("already tracking {CAPACITY} keys, retry once some expire");
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, TTL.as_secs().to_string())],
+            ErasedJson::pretty(ErrorDetail::new(429, "Too Many Requests", detail)),
+        )
+            .into_response();
+    }
+    let attempts = state.entry(key.clone()).or_insert(Attempts { count: 0, last_seen: now });
+    attempts.count = attempts.count.saturating_add(1);
+    attempts.last_seen = now;
+
+    let attempt = attempts.count;
+    let healthy = attempt > failures;
+    let body = ErasedJson::pretty(Flaky {
+        key,
+        attempt,
+        failures,
+        healthy,
+    });
+    if healthy {
+        body.into_response()
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, body).into_response()
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension,
+    http::{
+        HeaderName, HeaderValue, StatusCode, Version,
+        header::{CONNECTION, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use axum_valid::Garde;
+use garde::Validate;
+use serde::Deserialize;
+
+use crate::{config::Config, data::ErrorDetail, listener, negotiate};
+
+/// The body sent under a malformed head, its real size is in `X-Body-Bytes`.
+const BODY: &[u8] = b"this body is framed by hyper, whatever the head on the wire says\n";
+
+#[derive(Deserialize)]
+pub struct ContentLengthParam {
+    /// `-1` by default, any visible ASCII, spaces and tabs included
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct HeaderSizeParam {
+    /// the size of the `X-Fuzz-Padding` value, 64KiB by default
+    #[garde(range(min = 1, max = 8 * 1024 * 1024))]
+    pub bytes: Option<usize>,
+}
+
+/// The fuzz routes pretend not to exist without `--fuzz`, like the admin routes without a token.
+fn enabled(config: &Config) -> Result<(), Response> {
+    match config.fuzz {
+        true => Ok(()),
+        false => Err((StatusCode::NOT_FOUND, ErasedJson::pretty(ErrorDetail::new(404, "Not Found", ""))).into_response()),
+    }
+}
+
+/// Sends a response whose `Content-Length` is `value`, a negative, overflowing or non numeric one included. hyper
+/// refuses to write such a header, so a valid one is rewritten on its way to the socket like
+/// `/content-length-mismatch` does, which only an HTTP/1 connection over TCP allows; `X-Misframed` says whether it
+/// was. CR, LF and other control characters are answered 400, they would split the head rather than misframe it.
+pub async fn content_length(
+    Extension(config): Extension<Arc<Config>>,
+    version: Version,
+    misframed_length: Option<Extension<listener::MisframedLength>>,
+    Query(p): Query<ContentLengthParam>,
+) -> Response {
+    if let Err(response) = enabled(&config) {
+        return response;
+    }
+    let value = p.value.unwrap_or_else(|| "-1".to_string());
+    if value.is_empty() || value.len() > 64 || !value.bytes().all(|byte| byte == b'\t' || (b' '..=b'~').contains(&byte)) {
+        return ErrorDetail::response(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "invalid value `{}`, expected 1 to 64 visible ASCII characters",
+                value.escape_debug()
+            ),
+        );
+    }
+    let misframed_length = misframed_length.filter(|_| matches!(version, Version::HTTP_10 | Version::HTTP_11));
+    if let Some(Extension(misframed_length)) = &misframed_length {
+        misframed_length.enable(&value);
+    }
+    let mut response = (
+        [
+            // the client cannot know where this response ends, so neither can the next one start
+            (CONNECTION, HeaderValue::from_static("close")),
+            (CONTENT_TYPE, HeaderValue::from_static("text/plain")),
+            (HeaderName::from_static("x-body-bytes"), BODY.len().into()),
+            (
+                HeaderName::from_static("x-misframed"),
+                HeaderValue::from_static(if misframed_length.is_some() { "true" } else { "false" }),
+            ),
+        ],
+        BODY,
+    )
+        .into_response();
+    // a compressed body would be chunked, without a `Content-Length` to rewrite
+    response.extensions_mut().insert(negotiate::Negotiated);
+    response
+}
+
+/// Sends an `X-Fuzz-Padding` header of `bytes` bytes, to find the size past which a client gives up on a
+/// response head, and whether it does so cleanly.
+pub async fn header_size(Extension(config): Extension<Arc<Config>>, Garde(Query(p)): Garde<Query<HeaderSizeParam>>) -> Response {
+    if let Err(response) = enabled(&config) {
+        return response;
+    }
+    let bytes = p.bytes.unwrap_or(64 * 1024);
+    let padding: String = ('a'..='z').cycle().take(bytes).collect();
+    (
+        [
+            (CONTENT_TYPE, HeaderValue::from_static("text/plain")),
+            (
+                HeaderName::from_static("x-fuzz-padding"),
+                HeaderValue::from_str(&padding).expect("letters"),
+            ),
+        ],
+        BODY,
+    )
+        .into_response()
+}
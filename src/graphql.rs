@@ -0,0 +1,51 @@
+use std::{net::IpAddr, sync::LazyLock};
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+use async_graphql_axum::{GraphQLRejection, GraphQLRequest, GraphQLResponse};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_client_ip::InsecureClientIp;
+use axum_extra::response::ErasedJson;
+use serde_json::json;
+
+pub struct Query;
+
+/// The whole schema:
+///
+/// ```graphql
+/// type Query {
+///   echo(message: String): String
+///   ip: String!
+/// }
+/// ```
+#[Object]
+impl Query {
+    /// `message` as it was sent, `null` without it.
+    async fn echo(&self, message: Option<String>) -> Option<String> {
+        message
+    }
+
+    /// The client address, as `/ip` answers it.
+    async fn ip(&self, ctx: &Context<'_>) -> String {
+        ctx.data_unchecked::<IpAddr>().to_string()
+    }
+}
+
+static SCHEMA: LazyLock<Schema<Query, EmptyMutation, EmptySubscription>> =
+    LazyLock::new(|| Schema::new(Query, EmptyMutation, EmptySubscription));
+
+/// `GET /graphql?query=&variables=&operationName=` and `POST /graphql` with an `application/json` or
+/// `application/graphql` body. Syntax, validation and field errors are answered in the `errors` of the response
+/// envelope; a request that cannot be read at all gets a 400 with an `errors` envelope too.
+pub async fn graphql(InsecureClientIp(origin): InsecureClientIp, request: Result<GraphQLRequest, GraphQLRejection>) -> Response {
+    match request {
+        Ok(request) => GraphQLResponse::from(SCHEMA.execute(request.into_inner().data(origin)).await).into_response(),
+        Err(GraphQLRejection(e)) => (
+            StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(json!({"errors": [{"message": e.to_string()}]})),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,50 @@
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status};
+
+use crate::admin::Shutdown;
+
+include!(concat!(env!("OUT_DIR"), "/httpbin.Echo.rs"));
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EchoRequest {
+    #[prost(string, tag = "1")]
+    pub message: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EchoResponse {
+    #[prost(string, tag = "1")]
+    pub message: String,
+    #[prost(string, tag = "2")]
+    pub peer: String,
+}
+
+#[derive(Default)]
+pub struct EchoService;
+
+#[tonic::async_trait]
+impl echo_server::Echo for EchoService {
+    async fn echo(&self, request: Request<EchoRequest>) -> Result<Response<EchoResponse>, Status> {
+        let peer = request.remote_addr().map(|addr| addr.to_string()).unwrap_or_default();
+        Ok(Response::new(EchoResponse {
+            message: request.into_inner().message,
+            peer,
+        }))
+    }
+}
+
+/// Serves `httpbin.Echo/Echo` and the standard `grpc.health.v1.Health` service until shutdown.
+pub async fn serve(listener: TcpListener, shutdown: Shutdown) {
+    let (mut reporter, health) = tonic_health::server::health_reporter();
+    reporter.set_serving::<echo_server::EchoServer<EchoService>>().await;
+
+    let result = tonic::transport::Server::builder()
+        .add_service(health)
+        .add_service(echo_server::EchoServer::new(EchoService))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async move { shutdown.wait().await })
+        .await;
+    if let Err(e) = result {
+        tracing::error!("gRPC server stopped: {e}");
+    }
+}
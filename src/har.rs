@@ -0,0 +1,181 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, LazyLock},
+    time::Instant,
+};
+
+use axum::{
+    Extension,
+    body::Body,
+    extract::Request,
+    http::{
+        HeaderMap, StatusCode, Version,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE, HOST, LOCATION},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio::sync::Mutex;
+
+use crate::{admin, config::Config, data::ErrorDetail};
+
+static ENTRIES: LazyLock<Arc<Mutex<VecDeque<Value>>>> = LazyLock::new(|| Arc::new(Mutex::new(VecDeque::new())));
+
+#[derive(Serialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+fn headers(header_map: &HeaderMap) -> Vec<NameValue> {
+    header_map
+        .iter()
+        .map(|(name, value)| NameValue {
+            name: name.to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        })
+        .collect()
+}
+
+fn http_version(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_11 => "HTTP/1.1",
+        Version::HTTP_2 => "HTTP/2.0",
+        Version::HTTP_3 => "HTTP/3.0",
+        _ => "unknown",
+    }
+}
+
+/// Records every request outside of `/admin` as a HAR 1.2 entry, keeping the last `--har-capacity`.
+///
+/// The request body is buffered to be recorded, up to `--max-body-bytes`, so recording answers 413
+/// to larger bodies and turns the streaming endpoints into buffered ones.
+pub async fn record(Extension(config): Extension<Arc<Config>>, request: Request, next: Next) -> Response {
+    if config.har_capacity == 0 || request.uri().path().starts_with("/admin") {
+        return next.run(request).await;
+    }
+
+    let started = jiff::Timestamp::now();
+    let start = Instant::now();
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, config.max_body_bytes).await {
+        Ok(body) => body,
+        Err(e) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErasedJson::pretty(ErrorDetail::new(413, "Payload Too Large", e.to_string())),
+            )
+                .into_response();
+        }
+    };
+
+    let host = parts.headers.get(HOST).and_then(|it| it.to_str().ok()).unwrap_or("localhost");
+    let url = format!("http://{host}{}", parts.uri);
+    let query: Vec<_> = form_urlencoded::parse(parts.uri.query().unwrap_or_default().as_bytes())
+        .map(|(name, value)| NameValue {
+            name: name.into_owned(),
+            value: value.into_owned(),
+        })
+        .collect();
+    let mime_type = parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|it| it.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    // HAR only defines an encoding for response content, binary request bodies get a custom `_encoding`
+    let post_data = (!body.is_empty()).then(|| match std::str::from_utf8(&body) {
+        Ok(text) => json!({"mimeType": mime_type, "text": text}),
+        Err(_) => json!({"mimeType": mime_type, "text": BASE64_STANDARD.encode(&body), "_encoding": "base64"}),
+    });
+    let mut request_entry = json!({
+        "method": parts.method.as_str(),
+        "url": url,
+        "httpVersion": http_version(parts.version),
+        "cookies": [],
+        "headers": headers(&parts.headers),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": body.len(),
+    });
+    if let Some(post_data) = post_data {
+        request_entry["postData"] = post_data;
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    let time = start.elapsed().as_secs_f64() * 1000.0;
+    let status = response.status();
+    let response_entry = json!({
+        "status": status.as_u16(),
+        "statusText": status.canonical_reason().unwrap_or_default(),
+        "httpVersion": http_version(response.version()),
+        "cookies": [],
+        "headers": headers(response.headers()),
+        "content": {
+            "size": -1,
+            "mimeType": response.headers().get(CONTENT_TYPE).and_then(|it| it.to_str().ok()).unwrap_or_default(),
+        },
+        "redirectURL": response.headers().get(LOCATION).and_then(|it| it.to_str().ok()).unwrap_or_default(),
+        "headersSize": -1,
+        "bodySize": -1,
+    });
+
+    let mut entries = ENTRIES.lock().await;
+    while entries.len() >= config.har_capacity {
+        entries.pop_front();
+    }
+    entries.push_back(json!({
+        "startedDateTime": started,
+        "time": time,
+        "request": request_entry,
+        "response": response_entry,
+        "cache": {},
+        // the response body is still streaming, the time covers the request until its response head
+        "timings": {"send": 0, "wait": time, "receive": 0},
+    }));
+    drop(entries);
+
+    response
+}
+
+/// The recorded requests as a HAR 1.2 document.
+pub async fn har(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap) -> Response {
+    if let Err(response) = admin::authorize(&config, &header_map) {
+        return response;
+    }
+    let entries = ENTRIES.lock().await;
+    let har = json!({
+        "log": {
+            "version": "1.2",
+            "creator": {"name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION")},
+            "entries": entries.iter().collect::<Vec<_>>(),
+        }
+    });
+    (
+        [(CONTENT_DISPOSITION, "attachment; filename=\"rs-httpbin.har\"")],
+        ErasedJson::pretty(har),
+    )
+        .into_response()
+}
+
+#[derive(Serialize)]
+struct Cleared {
+    cleared: usize,
+}
+
+pub async fn clear(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap) -> Response {
+    if let Err(response) = admin::authorize(&config, &header_map) {
+        return response;
+    }
+    let mut entries = ENTRIES.lock().await;
+    let cleared = entries.len();
+    entries.clear();
+    ErasedJson::pretty(Cleared { cleared }).into_response()
+}
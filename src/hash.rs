@@ -0,0 +1,191 @@
+use axum::{
+    extract::{Path, Query, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use futures_util::StreamExt as _;
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+
+use crate::data::ErrorDetail;
+
+pub const ALGORITHMS: &[&str] = &["md5", "sha1", "sha256", "sha512", "blake3"];
+pub const CHECKSUMS: &[&str] = &["crc32", "crc32c", "adler32"];
+
+/// CRC-32/ISO-HDLC as used by zip, gzip and PNG: polynomial 0x04C11DB7 (reflected 0xEDB88320).
+static CRC32: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+/// CRC-32C/Castagnoli as used by iSCSI, ext4 and cloud object stores: polynomial 0x1EDC6F41 (reflected 0x82F63B78).
+static CRC32C: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
+
+enum Hasher {
+    Md5(md5::Md5),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    Sha512(sha2::Sha512),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(algorithm: &str) -> Option<Self> {
+        Some(match algorithm {
+            "md5" => Hasher::Md5(md5::Md5::new()),
+            "sha1" => Hasher::Sha1(sha1::Sha1::new()),
+            "sha256" => Hasher::Sha256(sha2::Sha256::new()),
+            "sha512" => Hasher::Sha512(sha2::Sha512::new()),
+            "blake3" => Hasher::Blake3(Box::default()),
+            _ => return None,
+        })
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(hasher) => hasher.update(data),
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha512(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    fn hex(self) -> String {
+        match self {
+            Hasher::Md5(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Sha1(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Sha512(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Hashed {
+    algorithm: String,
+    bytes: usize,
+    digest: String,
+}
+
+/// Hashes the request body frame by frame as it arrives, without buffering it.
+pub async fn hash(Path(algorithm): Path<String>, request: Request) -> Response {
+    let Some(mut hasher) = Hasher::new(&algorithm) else {
+        let detail = format!("unknown algorithm `{algorithm}`, expected one of {}", ALGORITHMS.join(", "));
+        return (
+            StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", detail)),
+        )
+            .into_response();
+    };
+
+    let mut body = request.into_body().into_data_stream();
+    let mut bytes = 0;
+    while let Some(frame) = body.next().await {
+        match frame {
+            Ok(frame) => {
+                bytes += frame.len();
+                hasher.update(&frame);
+            }
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", e.to_string())),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    ErasedJson::pretty(Hashed {
+        algorithm,
+        bytes,
+        digest: hasher.hex(),
+    })
+    .into_response()
+}
+
+enum Checksum {
+    Crc32(crc::Digest<'static, u32>),
+    Crc32c(crc::Digest<'static, u32>),
+    /// Adler-32 from zlib (RFC 1950), two sums modulo 65521 rather than a polynomial.
+    Adler32(adler2::Adler32),
+}
+
+impl Checksum {
+    fn new(algorithm: &str) -> Option<Self> {
+        Some(match algorithm {
+            "crc32" => Checksum::Crc32(CRC32.digest()),
+            "crc32c" => Checksum::Crc32c(CRC32C.digest()),
+            "adler32" => Checksum::Adler32(adler2::Adler32::new()),
+            _ => return None,
+        })
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Checksum::Crc32(digest) | Checksum::Crc32c(digest) => digest.update(data),
+            Checksum::Adler32(adler) => adler.write_slice(data),
+        }
+    }
+
+    fn value(self) -> u32 {
+        match self {
+            Checksum::Crc32(digest) | Checksum::Crc32c(digest) => digest.finalize(),
+            Checksum::Adler32(adler) => adler.checksum(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ChecksumParam {
+    pub algo: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Checksummed {
+    algorithm: String,
+    bytes: usize,
+    hex: String,
+    decimal: u32,
+}
+
+/// Checksums the request body frame by frame, `?algo=` defaults to `crc32`.
+pub async fn checksum(Query(param): Query<ChecksumParam>, request: Request) -> Response {
+    let algorithm = param.algo.unwrap_or_else(|| "crc32".to_string());
+    let Some(mut checksum) = Checksum::new(&algorithm) else {
+        let detail = format!("unknown algorithm `{algorithm}`, expected one of {}", CHECKSUMS.join(", "));
+        return (
+            StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", detail)),
+        )
+            .into_response();
+    };
+
+    let mut body = request.into_body().into_data_stream();
+    let mut bytes = 0;
+    while let Some(frame) = body.next().await {
+        match frame {
+            Ok(frame) => {
+                bytes += frame.len();
+                checksum.update(&frame);
+            }
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", e.to_string())),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    let value = checksum.value();
+    ErasedJson::pretty(Checksummed {
+        algorithm,
+        bytes,
+        hex: format!("{value:08x}"),
+        decimal: value,
+    })
+    .into_response()
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Extension,
+    http::{HeaderMap, Version},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::{pretty::Pretty, serve::ConnectionReuse};
+
+/// Past this size a request head is not recorded, and neither is anything after it on the connection.
+pub const MAX_HEAD_BYTES: usize = 64 * 1024;
+/// Heads kept per connection, enough for the requests pipelined ahead of the one being handled.
+const KEPT: usize = 8;
+
+/// The request heads of an HTTP/1 connection as read from the socket, before hyper parses them into a
+/// `HeaderMap` that groups headers by lowercased name. Bodies are skipped by their `Content-Length` or chunked
+/// framing to find the next head. HTTP/2 connections are not recorded.
+#[derive(Clone, Default)]
+pub struct RawHeads(Arc<Mutex<Recorder>>);
+
+#[derive(Default)]
+struct Recorder {
+    state: State,
+    line: Vec<u8>,
+    head: Vec<(String, String)>,
+    head_bytes: usize,
+    /// heads read so far, the first request is `1` like in [`ConnectionReuse`]
+    count: u64,
+    heads: VecDeque<(u64, Vec<(String, String)>)>,
+}
+
+#[derive(Default, Clone, Copy)]
+enum State {
+    #[default]
+    RequestLine,
+    Headers,
+    Body(u64),
+    ChunkSize,
+    ChunkData(u64),
+    ChunkEnd,
+    Trailers,
+    Off,
+}
+
+impl RawHeads {
+    pub fn observe(&self, bytes: &[u8]) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).observe(bytes);
+    }
+
+    fn get(&self, request: u64) -> Option<Vec<(String, String)>> {
+        let recorder = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        recorder
+            .heads
+            .iter()
+            .find(|(count, _)| *count == request)
+            .map(|(_, head)| head.clone())
+    }
+}
+
+impl Recorder {
+    fn observe(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            match self.state {
+                State::Off => return,
+                State::Body(left) | State::ChunkData(left) => {
+                    let skipped = left.min(bytes.len() as u64);
+                    bytes = &bytes[skipped as usize..];
+                    self.state = match (self.state, left - skipped) {
+                        (State::Body(_), 0) => State::RequestLine,
+                        (State::Body(_), left) => State::Body(left),
+                        (_, 0) => State::ChunkEnd,
+                        (_, left) => State::ChunkData(left),
+                    };
+                }
+                _ => {
+                    let end = bytes.iter().position(|byte| *byte == b'\n').map(|end| end + 1);
+                    let (line, rest) = bytes.split_at(end.unwrap_or(bytes.len()));
+                    self.line.extend_from_slice(line);
+                    bytes = rest;
+                    if self.head_bytes + self.line.len() > MAX_HEAD_BYTES {
+                        self.state = State::Off;
+                    } else if end.is_some() {
+                        let line = std::mem::take(&mut self.line);
+                        let line = line.strip_suffix(b"\n").unwrap_or(&line);
+                        self.on_line(line.strip_suffix(b"\r").unwrap_or(line));
+                    }
+                }
+            }
+        }
+    }
+
+    fn on_line(&mut self, line: &[u8]) {
+        self.state = match self.state {
+            // hyper skips empty lines before a request
+            State::RequestLine if line.is_empty() => State::RequestLine,
+            State::RequestLine if line.starts_with(b"PRI * HTTP/2.0") => State::Off,
+            State::RequestLine => {
+                self.head_bytes = line.len();
+                State::Headers
+            }
+            State::Headers if line.is_empty() => self.end_head(),
+            State::Headers => {
+                self.head_bytes += line.len() + 2;
+                let line = String::from_utf8_lossy(line);
+                let (name, value) = line.split_once(':').unwrap_or((&line, ""));
+                self.head.push((name.to_string(), value.trim_matches([' ', '\t']).to_string()));
+                State::Headers
+            }
+            State::ChunkSize => {
+                let size = String::from_utf8_lossy(line);
+                let size = size.split(';').next().unwrap_or_default().trim();
+                match u64::from_str_radix(size, 16) {
+                    Ok(0) => State::Trailers,
+                    Ok(size) => State::ChunkData(size),
+                    Err(_) => State::Off,
+                }
+            }
+            State::ChunkEnd if line.is_empty() => State::ChunkSize,
+            State::Trailers if line.is_empty() => State::RequestLine,
+            State::Trailers => State::Trailers,
+            _ => State::Off,
+        };
+    }
+
+    /// Keeps the head and finds how its body is framed.
+    fn end_head(&mut self) -> State {
+        let head = std::mem::take(&mut self.head);
+        self.head_bytes = 0;
+        let value = |name: &str| {
+            head.iter()
+                .filter(|(it, _)| it.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+                .last()
+        };
+        let state = if value("transfer-encoding").is_some_and(|it| it.to_ascii_lowercase().ends_with("chunked")) {
+            State::ChunkSize
+        } else {
+            match value("content-length").map(str::parse) {
+                None => State::Body(0),
+                Some(Ok(length)) => State::Body(length),
+                Some(Err(_)) => State::Off,
+            }
+        };
+
+        self.count += 1;
+        if self.heads.len() == KEPT {
+            self.heads.pop_front();
+        }
+        self.heads.push_back((self.count, head));
+        state
+    }
+}
+
+#[derive(Serialize)]
+struct HeaderOrder {
+    protocol: String,
+    /// `false` when the order and casing below are those of the `HeaderMap`, not of the wire
+    preserved: bool,
+    headers: Vec<(String, String)>,
+}
+
+/// The request headers as `[name, value]` pairs, in the order and casing they were sent in when the connection
+/// recorded them, and whether it did.
+pub fn ordered(
+    version: Version,
+    header_map: &HeaderMap,
+    raw_heads: Option<Extension<RawHeads>>,
+    reuse: Option<Extension<ConnectionReuse>>,
+) -> (bool, Vec<(String, String)>) {
+    let raw = match (raw_heads, reuse) {
+        (Some(Extension(raw_heads)), Some(Extension(reuse))) if matches!(version, Version::HTTP_10 | Version::HTTP_11) => {
+            raw_heads.get(reuse.request)
+        }
+        _ => None,
+    };
+    let preserved = raw.is_some();
+    let headers = raw.unwrap_or_else(|| {
+        header_map
+            .iter()
+            .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+            .collect()
+    });
+    (preserved, headers)
+}
+
+/// Reflects the request headers as `[name, value]` pairs in the order and casing they were sent in. Only
+/// HTTP/1 over TCP keeps them: HTTP/2 and HTTP/3 send lowercase names through HPACK or QPACK, and there the
+/// headers come in `HeaderMap` order, grouped by name.
+pub async fn header_order(
+    pretty: Pretty,
+    version: Version,
+    header_map: HeaderMap,
+    raw_heads: Option<Extension<RawHeads>>,
+    reuse: Option<Extension<ConnectionReuse>>,
+) -> Response {
+    let (preserved, headers) = ordered(version, &header_map, raw_heads, reuse);
+    pretty
+        .json(HeaderOrder {
+            protocol: format!("{version:?}"),
+            preserved,
+            headers,
+        })
+        .into_response()
+}
+
+#[derive(Serialize)]
+struct Canonicalized {
+    protocol: String,
+    /// `false` when the raw names below are already those of the `HeaderMap`, not of the wire
+    preserved: bool,
+    headers: Vec<CanonicalName>,
+    /// the raw names that do not survive normalization, in the order they were sent
+    changed: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct CanonicalName {
+    raw: String,
+    /// as hyper, HTTP/2 and HTTP/3 carry it
+    lowercase: String,
+    /// the `Content-Type` form of Go's `CanonicalMIMEHeaderKey` and many HTTP/1 stacks
+    canonical: String,
+    /// whether `raw` differs from `lowercase`, the casing is lost to anything going through a `HeaderMap`
+    changed: bool,
+}
+
+/// Every letter lowercase but the first one and those after a `-`.
+fn canonical(name: &str) -> String {
+    let mut upper = true;
+    name.chars()
+        .map(|c| {
+            let c = if upper { c.to_ascii_uppercase() } else { c.to_ascii_lowercase() };
+            upper = c == '-';
+            c
+        })
+        .collect()
+}
+
+/// The request header names as sent, next to the lowercase form hyper keeps and the conventional canonical form,
+/// flagging those whose casing is lost. Like `/header-order`, only HTTP/1 over TCP keeps the raw names.
+pub async fn canonicalize(
+    pretty: Pretty,
+    version: Version,
+    header_map: HeaderMap,
+    raw_heads: Option<Extension<RawHeads>>,
+    reuse: Option<Extension<ConnectionReuse>>,
+) -> Response {
+    let (preserved, headers) = ordered(version, &header_map, raw_heads, reuse);
+    let headers: Vec<_> = headers
+        .into_iter()
+        .map(|(raw, _)| {
+            let lowercase = raw.to_ascii_lowercase();
+            CanonicalName {
+                changed: raw != lowercase,
+                canonical: canonical(&raw),
+                lowercase,
+                raw,
+            }
+        })
+        .collect();
+    pretty
+        .json(Canonicalized {
+            protocol: format!("{version:?}"),
+            preserved,
+            changed: headers.iter().filter(|it| it.changed).map(|it| it.raw.clone()).collect(),
+            headers,
+        })
+        .into_response()
+}
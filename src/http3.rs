@@ -0,0 +1,135 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::ConnectInfo,
+    http::{Request, StatusCode, Version},
+    response::IntoResponse as _,
+};
+use axum_extra::response::ErasedJson;
+use futures_util::StreamExt as _;
+use h3::server::RequestStream;
+use hyper::body::Buf as _;
+use tower::ServiceExt as _;
+
+use crate::{
+    admin::Shutdown,
+    data::{ErrorDetail, TlsInfo},
+    serve::ConnectionCounter,
+};
+
+/// QUIC needs TLS, the endpoint uses a self-signed certificate for `localhost` generated at startup.
+fn server_config() -> Result<quinn::ServerConfig, Box<dyn std::error::Error>> {
+    let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+    let key = rustls::pki_types::PrivateKeyDer::Pkcs8(key_pair.serialize_der().into());
+    let mut tls = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.der().clone()], key)?;
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+    Ok(quinn::ServerConfig::with_crypto(Arc::new(
+        quinn::crypto::rustls::QuicServerConfig::try_from(tls)?,
+    )))
+}
+
+/// Serves `router` over HTTP/3 on the UDP port `addr` until shutdown, answering 413 to request bodies larger than
+/// `max_body_bytes`.
+pub async fn serve(addr: SocketAddr, router: Router, max_body_bytes: usize, shutdown: Shutdown) {
+    let endpoint = match server_config().and_then(|config| Ok(quinn::Endpoint::server(config, addr)?)) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            tracing::error!("HTTP/3 server failed to start: {e}");
+            return;
+        }
+    };
+    eprintln!("Listening for HTTP/3 on https://{addr}");
+
+    loop {
+        let incoming = tokio::select! {
+            incoming = endpoint.accept() => incoming,
+            _ = shutdown.wait() => break,
+        };
+        let Some(incoming) = incoming else { break };
+        let router = router.clone();
+        tokio::spawn(async move {
+            if let Err(e) = connection(incoming, router, max_body_bytes).await {
+                tracing::debug!("HTTP/3 connection closed: {e}");
+            }
+        });
+    }
+    endpoint.close(quinn::VarInt::from_u32(0), b"shutting down");
+    endpoint.wait_idle().await;
+}
+
+async fn connection(
+    incoming: quinn::Incoming,
+    router: Router,
+    max_body_bytes: usize,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let connection = incoming.await?;
+    let remote = connection.remote_address();
+    let handshake = connection
+        .handshake_data()
+        .and_then(|data| data.downcast::<quinn::crypto::rustls::HandshakeData>().ok());
+    let tls = TlsInfo {
+        version: "TLSv1.3",
+        alpn: handshake
+            .as_ref()
+            .and_then(|it| it.protocol.as_deref())
+            .map(|it| String::from_utf8_lossy(it).into_owned()),
+        server_name: handshake.and_then(|it| it.server_name),
+    };
+    let mut h3 = h3::server::Connection::new(h3_quinn::Connection::new(connection)).await?;
+    let counter = ConnectionCounter::default();
+    while let Some((mut request, stream)) = h3.accept().await? {
+        request.extensions_mut().insert(counter.next());
+        request.extensions_mut().insert(tls.clone());
+        let router = router.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(request, stream, remote, router, max_body_bytes).await {
+                tracing::debug!("HTTP/3 request failed: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Runs a request through the same router as HTTP/1 and HTTP/2, the request body is buffered.
+async fn handle(
+    request: Request<()>,
+    mut stream: RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+    remote: SocketAddr,
+    router: Router,
+    max_body_bytes: usize,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut body = Vec::new();
+    let mut too_large = false;
+    while let Some(mut chunk) = stream.recv_data().await? {
+        if body.len() + chunk.remaining() > max_body_bytes {
+            too_large = true;
+            break;
+        }
+        body.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+    }
+
+    let response = if too_large {
+        let detail = format!("request body is larger than {max_body_bytes} bytes");
+        let error = ErrorDetail::new(413, "Payload Too Large", detail);
+        (StatusCode::PAYLOAD_TOO_LARGE, ErasedJson::pretty(error)).into_response()
+    } else {
+        let (mut parts, ()) = request.into_parts();
+        parts.version = Version::HTTP_3;
+        parts.extensions.insert(ConnectInfo(remote));
+        router.oneshot(Request::from_parts(parts, Body::from(body))).await?
+    };
+
+    let (parts, body) = response.into_parts();
+    stream.send_response(axum::http::Response::from_parts(parts, ())).await?;
+    let mut body = body.into_data_stream();
+    while let Some(chunk) = body.next().await {
+        stream.send_data(chunk?).await?;
+    }
+    stream.finish().await?;
+    Ok(())
+}
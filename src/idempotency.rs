@@ -0,0 +1,166 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::Query;
+use axum_valid::Garde;
+use garde::Validate;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::data::ErrorDetail;
+
+pub const DEFAULT_TTL: u64 = 3600;
+pub const MAX_TTL: u64 = 24 * 3600;
+pub const MAX_KEY_LEN: usize = 255;
+/// Keys remembered at once, the oldest is forgotten first.
+pub const CAPACITY: usize = 10_000;
+/// Bytes of responses remembered at once, the oldest are forgotten first, a larger response is answered 413.
+pub const MAX_CACHED_BYTES: usize = 64 * 1024 * 1024;
+
+static IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+static REPLAYED: HeaderName = HeaderName::from_static("x-idempotent-replayed");
+
+/// The first response to each key, with the digest of the body it was made for.
+static RESPONSES: LazyLock<Arc<Mutex<Responses>>> = LazyLock::new(Default::default);
+
+struct Cached {
+    digest: [u8; 32],
+    status: StatusCode,
+    body: Bytes,
+    created: Instant,
+    expires: Instant,
+}
+
+#[derive(Default)]
+struct Responses {
+    by_key: HashMap<String, Cached>,
+    /// the keys in the order they were first used, a key forgotten since may still be listed
+    created: VecDeque<(Instant, String)>,
+    /// of the cached bodies
+    bytes: usize,
+}
+
+impl Responses {
+    /// The response for a key, forgetting it once it expired.
+    fn get(&mut self, key: &str, now: Instant) -> Option<&Cached> {
+        if self.by_key.get(key).is_some_and(|cached| cached.expires <= now) {
+            self.remove(key);
+        }
+        self.by_key.get(key)
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(cached) = self.by_key.remove(key) {
+            self.bytes -= cached.body.len();
+        }
+    }
+
+    /// Forgets the oldest responses until `cached` fits within [`CAPACITY`] and [`MAX_CACHED_BYTES`].
+    fn insert(&mut self, key: String, cached: Cached) {
+        // the list holds every cached key, bounding it bounds the map too
+        while self.created.len() >= CAPACITY || self.bytes + cached.body.len() > MAX_CACHED_BYTES {
+            let Some((created, oldest)) = self.created.pop_front() else { break };
+            // a key that expired and was used again is listed twice, only the latest counts
+            if self.by_key.get(&oldest).is_some_and(|it| it.created == created) {
+                self.remove(&oldest);
+            }
+        }
+        self.bytes += cached.body.len();
+        self.created.push_back((cached.created, key.clone()));
+        self.by_key.insert(key, cached);
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct IdempotencyParam {
+    /// seconds the response is replayed for, from the first request
+    #[garde(range(min = 1, max = MAX_TTL))]
+    pub ttl: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct Processed {
+    id: Uuid,
+    key: String,
+    created_at: jiff::Timestamp,
+    data: String,
+    json: Option<serde_json::Value>,
+}
+
+fn key(header_map: &HeaderMap) -> Result<String, String> {
+    let key = header_map
+        .get(&IDEMPOTENCY_KEY)
+        .ok_or_else(|| "the `Idempotency-Key` header is required".to_string())?
+        .to_str()
+        .map_err(|_| "`Idempotency-Key` must be visible ASCII".to_string())?;
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(format!("`Idempotency-Key` must be 1 to {MAX_KEY_LEN} characters"));
+    }
+    Ok(key.to_string())
+}
+
+fn respond(status: StatusCode, body: Bytes, replayed: bool) -> Response {
+    let mut response = (status, [(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())], body).into_response();
+    response
+        .headers_mut()
+        .insert(REPLAYED.clone(), HeaderValue::from_static(if replayed { "true" } else { "false" }));
+    response
+}
+
+/// Processes a request once per `Idempotency-Key`, like a payment API: the first response is kept for `ttl`
+/// seconds and replayed with `X-Idempotent-Replayed: true` to requests with the same key and body, while a
+/// different body under the same key is a 409 Conflict. The body is limited to `--max-body-bytes` on the route.
+pub async fn idempotency_key(Garde(Query(p)): Garde<Query<IdempotencyParam>>, header_map: HeaderMap, body: Bytes) -> Response {
+    let key = match key(&header_map) {
+        Ok(key) => key,
+        Err(detail) => return ErrorDetail::response(StatusCode::BAD_REQUEST, detail),
+    };
+    let digest: [u8; 32] = Sha256::digest(&body).into();
+    let now = Instant::now();
+
+    let mut responses = RESPONSES.lock().await;
+    if let Some(cached) = responses.get(&key, now) {
+        if cached.digest != digest {
+            let detail = format!("`Idempotency-Key` {key} was already used with a different body");
+            return ErrorDetail::response(StatusCode::CONFLICT, detail);
+        }
+        return respond(cached.status, cached.body.clone(), true);
+    }
+
+    let processed = Processed {
+        id: Uuid::new_v4(),
+        key: key.clone(),
+        created_at: jiff::Timestamp::now(),
+        data: String::from_utf8_lossy(&body).into_owned(),
+        json: serde_json::from_slice(&body).ok(),
+    };
+    let status = StatusCode::CREATED;
+    let body = Bytes::from(serde_json::to_vec_pretty(&processed).unwrap_or_default());
+    if body.len() > MAX_CACHED_BYTES {
+        return ErrorDetail::response(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("the response would be larger than the {MAX_CACHED_BYTES} bytes kept"),
+        );
+    }
+    responses.insert(
+        key,
+        Cached {
+            digest,
+            status,
+            body: body.clone(),
+            created: now,
+            expires: now + Duration::from_secs(p.ttl.unwrap_or(DEFAULT_TTL)),
+        },
+    );
+    respond(status, body, false)
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    Extension,
+    extract::ConnectInfo,
+    http::{
+        HeaderMap,
+        header::{ACCEPT_LANGUAGE, AUTHORIZATION, USER_AGENT},
+    },
+    response::IntoResponse,
+};
+use axum_client_ip::InsecureClientIp;
+use axum_extra::{extract::CookieJar, response::ErasedJson};
+use base64::{
+    Engine as _,
+    prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD},
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::auth_scheme;
+
+const REDACTED: &str = "[redacted]";
+
+/// Geolocation headers set by CDNs and platforms, the first one present wins.
+const COUNTRY: &[&str] = &["cf-ipcountry", "cloudfront-viewer-country", "x-appengine-country", "x-country-code"];
+const REGION: &[&str] = &["cloudfront-viewer-country-region", "x-appengine-region"];
+const CITY: &[&str] = &["cloudfront-viewer-city", "x-appengine-city"];
+const TIME_ZONE: &[&str] = &["cloudfront-viewer-time-zone", "x-timezone"];
+const COORDINATES: &[&str] = &["x-appengine-citylatlong"];
+
+#[derive(Serialize)]
+struct WhoAmI {
+    client: Client,
+    user_agent: Option<String>,
+    /// `null` without an `Authorization` header
+    principal: Option<Principal>,
+    /// the byte length of each cookie value, the values themselves can be session secrets
+    cookies: BTreeMap<String, usize>,
+    geo: Geo,
+}
+
+#[derive(Serialize)]
+struct Client {
+    /// the client address, taken from forwarding headers such as `X-Forwarded-For` when present
+    origin: IpAddr,
+    /// the address of the socket peer, `null` when unknown
+    peer: Option<SocketAddr>,
+    /// the hops of `X-Forwarded-For`, client first
+    forwarded_for: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Principal {
+    scheme: String,
+    /// the Basic user name or the `sub` claim of a JWT
+    name: Option<String>,
+    /// always redacted, the password or the token
+    credentials: &'static str,
+    /// the header and claims of a JWT bearer token, decoded but not verified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jwt: Option<Jwt>,
+}
+
+#[derive(Serialize)]
+struct Jwt {
+    header: Value,
+    claims: Value,
+    verified: bool,
+}
+
+#[derive(Serialize)]
+struct Geo {
+    country: Option<String>,
+    region: Option<String>,
+    city: Option<String>,
+    time_zone: Option<String>,
+    coordinates: Option<String>,
+    /// the first language of `Accept-Language`
+    language: Option<String>,
+    /// the headers the values above come from
+    sources: Vec<&'static str>,
+}
+
+fn header(header_map: &HeaderMap, name: &str) -> Option<String> {
+    header_map
+        .get(name)
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn segment(segment: &str) -> Option<Value> {
+    let decoded = BASE64_URL_SAFE_NO_PAD.decode(segment.trim_end_matches('=')).ok()?;
+    serde_json::from_slice::<Value>(&decoded).ok().filter(Value::is_object)
+}
+
+/// A bearer token with three dot separated segments, the first two JSON objects.
+fn jwt(token: &str) -> Option<Jwt> {
+    let [header, claims, _signature] = token.split('.').collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    Some(Jwt {
+        header: segment(header)?,
+        claims: segment(claims)?,
+        verified: false,
+    })
+}
+
+fn principal(authorization: &str) -> Principal {
+    let (scheme, credentials) = auth_scheme::split(authorization).unwrap_or((auth_scheme::UNKNOWN, ""));
+    let mut principal = Principal {
+        scheme: scheme.to_ascii_lowercase(),
+        name: None,
+        credentials: REDACTED,
+        jwt: None,
+    };
+    if scheme.eq_ignore_ascii_case("basic") {
+        principal.name = BASE64_STANDARD
+            .decode(credentials)
+            .ok()
+            .and_then(|decoded| Some(String::from_utf8_lossy(decoded.split(|byte| *byte == b':').next()?).into_owned()));
+    } else if scheme.eq_ignore_ascii_case("bearer") {
+        principal.jwt = jwt(credentials);
+        principal.name = principal
+            .jwt
+            .as_ref()
+            .and_then(|jwt| jwt.claims.get("sub")?.as_str().map(str::to_string));
+    }
+    principal
+}
+
+/// Who the server thinks the client is: its addresses, `User-Agent`, the principal of `Authorization` with the
+/// credentials redacted, the cookie names and lengths, and the geolocation hints of CDN headers. Every field is `null` or
+/// empty when the request carries no such signal.
+pub async fn whoami(
+    InsecureClientIp(origin): InsecureClientIp,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    header_map: HeaderMap,
+    jar: CookieJar,
+) -> impl IntoResponse {
+    let mut sources = Vec::new();
+    let mut geo = |names: &[&'static str]| {
+        let (name, value) = names.iter().find_map(|name| Some((*name, header(&header_map, name)?)))?;
+        sources.push(name);
+        Some(value)
+    };
+    let (country, region, city, time_zone, coordinates) = (geo(COUNTRY), geo(REGION), geo(CITY), geo(TIME_ZONE), geo(COORDINATES));
+    let language = header(&header_map, ACCEPT_LANGUAGE.as_str())
+        .and_then(|it| Some(it.split(',').next()?.split(';').next()?.trim().to_string()))
+        .filter(|it| !it.is_empty() && it != "*");
+    if language.is_some() {
+        sources.push("accept-language");
+    }
+
+    ErasedJson::pretty(WhoAmI {
+        client: Client {
+            origin,
+            // Unix domain socket connections carry a placeholder with port 0
+            peer: connect_info
+                .map(|Extension(ConnectInfo(peer))| peer)
+                .filter(|peer| peer.port() != 0),
+            forwarded_for: header(&header_map, "x-forwarded-for")
+                .map(|it| it.split(',').map(|hop| hop.trim().to_string()).collect())
+                .unwrap_or_default(),
+        },
+        user_agent: header(&header_map, USER_AGENT.as_str()),
+        principal: header(&header_map, AUTHORIZATION.as_str()).map(|it| principal(&it)),
+        cookies: jar.iter().map(|cookie| (cookie.name().to_string(), cookie.value().len())).collect(),
+        geo: Geo {
+            country,
+            region,
+            city,
+            time_zone,
+            coordinates,
+            language,
+            sources,
+        },
+    })
+}
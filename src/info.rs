@@ -0,0 +1,112 @@
+use std::{
+    sync::{Arc, LazyLock},
+    time::Instant,
+};
+
+use axum::{Extension, response::IntoResponse};
+use axum_extra::response::ErasedJson;
+use serde_json::{Value, json};
+
+use crate::config::{Config, Overflow};
+
+/// Forced when the server starts, so uptime is not counted from the first `/info` request.
+pub static STARTED: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+const FEATURES: &[(&str, bool)] = &[
+    ("charset", cfg!(feature = "charset")),
+    ("graphql", cfg!(feature = "graphql")),
+    ("grpc", cfg!(feature = "grpc")),
+    ("http3", cfg!(feature = "http3")),
+    ("json-schema", cfg!(feature = "json-schema")),
+    ("otel", cfg!(feature = "otel")),
+    ("protobuf", cfg!(feature = "protobuf")),
+    ("proxy", cfg!(feature = "proxy")),
+    ("tls", cfg!(feature = "tls")),
+    ("webdav", cfg!(feature = "webdav")),
+];
+
+fn redacted(secret: &Option<String>) -> Value {
+    match secret {
+        Some(_) => "[redacted]".into(),
+        None => Value::Null,
+    }
+}
+
+/// The configuration as given on the command line, secrets only tell whether they are set.
+fn config(config: &Config) -> Value {
+    #[allow(unused_mut)]
+    let mut value = json!({
+        "admin_token": redacted(&config.admin_token),
+        // only the names, an operator may add an `Authorization` or a `Set-Cookie`
+        "add_headers": config.add_headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(),
+        "force_headers": config.force_headers,
+        "request_id_header": config.request_id_header.as_str(),
+        "pretty": config.pretty,
+        "max_header_bytes": config.max_header_bytes,
+        "max_uri_length": config.max_uri_length,
+        "uds": config.uds,
+        "accept_proxy_protocol": config.accept_proxy_protocol,
+        "session_secret": redacted(&config.session_secret),
+        "session_ttl": config.session_ttl.as_secs_f64(),
+        "max_connections": config.max_connections,
+        "connection_overflow": match config.connection_overflow {
+            Overflow::Reject => "reject",
+            Overflow::Queue => "queue",
+        },
+        "header_read_timeout": config.header_read_timeout.as_secs_f64(),
+        "max_body_bytes": config.max_body_bytes,
+        "alt_svc": config.alt_svc.as_ref().and_then(|it| it.to_str().ok()),
+        "har_capacity": config.har_capacity,
+        "tcp_nodelay": config.tcp_nodelay,
+        "accept_delay": config.accept_delay.as_secs_f64(),
+        "latency_profile": config.latency_profile.as_ref().map(|it| &it.path),
+        "allow_cidrs": config.allow_cidrs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "deny_cidrs": config.deny_cidrs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "trusted_proxies": config.trusted_proxies.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "request_deadline": config.request_deadline.map(|it| it.as_secs_f64()),
+        "max_request_deadline": config.max_request_deadline.as_secs_f64(),
+        "server_header": config.server_header.as_ref().and_then(|it| it.to_str().ok()),
+        "date_header": config.date_header.as_ref().and_then(|it| it.to_str().ok()),
+        "compress_min_size": config.compress_min_size,
+        "keepalive_timeout": config.keepalive_timeout.map(|it| it.as_secs_f64()),
+        "keepalive_max_requests": config.keepalive_max_requests,
+        "fuzz": config.fuzz,
+        "redirect_allowlist": config.redirect_allowlist,
+        "vhosts": config.vhosts.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "vhost_default": config.vhost_default.to_string(),
+        "serve_files": config.serve_files.iter().map(|it| format!("{}@{}", it.path.display(), it.route)).collect::<Vec<_>>(),
+    });
+    #[cfg(feature = "grpc")]
+    {
+        value["grpc_port"] = json!(config.grpc_port);
+    }
+    #[cfg(feature = "http3")]
+    {
+        value["http3"] = json!(config.http3);
+    }
+    #[cfg(feature = "tls")]
+    {
+        value["tls"] = json!(config.tls);
+    }
+    #[cfg(feature = "proxy")]
+    {
+        value["proxy_allowlist"] = json!(config.proxy_allowlist);
+    }
+    #[cfg(feature = "json-schema")]
+    {
+        value["schema_allowlist"] = json!(config.schema_allowlist);
+    }
+    value
+}
+
+pub async fn info(Extension(config): Extension<Arc<Config>>) -> impl IntoResponse {
+    ErasedJson::pretty(json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_hash": env!("GIT_HASH"),
+        "rustc": env!("RUSTC_VERSION"),
+        "features": FEATURES.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| name).collect::<Vec<_>>(),
+        "uptime": STARTED.elapsed().as_secs_f64(),
+        "config": self::config(&config),
+    }))
+}
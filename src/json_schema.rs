@@ -0,0 +1,149 @@
+use std::{
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use axum::{
+    Extension,
+    body::Bytes,
+    http::{StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use futures_util::StreamExt as _;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    config::{self, Config},
+    data::ErrorDetail,
+};
+
+/// Bounds for fetching a remote schema.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_SCHEMA_BYTES: usize = 1024 * 1024;
+
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        // a redirect could leave `--schema-allowlist`
+        .redirect(reqwest::redirect::Policy::none())
+        // and so could an `HTTP_PROXY` of the environment
+        .no_proxy()
+        .build()
+        .expect("a client without custom TLS settings")
+});
+
+#[derive(Deserialize)]
+pub struct ValidateParam {
+    /// inline JSON, or an http(s) URL to fetch it from
+    pub schema: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Violation {
+    path: String,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct Validation {
+    valid: bool,
+    errors: Vec<Violation>,
+}
+
+async fn fetch(url: &str) -> Result<Value, String> {
+    let response = CLIENT
+        .get(url)
+        .send()
+        .await
+        .and_then(|it| it.error_for_status())
+        .map_err(|e| format!("failed to fetch schema: {e}"))?;
+    let too_large = || format!("schema is larger than {MAX_SCHEMA_BYTES} bytes");
+    if response.content_length().is_some_and(|len| len > MAX_SCHEMA_BYTES as u64) {
+        return Err(too_large());
+    }
+    let mut bytes = Vec::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| format!("failed to fetch schema: {e}"))?;
+        if bytes.len() + chunk.len() > MAX_SCHEMA_BYTES {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    serde_json::from_slice(&bytes).map_err(|e| format!("schema is not valid JSON: {e}"))
+}
+
+/// Answers 403 unless `--schema-allowlist` lists the host of a schema URL.
+fn allowed_host(config: &Config, url: &str) -> Result<(), Response> {
+    let host = url.parse::<Uri>().ok().and_then(|it| it.host().map(str::to_string));
+    let Some(host) = host else {
+        return Err(ErrorDetail::response(
+            StatusCode::BAD_REQUEST,
+            format!("invalid schema URL `{url}`"),
+        ));
+    };
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    match config::host_allowed(&config.schema_allowlist, &host) {
+        true => Ok(()),
+        false => {
+            let detail = format!("the schema host `{host}` is not in --schema-allowlist");
+            Err((
+                StatusCode::FORBIDDEN,
+                ErasedJson::pretty(ErrorDetail::new(403, "Forbidden", detail)),
+            )
+                .into_response())
+        }
+    }
+}
+
+/// Validates the body against the `schema`, inline or fetched from an http(s) URL whose host `--schema-allowlist`
+/// lists, without following redirects.
+pub async fn validate_json(
+    Extension(config): Extension<Arc<Config>>,
+    Query(ValidateParam { schema }): Query<ValidateParam>,
+    body: Bytes,
+) -> Response {
+    let schema = match schema.as_deref().map(str::trim) {
+        None => return ErrorDetail::response(StatusCode::BAD_REQUEST, "missing `schema` query parameter"),
+        Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+            if let Err(response) = allowed_host(&config, url) {
+                return response;
+            }
+            match fetch(url).await {
+                Ok(schema) => schema,
+                Err(e) => return ErrorDetail::response(StatusCode::BAD_REQUEST, e),
+            }
+        }
+        Some(inline) => match serde_json::from_str(inline) {
+            Ok(schema) => schema,
+            Err(e) => return ErrorDetail::response(StatusCode::BAD_REQUEST, format!("schema is not valid JSON: {e}")),
+        },
+    };
+    let validator = match jsonschema::validator_for(&schema) {
+        Ok(validator) => validator,
+        Err(e) => return ErrorDetail::response(StatusCode::BAD_REQUEST, format!("invalid schema: {e}")),
+    };
+    let instance: Value = match serde_json::from_slice(&body) {
+        Ok(instance) => instance,
+        Err(e) => return ErrorDetail::response(StatusCode::BAD_REQUEST, format!("body is not valid JSON: {e}")),
+    };
+
+    let errors: Vec<_> = validator
+        .iter_errors(&instance)
+        .map(|e| Violation {
+            path: e.instance_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect();
+    ErasedJson::pretty(Validation {
+        valid: errors.is_empty(),
+        errors,
+    })
+    .into_response()
+}
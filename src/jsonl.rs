@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension,
+    extract::Request,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use futures_util::StreamExt as _;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{chunked, config::Config, data::ErrorDetail};
+
+/// Records and errors kept for the response, past them lines are still validated and counted.
+pub const MAX_KEPT: usize = 1000;
+
+#[derive(Serialize)]
+struct LineError {
+    /// 1-based, like `column`
+    line: usize,
+    column: usize,
+    error: String,
+}
+
+#[derive(Serialize, Default)]
+struct Summary {
+    /// every line, blank ones included, a final newline does not start another
+    lines: usize,
+    valid: usize,
+    invalid: usize,
+    /// whether the body ends with a newline, as NDJSON asks of every line
+    trailing_newline: bool,
+    /// whether `records` or `errors` stop at [`MAX_KEPT`]
+    truncated: bool,
+    errors: Vec<LineError>,
+    records: Vec<Value>,
+}
+
+impl Summary {
+    fn line(&mut self, line: &[u8]) {
+        self.lines += 1;
+        // `\r\n` is allowed as a separator
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let result = match line.iter().all(u8::is_ascii_whitespace) {
+            true => Err(LineError {
+                line: self.lines,
+                column: 1,
+                error: "blank line".to_string(),
+            }),
+            false => serde_json::from_slice::<Value>(line).map_err(|e| LineError {
+                line: self.lines,
+                column: e.column(),
+                error: e.to_string(),
+            }),
+        };
+        match result {
+            Ok(record) => {
+                self.valid += 1;
+                self.truncated |= !keep(&mut self.records, record);
+            }
+            Err(error) => {
+                self.invalid += 1;
+                self.truncated |= !keep(&mut self.errors, error);
+            }
+        }
+    }
+}
+
+fn keep<T>(list: &mut Vec<T>, item: T) -> bool {
+    let kept = list.len() < MAX_KEPT;
+    if kept {
+        list.push(item);
+    }
+    kept
+}
+
+/// Parses a newline-delimited JSON body line by line as it arrives, holding only the current line, and returns the
+/// records with an error per invalid line rather than failing them all. Blank lines are errors, the body is
+/// answered 200 whatever its lines, 413 past `--max-body-bytes`.
+pub async fn jsonl(Extension(config): Extension<Arc<Config>>, request: Request) -> Response {
+    let mut body = request.into_body().into_data_stream();
+    let mut summary = Summary::default();
+    let (mut bytes, mut line) = (0, Vec::new());
+    while let Some(frame) = body.next().await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", e.to_string())),
+                )
+                    .into_response();
+            }
+        };
+        bytes += frame.len();
+        if bytes > config.max_body_bytes {
+            return chunked::too_large(config.max_body_bytes);
+        }
+        let mut rest = &frame[..];
+        while let Some(end) = rest.iter().position(|byte| *byte == b'\n') {
+            line.extend_from_slice(&rest[..end]);
+            summary.line(&line);
+            line.clear();
+            rest = &rest[end + 1..];
+        }
+        line.extend_from_slice(rest);
+    }
+    summary.trailing_newline = bytes > 0 && line.is_empty();
+    if !line.is_empty() {
+        summary.line(&line);
+    }
+    ErasedJson::pretty(summary).into_response()
+}
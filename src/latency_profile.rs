@@ -0,0 +1,187 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use axum::{Extension, extract::Request, http::StatusCode, middleware::Next, response::Response};
+use serde::Deserialize;
+
+use crate::{config::Config, data::ErrorDetail};
+
+pub const MAX_LATENCY_MS: u64 = 60_000;
+
+/// A `--latency-profile` file:
+///
+/// ```json
+/// {"rules": [
+///   {"route": "/base64/*", "latency_ms": 200, "jitter_ms": 100},
+///   {"route": "/anything/**", "failure_rate": 0.1, "failure_status": 502}
+/// ]}
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// path glob, `*` matches within a segment and `**` across segments
+    pub route: String,
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// up to this much more latency, picked at random for every request
+    #[serde(default)]
+    pub jitter_ms: u64,
+    #[serde(default)]
+    pub failure_rate: f64,
+    #[serde(default = "service_unavailable")]
+    pub failure_status: u16,
+}
+
+fn service_unavailable() -> u16 {
+    503
+}
+
+/// The profile file as loaded at startup, the path is read again on `SIGHUP`.
+#[derive(Debug, Clone)]
+pub struct ProfileFile {
+    pub path: PathBuf,
+    pub profile: Profile,
+}
+
+/// The profile in effect, swapped on reload.
+#[derive(Clone)]
+pub struct Live(Arc<RwLock<Arc<Profile>>>);
+
+impl Live {
+    pub fn new(profile: Profile) -> Self {
+        Live(Arc::new(RwLock::new(Arc::new(profile))))
+    }
+
+    /// The profile of `--latency-profile` as loaded at startup, one without rules otherwise.
+    pub fn from_config(config: &Config) -> Self {
+        Live::new(config.latency_profile.as_ref().map(|it| it.profile.clone()).unwrap_or_default())
+    }
+
+    fn get(&self) -> Arc<Profile> {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set(&self, profile: Profile) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(profile);
+    }
+}
+
+impl Rule {
+    fn validate(&self) -> Result<(), String> {
+        if !self.route.starts_with('/') {
+            return Err("`route` must start with `/`".to_string());
+        }
+        if self.route.matches("**").count() > 1 {
+            return Err("`route` can have at most one `**`".to_string());
+        }
+        if self.latency_ms.saturating_add(self.jitter_ms) > MAX_LATENCY_MS {
+            return Err(format!("`latency_ms` and `jitter_ms` add up to more than {MAX_LATENCY_MS}"));
+        }
+        if !(0.0..=1.0).contains(&self.failure_rate) {
+            return Err(format!("`failure_rate` {} is not between 0 and 1", self.failure_rate));
+        }
+        StatusCode::from_u16(self.failure_status).map_err(|e| format!("`failure_status` {}: {e}", self.failure_status))?;
+        Ok(())
+    }
+}
+
+pub fn load(path: &Path) -> Result<Profile, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let profile: Profile = serde_json::from_str(&text).map_err(|e| format!("invalid profile {}: {e}", path.display()))?;
+    for (index, rule) in profile.rules.iter().enumerate() {
+        rule.validate()
+            .map_err(|e| format!("invalid profile {}: rule {index} (`{}`): {e}", path.display(), rule.route))?;
+    }
+    Ok(profile)
+}
+
+/// Matches a path against a glob with `*` within a segment and `**` across segments.
+///
+/// Iterative, backtracking only to the last `*` and to the `**`, at most one per rule, so a rule costs at most
+/// the product of the glob and path lengths. A `*` that has to grow over a `/` hands over to the `**` before it,
+/// which can match anything the `*`s between them could.
+pub fn matches(glob: &[u8], path: &[u8]) -> bool {
+    let (mut g, mut p) = (0, 0);
+    // the glob past the star and the end of what the star matched so far
+    let mut star: Option<(usize, usize)> = None;
+    let mut double_star: Option<(usize, usize)> = None;
+    while p < path.len() {
+        match glob.get(g) {
+            Some(b'*') if glob.get(g + 1) == Some(&b'*') => {
+                g += 2;
+                double_star = Some((g, p));
+                star = None;
+            }
+            Some(b'*') => {
+                g += 1;
+                star = Some((g, p));
+            }
+            Some(byte) if *byte == path[p] => {
+                g += 1;
+                p += 1;
+            }
+            _ => {
+                if let Some((after, end)) = star.filter(|(_, end)| path[*end] != b'/') {
+                    star = Some((after, end + 1));
+                    (g, p) = (after, end + 1);
+                } else if let Some((after, end)) = double_star {
+                    double_star = Some((after, end + 1));
+                    star = None;
+                    (g, p) = (after, end + 1);
+                } else {
+                    return false;
+                }
+            }
+        }
+    }
+    glob[g..].iter().all(|byte| *byte == b'*')
+}
+
+/// Delays, and fails at random, the requests matching the first rule of the live profile for their path.
+pub async fn inject(Extension(live): Extension<Live>, request: Request, next: Next) -> Response {
+    let profile = live.get();
+    let Some((index, rule)) = profile
+        .rules
+        .iter()
+        .enumerate()
+        .find(|(_, rule)| matches(rule.route.as_bytes(), request.uri().path().as_bytes()))
+    else {
+        return next.run(request).await;
+    };
+
+    let latency = rule.latency_ms + fastrand::u64(0..=rule.jitter_ms);
+    tokio::time::sleep(Duration::from_millis(latency)).await;
+    if rule.failure_rate > 0.0 && fastrand::f64() < rule.failure_rate {
+        let status = StatusCode::from_u16(rule.failure_status).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+        let detail = format!("injected by latency profile rule {index} (`{}`)", rule.route);
+        return ErrorDetail::response(status, detail);
+    }
+    next.run(request).await
+}
+
+/// Reloads the profile from its file on every `SIGHUP`, keeping the previous one when the file is invalid.
+#[cfg(unix)]
+pub async fn reload_on_sighup(path: PathBuf, live: Live) {
+    let Ok(mut signal) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) else {
+        tracing::error!("cannot listen for SIGHUP, the latency profile will not be reloaded");
+        return;
+    };
+    while signal.recv().await.is_some() {
+        match load(&path) {
+            Ok(profile) => {
+                tracing::info!(rules = profile.rules.len(), "reloaded the latency profile");
+                live.set(profile);
+            }
+            Err(e) => tracing::error!("{e}, keeping the previous latency profile"),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    Extension,
+    extract::Request,
+    http::{HeaderName, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+
+use crate::{config::Config, data::ErrorDetail};
+
+/// Size of the request header block as it appears on an HTTP/1.1 wire: `name: value\r\n` per header.
+pub fn header_bytes(request: &Request) -> usize {
+    request
+        .headers()
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len() + 4)
+        .sum()
+}
+
+/// Rejects requests whose headers add up to more than `--max-header-bytes` with 431.
+///
+/// hyper already refuses HTTP/1 requests with more than 100 headers, or when the head outgrows its read buffer.
+pub async fn header_size(Extension(config): Extension<Arc<Config>>, request: Request, next: Next) -> Response {
+    let size = header_bytes(&request);
+    if size > config.max_header_bytes {
+        return (
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            ErasedJson::pretty(ErrorDetail::new(
+                431,
+                "Request Header Fields Too Large",
+                format!("request headers are {size} bytes, the limit is {} bytes", config.max_header_bytes),
+            )),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Rejects requests whose target, the path and query as sent, is longer than `--max-uri-length` with 414. The
+/// scheme and authority of an absolute-form target are left out, as other requests send them in `Host`.
+pub async fn uri_length(Extension(config): Extension<Arc<Config>>, request: Request, next: Next) -> Response {
+    let length = request.uri().path_and_query().map_or(0, |it| it.as_str().len());
+    if length > config.max_uri_length {
+        return (
+            StatusCode::URI_TOO_LONG,
+            ErasedJson::pretty(ErrorDetail::new(
+                414,
+                "URI Too Long",
+                format!("the request target is {length} bytes, the limit is {} bytes", config.max_uri_length),
+            )),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Asks for a deadline of this many seconds instead of `--request-deadline`, capped to `--max-request-deadline`.
+pub static X_REQUEST_DEADLINE: HeaderName = HeaderName::from_static("x-request-deadline");
+
+/// Answers 504 to requests whose response is not ready within the deadline, dropping, and so cancelling, the
+/// handler. A streamed body is not timed once its response is returned, and neither are tasks a handler spawns.
+pub async fn deadline(Extension(config): Extension<Arc<Config>>, request: Request, next: Next) -> Response {
+    let asked = match request.headers().get(&X_REQUEST_DEADLINE) {
+        None => None,
+        Some(value) => match value
+            .to_str()
+            .ok()
+            .and_then(|it| it.trim().parse::<f64>().ok())
+            .and_then(|it| Duration::try_from_secs_f64(it).ok())
+        {
+            Some(deadline) => Some(deadline),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(
+                        400,
+                        "Bad Request",
+                        format!("`{X_REQUEST_DEADLINE}` must be a number of seconds"),
+                    )),
+                )
+                    .into_response();
+            }
+        },
+    };
+    let Some(deadline) = asked.or(config.request_deadline) else {
+        return next.run(request).await;
+    };
+    let deadline = deadline.min(config.max_request_deadline);
+
+    match tokio::time::timeout(deadline, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => (
+            StatusCode::GATEWAY_TIMEOUT,
+            ErasedJson::pretty(ErrorDetail::new(
+                504,
+                "Gateway Timeout",
+                format!("no response within the {}s deadline", deadline.as_secs_f64()),
+            )),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,535 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::{
+        Arc, LazyLock, Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+
+use axum::http::Extensions;
+use serde::Serialize;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::{TcpListener, TcpStream},
+    sync::{OwnedSemaphorePermit, Semaphore, mpsc},
+    time::Sleep,
+};
+
+use crate::{
+    config::{Config, Overflow},
+    header_order::RawHeads,
+    proxy_protocol,
+};
+
+/// Connection counters, across all listeners of the process.
+pub static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+pub static REJECTED: AtomicU64 = AtomicU64::new(0);
+/// Open connections by client address, the one of the PROXY header with `--accept-proxy-protocol`, moved to
+/// the `X-Forwarded-For` client of the latest request from a `--trusted-proxy`, see [`CountedClient`].
+static BY_IP: LazyLock<Mutex<HashMap<IpAddr, usize>>> = LazyLock::new(Mutex::default);
+
+/// The open TCP connections of every client address that has any.
+pub fn connections_by_ip() -> BTreeMap<IpAddr, usize> {
+    BY_IP
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(ip, count)| (*ip, *count))
+        .collect()
+}
+
+const REJECTION: &[u8] = b"HTTP/1.1 503 Service Unavailable\r\nconnection: close\r\ncontent-length: 0\r\n\r\n";
+
+/// TCP listener applying the connection level options: `--accept-proxy-protocol`, `--max-connections`,
+/// `--tcp-nodelay` and `--accept-delay`.
+///
+/// Connections are accepted and prepared on a background task, so a client slow to send its PROXY header does
+/// not hold up the others.
+pub struct Listener {
+    local_addr: SocketAddr,
+    accepted: mpsc::Receiver<(Connection, SocketAddr)>,
+}
+
+/// The socket of a connection, or its TLS session with `--tls`.
+enum Stream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<tokio_rustls::server::TlsStream<crate::tls::Replay>>),
+}
+
+/// An accepted connection, holding its slot of `--max-connections` until dropped.
+pub struct Connection {
+    stream: Stream,
+    socket_options: SocketOptions,
+    slow_frame: SlowFrame,
+    misframed_length: MisframedLength,
+    raw_heads: RawHeads,
+    alpn: Option<proxy_protocol::ProxiedAlpn>,
+    #[cfg(feature = "tls")]
+    tls: Option<(Arc<crate::tls::ClientHello>, crate::data::TlsInfo)>,
+    client: CountedClient,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+/// The address a connection counts under in [`connections_by_ip`]. It starts as the peer and follows the
+/// client of each request, as `access::client_ip` resolves it, so a connection of a trusted proxy counts for
+/// the client it last carried.
+#[derive(Clone)]
+pub struct CountedClient(Arc<Mutex<IpAddr>>);
+
+impl CountedClient {
+    pub fn attribute(&self, client: IpAddr) {
+        let client = client.to_canonical();
+        let mut current = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if *current == client {
+            return;
+        }
+        let mut by_ip = BY_IP.lock().unwrap_or_else(|e| e.into_inner());
+        release(&mut by_ip, *current);
+        *by_ip.entry(client).or_default() += 1;
+        *current = client;
+    }
+}
+
+fn release(by_ip: &mut HashMap<IpAddr, usize>, client: IpAddr) {
+    if let Some(count) = by_ip.get_mut(&client) {
+        *count -= 1;
+        if *count == 0 {
+            by_ip.remove(&client);
+        }
+    }
+}
+
+/// Splits the writes of an HTTP/1 connection once enabled by `/slow-frame`: one write per line of the response
+/// head, then the body `chunk_size` bytes at a time, sleeping `delay` between writes. It stays on until the
+/// connection closes.
+#[derive(Clone, Default)]
+pub struct SlowFrame(Arc<Mutex<Option<Split>>>);
+
+struct Split {
+    delay: Duration,
+    chunk_size: usize,
+    in_body: bool,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl SlowFrame {
+    pub fn enable(&self, delay: Duration, chunk_size: usize) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(Split {
+            delay,
+            chunk_size: chunk_size.max(1),
+            in_body: false,
+            sleep: None,
+        });
+    }
+}
+
+/// Rewrites the `content-length` header of the next response head on an HTTP/1 connection once enabled by
+/// `/content-length-mismatch`, or its status line for `/304-without-body?broken=true`. hyper still writes the
+/// body it framed, only the head on the wire changes. The `/fuzz/content-length` values need not be numbers.
+#[derive(Clone, Default)]
+pub struct MisframedLength(Arc<Mutex<Option<Misframe>>>);
+
+struct Misframe {
+    content_length: Option<String>,
+    status_line: Option<&'static str>,
+    /// the rewritten head, how much of it is written and how much of the buffer it was rewritten from
+    pending: Option<(Vec<u8>, usize, usize)>,
+    done: bool,
+}
+
+impl MisframedLength {
+    pub fn enable(&self, content_length: impl std::fmt::Display) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(Misframe {
+            content_length: Some(content_length.to_string()),
+            status_line: None,
+            pending: None,
+            done: false,
+        });
+    }
+
+    /// Replaces the status line, without its CRLF, keeping the headers and the body of the response.
+    pub fn enable_status(&self, status_line: &'static str) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(Misframe {
+            content_length: None,
+            status_line: Some(status_line),
+            pending: None,
+            done: false,
+        });
+    }
+
+    fn is_pending(&self) -> bool {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|misframe| !misframe.done)
+    }
+}
+
+impl Misframe {
+    fn rewrite(&self, head: &[u8]) -> Vec<u8> {
+        let mut rewritten = Vec::with_capacity(head.len() + 20);
+        for (index, line) in head.split_inclusive(|byte| *byte == b'\n').enumerate() {
+            if let Some(status_line) = self.status_line.filter(|_| index == 0) {
+                rewritten.extend_from_slice(format!("{status_line}\r\n").as_bytes());
+            } else if let Some(content_length) = self
+                .content_length
+                .as_ref()
+                .filter(|_| line.len() > 15 && line[..15].eq_ignore_ascii_case(b"content-length:"))
+            {
+                rewritten.extend_from_slice(format!("content-length: {content_length}\r\n").as_bytes());
+            } else {
+                rewritten.extend_from_slice(line);
+            }
+        }
+        rewritten
+    }
+
+    /// Writes the rewritten head in place of the head at the start of `buf`, reporting the original as written.
+    fn poll_write(&mut self, mut stream: Pin<&mut Stream>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let pending = match self.pending.take() {
+            Some(pending) => pending,
+            None => match buf.windows(4).position(|it| it == b"\r\n\r\n") {
+                Some(end) => (self.rewrite(&buf[..end + 4]), 0, end + 4),
+                None => return stream.poll_write(cx, buf),
+            },
+        };
+        let (head, written, consumed) = self.pending.insert(pending);
+        while *written < head.len() {
+            match ready!(stream.as_mut().poll_write(cx, &head[*written..]))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                n => *written += n,
+            }
+        }
+        let consumed = *consumed;
+        self.pending = None;
+        self.done = true;
+        Poll::Ready(Ok(consumed))
+    }
+}
+
+/// The options of an accepted socket once `--tcp-nodelay` is applied, as the kernel reports them.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SocketOptions {
+    pub nodelay: Option<bool>,
+    pub keepalive: Option<bool>,
+    pub recv_buffer_size: Option<usize>,
+    pub send_buffer_size: Option<usize>,
+}
+
+impl SocketOptions {
+    fn of(stream: &TcpStream) -> Self {
+        let socket = socket2::SockRef::from(stream);
+        SocketOptions {
+            nodelay: stream.nodelay().ok(),
+            keepalive: socket.keepalive().ok(),
+            recv_buffer_size: socket.recv_buffer_size().ok(),
+            send_buffer_size: socket.send_buffer_size().ok(),
+        }
+    }
+}
+
+impl Connection {
+    fn new(
+        stream: Stream,
+        socket_options: SocketOptions,
+        permit: Option<OwnedSemaphorePermit>,
+        client: IpAddr,
+        alpn: Option<proxy_protocol::ProxiedAlpn>,
+    ) -> Self {
+        ACTIVE.fetch_add(1, Ordering::Relaxed);
+        // IPv4 clients of a dual-stack socket count with the same clients over IPv4
+        let client = client.to_canonical();
+        *BY_IP.lock().unwrap_or_else(|e| e.into_inner()).entry(client).or_default() += 1;
+        Connection {
+            socket_options,
+            slow_frame: SlowFrame::default(),
+            misframed_length: MisframedLength::default(),
+            raw_heads: RawHeads::default(),
+            alpn,
+            #[cfg(feature = "tls")]
+            tls: None,
+            stream,
+            client: CountedClient(Arc::new(Mutex::new(client))),
+            _permit: permit,
+        }
+    }
+}
+
+impl crate::serve::ConnectionExtensions for Connection {
+    fn extensions(&self) -> Extensions {
+        let mut extensions = Extensions::new();
+        extensions.insert(self.socket_options);
+        extensions.insert(self.slow_frame.clone());
+        extensions.insert(self.misframed_length.clone());
+        extensions.insert(self.raw_heads.clone());
+        extensions.insert(self.client.clone());
+        if let Some(alpn) = &self.alpn {
+            extensions.insert(alpn.clone());
+        }
+        #[cfg(feature = "tls")]
+        if let Some((hello, info)) = &self.tls {
+            extensions.insert(hello.clone());
+            extensions.insert(info.clone());
+        }
+        extensions
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        ACTIVE.fetch_sub(1, Ordering::Relaxed);
+        let client = self.client.0.lock().unwrap_or_else(|e| e.into_inner());
+        release(&mut BY_IP.lock().unwrap_or_else(|e| e.into_inner()), *client);
+    }
+}
+
+impl Listener {
+    pub fn new(listener: TcpListener, config: &Config) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (tx, accepted) = mpsc::channel(64);
+        let proxy_protocol = config.accept_proxy_protocol;
+        let nodelay = config.tcp_nodelay;
+        let accept_delay = config.accept_delay;
+        let limit = config
+            .max_connections
+            .map(|max| (Arc::new(Semaphore::new(max)), config.connection_overflow));
+        #[cfg(feature = "tls")]
+        let tls = config.tls.then(crate::tls::acceptor).transpose()?;
+
+        tokio::spawn(async move {
+            loop {
+                // queueing leaves the connections in the listen backlog until a slot frees up
+                let queued = match &limit {
+                    Some((semaphore, Overflow::Queue)) => Some(semaphore.clone().acquire_owned().await.expect("never closed")),
+                    _ => None,
+                };
+                // like a saturated server, connections wait in the backlog and past it clients see connect timeouts
+                if !accept_delay.is_zero() {
+                    tokio::time::sleep(accept_delay).await;
+                }
+                let (mut stream, peer) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        tracing::error!("accept error: {e}");
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                if let Err(e) = stream.set_nodelay(nodelay) {
+                    tracing::debug!(%peer, "failed to set TCP_NODELAY: {e}");
+                }
+                let permit = match &limit {
+                    Some((semaphore, Overflow::Reject)) => match semaphore.clone().try_acquire_owned() {
+                        Ok(permit) => Some(permit),
+                        Err(_) => {
+                            REJECTED.fetch_add(1, Ordering::Relaxed);
+                            tokio::spawn(async move {
+                                stream.write_all(REJECTION).await.ok();
+                                stream.shutdown().await.ok();
+                            });
+                            continue;
+                        }
+                    },
+                    _ => queued,
+                };
+
+                let tx = tx.clone();
+                #[cfg(feature = "tls")]
+                let tls = tls.clone();
+                tokio::spawn(async move {
+                    let mut source = peer;
+                    let mut alpn = None;
+                    if proxy_protocol {
+                        match tokio::time::timeout(proxy_protocol::HEADER_TIMEOUT, proxy_protocol::read_header(&mut stream)).await {
+                            Ok(Ok(header)) => {
+                                source = header.source.unwrap_or(peer);
+                                alpn = header.alpn;
+                            }
+                            Ok(Err(e)) => {
+                                tracing::debug!(%peer, "rejected connection without a valid PROXY header: {e}");
+                                return;
+                            }
+                            Err(_) => {
+                                tracing::debug!(%peer, "rejected connection, no PROXY header in time");
+                                return;
+                            }
+                        }
+                    }
+                    let socket_options = SocketOptions::of(&stream);
+                    #[cfg(feature = "tls")]
+                    let (stream, tls) = match &tls {
+                        Some(acceptor) => {
+                            match tokio::time::timeout(crate::tls::HANDSHAKE_TIMEOUT, crate::tls::accept(acceptor, stream)).await {
+                                Ok(Ok((stream, hello, info))) => (Stream::Tls(Box::new(stream)), Some((Arc::new(hello), info))),
+                                Ok(Err(e)) => {
+                                    tracing::debug!(%peer, "rejected connection, TLS handshake failed: {e}");
+                                    return;
+                                }
+                                Err(_) => {
+                                    tracing::debug!(%peer, "rejected connection, no TLS handshake in time");
+                                    return;
+                                }
+                            }
+                        }
+                        None => (Stream::Plain(stream), None),
+                    };
+                    #[cfg(not(feature = "tls"))]
+                    let stream = Stream::Plain(stream);
+                    #[allow(unused_mut)]
+                    let mut connection = Connection::new(stream, socket_options, permit, source.ip(), alpn);
+                    #[cfg(feature = "tls")]
+                    {
+                        connection.tls = tls;
+                    }
+                    tx.send((connection, source)).await.ok();
+                });
+            }
+        });
+        Ok(Listener { local_addr, accepted })
+    }
+}
+
+impl axum::serve::Listener for Listener {
+    type Io = Connection;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.accepted.recv().await {
+            Some(accepted) => accepted,
+            // the accept task only stops with the runtime
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Stream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+
+    fn poll_write_vectored(self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Stream::Plain(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            Stream::Plain(stream) => stream.is_write_vectored(),
+            #[cfg(feature = "tls")]
+            Stream::Tls(stream) => stream.is_write_vectored(),
+        }
+    }
+}
+
+impl AsyncRead for Connection {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut self.stream).poll_read(cx, buf))?;
+        self.raw_heads.observe(&buf.filled()[filled..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for Connection {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let Connection {
+            stream,
+            slow_frame,
+            misframed_length,
+            ..
+        } = self.get_mut();
+        if let Some(misframe) = misframed_length
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+            .filter(|misframe| !misframe.done)
+        {
+            return misframe.poll_write(Pin::new(stream), cx, buf);
+        }
+        let mut guard = slow_frame.0.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(split) = guard.as_mut() else {
+            return Pin::new(stream).poll_write(cx, buf);
+        };
+        if let Some(sleep) = &mut split.sleep {
+            ready!(sleep.as_mut().poll(cx));
+            split.sleep = None;
+        }
+        let len = if split.in_body {
+            buf.len().min(split.chunk_size)
+        } else {
+            buf.windows(2).position(|it| it == b"\r\n").map_or(buf.len(), |end| end + 2)
+        };
+        let written = ready!(Pin::new(stream).poll_write(cx, &buf[..len]))?;
+        // the empty line ends the head
+        if buf.starts_with(b"\r\n") && written == 2 {
+            split.in_body = true;
+        }
+        split.sleep = Some(Box::pin(tokio::time::sleep(split.delay)));
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(mut self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        if self.slow_frame.0.lock().unwrap_or_else(|e| e.into_inner()).is_some() || self.misframed_length.is_pending() {
+            let buf = bufs.iter().find(|it| !it.is_empty()).map_or(&[][..], |it| &**it);
+            return self.poll_write(cx, buf);
+        }
+        Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::Bytes,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use axum_valid::Garde;
+use garde::Validate;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Pending waiters per key, a std mutex so that dropped waiters can unregister synchronously.
+static CHANNELS: LazyLock<Mutex<HashMap<String, broadcast::Sender<Event>>>> = LazyLock::new(Default::default);
+
+#[derive(Clone, Serialize)]
+struct Event {
+    key: String,
+    data: String,
+    json: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct LongPollParam {
+    #[garde(skip)]
+    pub key: Option<String>,
+    /// seconds
+    #[garde(range(min = 0, max = 300))]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct NotifyParam {
+    pub key: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Notified {
+    key: String,
+    delivered: usize,
+}
+
+/// Unregisters the channel of a key once its last waiter is gone, whether it was notified, timed out or the
+/// client disconnected and the handler future was dropped.
+struct Waiter {
+    key: String,
+    receiver: Option<broadcast::Receiver<Event>>,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        drop(self.receiver.take());
+        let mut channels = CHANNELS.lock().unwrap();
+        if channels.get(&self.key).is_some_and(|sender| sender.receiver_count() == 0) {
+            channels.remove(&self.key);
+        }
+    }
+}
+
+fn key(key: Option<String>) -> String {
+    key.unwrap_or_else(|| "default".to_string())
+}
+
+/// Number of clients currently waiting on `key`.
+pub fn waiting(key: &str) -> usize {
+    CHANNELS.lock().unwrap().get(key).map_or(0, |sender| sender.receiver_count())
+}
+
+pub async fn long_poll(Garde(Query(p)): Garde<Query<LongPollParam>>) -> Response {
+    let key = key(p.key);
+    let receiver = CHANNELS
+        .lock()
+        .unwrap()
+        .entry(key.clone())
+        .or_insert_with(|| broadcast::channel(16).0)
+        .subscribe();
+    let mut waiter = Waiter {
+        key,
+        receiver: Some(receiver),
+    };
+
+    let timeout = Duration::from_secs(p.timeout.unwrap_or(30));
+    let receiver = waiter.receiver.as_mut().expect("set above");
+    match tokio::time::timeout(timeout, receiver.recv()).await {
+        Ok(Ok(event)) => ErasedJson::pretty(event).into_response(),
+        _ => StatusCode::NO_CONTENT.into_response(),
+    }
+}
+
+pub async fn notify(Query(NotifyParam { key: k }): Query<NotifyParam>, body: Bytes) -> impl IntoResponse {
+    let key = key(k);
+    let event = Event {
+        key: key.clone(),
+        data: String::from_utf8_lossy(&body).into_owned(),
+        json: serde_json::from_slice(&body).ok(),
+    };
+    let sender = CHANNELS.lock().unwrap().get(&key).cloned();
+    let delivered = sender.and_then(|sender| sender.send(event).ok()).unwrap_or(0);
+    ErasedJson::pretty(Notified { key, delivered })
+}
@@ -1,15 +1,16 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     net::{Ipv4Addr, SocketAddr},
     str::FromStr,
+    sync::{Arc, LazyLock},
     time::{Duration, Instant},
 };
 
 use axum::{
-    Router,
+    Extension, Router,
     body::{Body, Bytes},
     extract::{DefaultBodyLimit, MatchedPath, Path, Request},
-    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri, header::*},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri, Version, header::*},
     middleware,
     response::{AppendHeaders, Html, IntoResponse, Redirect, Response, Sse, sse::Event},
     routing::*,
@@ -31,23 +32,116 @@ use mime::{APPLICATION_JSON, IMAGE, TEXT_HTML_UTF_8, TEXT_PLAIN, TEXT_PLAIN_UTF_
 use serde::{Deserialize, Serialize};
 use tower::ServiceBuilder;
 use tower_http::{
-    ServiceBuilderExt, compression::CompressionLayer, cors::CorsLayer, request_id::MakeRequestUuid, set_header::SetRequestHeaderLayer,
+    ServiceBuilderExt,
+    compression::{CompressionLayer, Predicate as _},
+    cors::CorsLayer,
+    request_id::MakeRequestUuid,
+    set_header::{SetRequestHeaderLayer, SetResponseHeaderLayer},
     trace::TraceLayer,
 };
 use tracing::debug_span;
 use tracing_subscriber::{EnvFilter, fmt::layer, layer::SubscriberExt, util::SubscriberInitExt};
 use uuid::Uuid;
 
-use crate::data::{Headers, Http, Queries};
+use crate::{
+    admin::Shutdown,
+    config::Config,
+    data::{Headers, Http, Queries},
+    pretty::Pretty,
+};
 
+mod access;
+mod admin;
+mod alt_svc;
+mod auth_negotiate;
+#[cfg(feature = "charset")]
+mod charset;
+mod chunked;
+mod conditional;
+mod config;
 mod data;
+mod decode;
+mod decompress;
+mod etag;
+mod expect;
+mod flaky;
+mod fuzz;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod har;
+mod hash;
+mod header_order;
+#[cfg(feature = "http3")]
+mod http3;
+mod idempotency;
+mod identity;
+mod info;
+#[cfg(feature = "json-schema")]
+mod json_schema;
+mod jsonl;
+mod latency_profile;
+mod limits;
+mod listener;
+mod long_poll;
+mod metrics;
+mod mock;
+mod negotiate;
+#[cfg(feature = "otel")]
+mod otel;
+mod paginate;
+mod path_info;
+mod preflight;
+mod pretty;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+#[cfg(feature = "proxy")]
+mod proxy;
+mod proxy_protocol;
+mod query;
+mod random_drop;
+mod range;
+mod reflect_raw;
+mod rotate;
+mod serve;
+mod session;
+mod sign;
+mod sniff;
+mod static_file;
+mod stats;
+mod stream_abort;
+mod timing;
+#[cfg(feature = "tls")]
+mod tls;
+mod trailers;
+#[cfg(unix)]
+mod uds;
+mod upload_etag;
+mod vhost;
+#[cfg(feature = "webdav")]
+mod webdav;
+mod webhook;
 mod ws;
 mod ws_chat;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
 fn app() -> Router<()> {
+    app_with(Default::default(), Shutdown::default())
+}
+
+/// [`app_with_profile`] with a latency profile that is never reloaded.
+#[cfg(test)]
+fn app_with(config: Arc<Config>, shutdown: Shutdown) -> Router<()> {
+    let live = latency_profile::Live::from_config(&config);
+    app_with_profile(config, shutdown, live, health::Ready::default())
+}
+
+/// The routes and the per-request layers, `live` holds the latency profile `server()` reloads on `SIGHUP`.
+fn app_with_profile(config: Arc<Config>, shutdown: Shutdown, live: latency_profile::Live, ready: health::Ready) -> Router<()> {
     let mut router = Router::new()
         .route("/", get(index))
         .merge(
@@ -64,33 +158,72 @@ fn app() -> Router<()> {
         .merge(
             Router::new()
                 .route("/anything", any(anything))
-                .route("/anything/{*path}", any(anything)),
+                .route("/anything/{*path}", any(anything))
+                .route("/path-info/{*rest}", any(path_info::path_info))
+                .route("/decode-test/{*rest}", any(decode::decode_test))
+                .route("/form", post(decode::form)),
+        )
+        .merge(
+            Router::new()
+                .route("/user-agent", any(user_agent))
+                .route("/headers", any(headers))
+                .route("/headers/raw", any(raw_headers))
+                .route("/headers/server", any(server_headers))
+                .route("/header-order", any(header_order::header_order))
+                .route("/headers/canonicalize", any(header_order::canonicalize))
+                .route("/reflect-raw", any(reflect_raw::reflect_raw))
+                .route("/timing", any(timing::timing))
+                .route("/sniff", post(sniff::sniff_body))
+                .route("/query", any(query::query))
+                .route("/near-header-limit", get(header_size::near_limit))
+                .route("/headers/set-size", any(header_size::set_size))
+                .route("/duplicate-headers", get(header_size::duplicates))
+                .route("/ip", any(ip)),
         )
         .merge(
             Router::new()
                 .route("/absolute-redirect/{n}", any(redirect::absolute_redirect))
                 .route("/redirect/{n}", any(redirect::redirect))
                 .route("/relative-redirect/{n}", any(redirect::relative_redirect))
-                .route("/redirect-to", any(redirect::redirect_to)),
+                .route("/redirect-to", any(redirect::redirect_to))
+                .route("/redirect-relative-loop", any(redirect::relative_loop)),
         )
         .merge(
             Router::new()
                 .route("/basic-auth/{user}/{passwd}", any(basic_auth::basic_auth))
+                .route("/auth-negotiate/{user}/{passwd}", any(auth_negotiate::auth_negotiate))
                 .route("/hidden-basic-auth/{user}/{passwd}", any(basic_auth::hidden_basic_auth)),
         )
         .merge(
             Router::new()
-                .route("/user-agent", any(user_agent))
-                .route("/headers", any(headers))
-                .route("/json", get(resp_data::json))
+                .route("/json", get(resp_data::json).layer(middleware::from_fn(etag::etag)))
                 .route("/xml", get(resp_data::xml))
+                .route("/invalid-json", get(resp_data::invalid_json))
                 .route("/forms/post", any(resp_data::forms_post))
-                .route("/html", get(resp_data::html))
+                .route("/html", get(resp_data::html).layer(middleware::from_fn(etag::etag)))
                 .route("/hostname", get(hostname))
                 .route("/uuid", any(uuid))
+                .route("/uuid/v5/{namespace}/{name}", any(uuid_v5))
+                .route("/uuid-validate/{value}", any(uuid_validate))
+                .route("/uuid/bulk/{n}", any(uuid_bulk))
+                .route("/cache-bust", any(cache_bust))
                 .route("/response-headers", any(response_headers))
-                .route("/ip", any(ip))
-                .route("/bearer", any(bearer)),
+                .route("/connection-close", any(connection::close))
+                .route("/connection-keep-alive", any(connection::keep_alive))
+                .route("/connection-reuse", any(connection::reuse))
+                .route("/connection", any(connection::connection))
+                .route("/socket-opts", any(connection::socket_opts))
+                .route("/http10", any(connection::http10))
+                .route("/slow-frame", any(connection::slow_frame))
+                .route("/content-length-mismatch", get(connection::content_length_mismatch))
+                .route("/fuzz/content-length", get(fuzz::content_length))
+                .route("/fuzz/header-size", get(fuzz::header_size))
+                .route("/304-without-body", get(connection::not_modified))
+                .route("/keepalive-timeout", get(connection::keepalive_timeout))
+                .route(preflight::PATH, any(preflight::preflight_cache))
+                .route("/bearer", any(bearer))
+                .route("/auth-scheme", any(auth_scheme::auth_scheme))
+                .route("/whoami", any(identity::whoami)),
         )
         .merge(
             Router::new()
@@ -100,7 +233,10 @@ fn app() -> Router<()> {
                 .route("/image/png", any(image::png))
                 .route("/image/webp", any(image::webp))
                 .route("/image/avif", any(image::avif))
-                .route("/image/jxl", any(image::jxl)),
+                .route("/image/jxl", any(image::jxl))
+                .route("/if-range/{n}", get(range::if_range))
+                .layer(middleware::from_fn(etag::etag))
+                .layer(middleware::from_fn(range::ranges)),
         )
         //
         .merge(
@@ -110,20 +246,102 @@ fn app() -> Router<()> {
                 .route("/base64/decode/{value}", any(base_64::base64_decode)),
         )
         .route("/sse", any(sse::sse_handler))
+        .route("/sse-json", get(sse::sse_json))
+        .route("/delay-progress", get(sse::delay_progress))
+        .route("/sse-backpressure", get(sse::sse_backpressure))
+        .route("/multistatus", post(multistatus::multistatus))
+        .route("/compress-request", post(compression::compress_request))
+        .route("/gzip-stream", get(compression::gzip_stream))
+        .route("/multipart-stream/{n}", any(multipart_stream::multipart_stream))
+        .merge(
+            Router::new()
+                .route("/long-poll", any(long_poll::long_poll))
+                .route("/long-poll/notify", post(long_poll::notify)),
+        )
         .merge(
             Router::new()
                 .route("/cookies", any(cookies::cookies))
                 .route("/cookies/set", any(cookies::cookies_set))
-                .route("/cookies/delete", any(cookies::cookies_del)),
+                .route("/cookies/delete", any(cookies::cookies_del))
+                .route("/cookies/jar", any(session::cookie_jar))
+                .route("/samesite-test", any(cookies::samesite_test))
+                .route("/samesite-test/report", any(cookies::samesite_report))
+                .route("/cookie-size-limit", any(cookies::size_limit)),
         )
         .route("/encoding/utf8", any(utf8))
         .route("/robots.txt", any(robots_txt))
+        .route("/health", get(health::health))
+        .route("/ready", get(health::ready))
+        .route("/metrics", get(metrics::metrics))
+        .route("/stats", get(stats::stats))
+        .route("/info", get(info::info))
         .merge(
             Router::new()
                 .route("/links/{total}", any(links::links))
                 .route("/links/{total}/{page}", any(links::links)),
         )
+        .route("/paginate", get(paginate::paginate))
         .route("/unstable", get(unstable))
+        .route("/status/random", any(status::random))
+        .route("/throttle", any(throttle::throttle))
+        .route("/reset-mid-response/{after_bytes}", any(reset::reset_mid_response))
+        .route("/sleep-random", any(sleep_random::sleep_random))
+        .route("/compute", any(compute::compute))
+        .route("/flaky/{failures}", any(flaky::flaky))
+        .route("/ratelimit-headers", any(ratelimit_headers::ratelimit_headers))
+        .route("/retry-after", any(retry_after::retry_after))
+        .route("/method", any(method::method))
+        .route("/h2-pseudo", any(h2_pseudo::h2_pseudo))
+        .route("/host", any(host::reflect))
+        .route("/host-check", any(host::check))
+        .route("/http-version", any(http_version))
+        .route("/clock", any(clock::clock))
+        .route("/http-date", get(http_date::http_date))
+        .route("/conditional", any(conditional::conditional))
+        .route("/bytes/{n}", get(generate::bytes))
+        .route("/stream-bytes/{n}", get(generate::stream_bytes))
+        .route("/entropy", get(generate::entropy))
+        .route("/random-drop/{n}", get(random_drop::random_drop))
+        .route("/stream-abort", get(stream_abort::stream_abort))
+        .route("/stream-abort/log", get(stream_abort::stream_abort_log))
+        .route("/large-json/{n}", get(generate::large_json))
+        .route("/lorem", get(generate::lorem))
+        .route("/stream-json-fragments", get(json_fragments::stream_json_fragments))
+        .route("/alt-svc", any(alt_svc::alt_svc))
+        .route("/negotiate", any(negotiate::negotiate))
+        .route("/negotiate-encoding", any(negotiate::negotiate_encoding))
+        .route("/expect-100", any(expect::expect_100))
+        .route("/reject-upload", any(expect::reject_upload))
+        .route("/chunked-upload", any(chunked::chunked_upload))
+        .route("/drain", post(chunked::drain).put(chunked::drain))
+        .route("/jsonl", post(jsonl::jsonl))
+        .route("/request-trailers", any(trailers::request_trailers))
+        .route(
+            "/upload-etag",
+            put(upload_etag::create).layer(DefaultBodyLimit::max(config.max_body_bytes)),
+        )
+        .route(
+            "/idempotency-key",
+            post(idempotency::idempotency_key).layer(DefaultBodyLimit::max(config.max_body_bytes)),
+        )
+        .route(
+            "/upload-etag/{id}",
+            get(upload_etag::get)
+                .put(upload_etag::update)
+                .layer(DefaultBodyLimit::max(config.max_body_bytes)),
+        )
+        .route("/hash/{algorithm}", any(hash::hash))
+        .route("/checksum", any(hash::checksum))
+        .route("/sign", post(sign::sign).layer(DefaultBodyLimit::max(sign::MAX_PAYLOAD_BYTES)))
+        .merge(
+            Router::new()
+                .route(
+                    "/webhook",
+                    post(webhook::webhook).layer(DefaultBodyLimit::max(config.max_body_bytes)),
+                )
+                .route("/webhook/log", get(webhook::webhook_log))
+                .route("/webhook/clear", post(webhook::webhook_clear).delete(webhook::webhook_clear)),
+        )
         .route(
             "/delay/{n}",
             any(anything).layer({
@@ -141,6 +359,10 @@ fn app() -> Router<()> {
             }),
         )
         .route("/websocket/echo", any(ws::ws_handler))
+        .route("/binary-echo", any(ws::binary_echo))
+        .route("/websocket/subprotocol", any(ws::subprotocol))
+        .route("/websocket/close", any(ws::close))
+        .route("/websocket/mirror", any(ws::mirror))
         .route("/websocket/chat", any(ws_chat::ws_handler))
         .route(
             "/socket-io/chat",
@@ -150,22 +372,109 @@ fn app() -> Router<()> {
             Router::new()
                 .route("/openapi.json", get(|| async { include_str!("../openapi.json") }))
                 .route("/swagger-ui", get(|| async { Html(swagger_ui::swagger_ui_html("/openapi.json")) })),
-        );
+        )
+        .route("/admin/shutdown", post(admin::shutdown))
+        .route("/connections/by-ip", get(admin::connections_by_ip))
+        .route("/admin/har", get(har::har).delete(har::clear))
+        .route("/admin/mock", get(mock::list).post(mock::register).delete(mock::unregister))
+        .route("/mock", any(mock::serve))
+        .route("/mock/{*path}", any(mock::serve))
+        .route("/admin/rotate", put(rotate::configure).delete(rotate::reset))
+        .route("/admin/stats", delete(stats::reset))
+        .route("/rotate", any(rotate::rotate));
+
+    #[cfg(feature = "protobuf")]
+    {
+        router = router.route("/protobuf", post(protobuf::protobuf));
+    }
+
+    #[cfg(feature = "json-schema")]
+    {
+        router = router.route("/validate-json", post(json_schema::validate_json));
+    }
+
+    #[cfg(feature = "proxy")]
+    if !config.proxy_allowlist.is_empty() {
+        router = router.route("/proxy/{*url}", any(proxy::proxy));
+    }
+
+    #[cfg(feature = "charset")]
+    {
+        router = router.route("/charset/{name}", get(charset::charset));
+    }
+
+    #[cfg(feature = "tls")]
+    {
+        router = router.route("/tls-fingerprint", get(tls::tls_fingerprint));
+    }
+
+    #[cfg(feature = "graphql")]
+    {
+        router = router.route("/graphql", get(graphql::graphql).post(graphql::graphql));
+    }
+
+    #[cfg(feature = "webdav")]
+    {
+        let limit = DefaultBodyLimit::max(webdav::MAX_BYTES.min(config.max_body_bytes));
+        router = router
+            .route(webdav::PREFIX, any(webdav::dav).layer(limit))
+            .route("/dav/{*path}", any(webdav::dav).layer(limit));
+    }
 
     for format in ["gzip", "zstd", "br", "deflate"] {
-        router = router.route(
-            format!("/{format}").as_str(),
-            get(anything).layer(
-                ServiceBuilder::default()
-                    .layer(SetRequestHeaderLayer::overriding(ACCEPT_ENCODING, HeaderValue::from_static(format)))
-                    .layer(CompressionLayer::new()),
-            ),
-        );
+        router = router
+            .route(
+                format!("/{format}").as_str(),
+                get(move |Query(p): Query<compression::LevelParam>, request: Request| compression::compressed(format, p.level, request)),
+            )
+            .route(
+                format!("/{format}/{{level}}").as_str(),
+                get(move |Path(level): Path<i32>, request: Request| compression::compressed(format, Some(level), request)),
+            );
     }
 
     // router.merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", api))
 
+    router = router.route_layer(middleware::from_fn(record_route));
+
+    // inside the header layers, the served files and the vhost answers get `--add-header` and `--alt-svc` as the
+    // routes do
+    router = router
+        .layer(middleware::from_fn(static_file::serve))
+        .layer(middleware::from_fn(vhost::route));
+
+    if let Some(alt_svc) = &config.alt_svc {
+        router = router.layer(SetResponseHeaderLayer::if_not_present(ALT_SVC, alt_svc.clone()));
+    }
+
+    for (name, value) in &config.add_headers {
+        router = router.layer(if config.force_headers {
+            SetResponseHeaderLayer::overriding(name.clone(), value.clone())
+        } else {
+            SetResponseHeaderLayer::if_not_present(name.clone(), value.clone())
+        });
+    }
+
     router
+        .layer(middleware::from_fn(latency_profile::inject))
+        .layer(middleware::from_fn(limits::deadline))
+        .layer(middleware::from_fn(har::record))
+        .layer(middleware::from_fn(limits::header_size))
+        .layer(middleware::from_fn(limits::uri_length))
+        .layer(middleware::from_fn(stats::count))
+        .layer(middleware::from_fn(timing::stamp))
+        .layer(Extension(live))
+        .layer(Extension(config))
+        .layer(Extension(shutdown))
+        .layer(Extension(ready))
+}
+
+/// Records the route template on the request span of `server()`, which is created before the router matched it.
+async fn record_route(request: Request, next: middleware::Next) -> Response {
+    if let Some(path) = request.extensions().get::<MatchedPath>() {
+        tracing::Span::current().record("matched_path", path.as_str());
+    }
+    next.run(request).await
 }
 
 mod socket_io_chat;
@@ -173,28 +482,113 @@ mod swagger_ui;
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::registry()
+    let registry = tracing_subscriber::registry()
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| "DEBUG".into()))
-        .with(layer().json())
-        .init();
+        .with(layer().json());
+    #[cfg(feature = "otel")]
+    let registry = registry.with(otel::layer());
+    registry.init();
+
+    let config = Config::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(2);
+    });
+
+    match config.uds.clone() {
+        #[cfg(unix)]
+        Some(path) => {
+            // a socket left behind by a previous run would fail the bind
+            std::fs::remove_file(&path).ok();
+            let listener = tokio::net::UnixListener::bind(path).unwrap();
+            start_uds_server(listener, config).await;
+        }
+        _ => {
+            let port: u16 = std::env::var("PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(3000);
+            let listener = tokio::net::TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).await.unwrap();
+            start_server(listener, config).await;
+        }
+    }
+
+    #[cfg(feature = "otel")]
+    otel::shutdown();
+}
 
-    let port: u16 = std::env::var("PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(3000);
-    let listener = tokio::net::TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).await.unwrap();
-    start_server(listener).await;
+async fn shutdown_signal(shutdown: Shutdown) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => shutdown.trigger(),
+        _ = terminate => shutdown.trigger(),
+        _ = shutdown.wait() => {},
+    }
+    tracing::info!("shutting down, draining connections");
 }
 
-pub(crate) async fn start_server(listener: tokio::net::TcpListener) {
-    let router = app();
+/// Router wrapped in the server wide layers, with the side servers started, shared by the TCP and UDS listeners.
+async fn server(config: Config, ready: health::Ready) -> (Router, Shutdown) {
+    let shutdown = Shutdown::default();
+    let request_id_header = config.request_id_header.clone();
+    let compress_min_size = config.compress_min_size;
+    #[cfg(feature = "grpc")]
+    let grpc_port = config.grpc_port;
+    let live = latency_profile::Live::from_config(&config);
+    #[cfg(unix)]
+    if let Some(file) = &config.latency_profile {
+        tokio::spawn(latency_profile::reload_on_sighup(file.path.clone(), live.clone()));
+    }
+    let (server_header, date_header) = (config.server_header.clone(), config.date_header.clone());
+    let config = Arc::new(config);
+    let router = app_with_profile(config.clone(), shutdown.clone(), live, ready);
     let service = ServiceBuilder::default()
-        .compression()
-        .set_x_request_id(MakeRequestUuid)
-        .propagate_x_request_id()
-        .layer(TraceLayer::new_for_http().make_span_with(|request: &Request<Body>| {
-            let request_id = request.headers()["x-request-id"].to_str().ok();
-            let matched_path = request.extensions().get::<MatchedPath>().map(MatchedPath::as_str);
-            let method = request.method().as_str();
-            debug_span!("request_id", method, matched_path, request_id,)
+        .layer(middleware::from_fn(move |request: Request, next: middleware::Next| {
+            access::filter(config.clone(), request, next)
+        }))
+        .layer(CompressionLayer::new().compress_when(compression::Reported.and(negotiate::NotNegotiated)))
+        .layer(middleware::from_fn(move |request: Request, next: middleware::Next| {
+            compression::report(compress_min_size, request, next)
         }))
+        .set_request_id(request_id_header.clone(), MakeRequestUuid)
+        .propagate_request_id(request_id_header.clone())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(move |request: &Request<Body>| {
+                    let request_id = request.headers().get(&request_id_header).and_then(|it| it.to_str().ok());
+                    let method = request.method().as_str();
+                    let span = debug_span!(
+                        "request_id",
+                        method,
+                        // recorded by `record_route` once the router matched the request
+                        matched_path = tracing::field::Empty,
+                        request_id,
+                        status = tracing::field::Empty,
+                        otel.kind = "server",
+                    );
+                    #[cfg(feature = "otel")]
+                    otel::set_parent(&span, request.headers());
+                    span
+                })
+                .on_response(|response: &axum::http::Response<_>, latency: Duration, span: &tracing::Span| {
+                    let status = response.status().as_u16();
+                    span.record("status", status);
+                    tracing::debug!(status, latency = ?latency, "finished processing request");
+                }),
+        )
+        .layer(middleware::from_fn(preflight::bypass_cors))
         .layer(CorsLayer::very_permissive())
         .layer({
             async fn delay(request: Request, next: middleware::Next) -> impl IntoResponse {
@@ -211,22 +605,152 @@ pub(crate) async fn start_server(listener: tokio::net::TcpListener) {
         .layer(socket_io_chat::socket_io_layer())
         .layer(DefaultBodyLimit::disable());
 
-    let app = router.layer(service);
+    let mut app = router.layer(service);
+    // outside every other layer, so that preflights, socket.io and the 403 and 431 answers carry them too; hyper
+    // only adds a `Date` to responses that have none, it cannot be left out
+    if let Some(server) = server_header {
+        app = app.layer(SetResponseHeaderLayer::overriding(SERVER, server));
+    }
+    if let Some(date) = date_header {
+        app = app.layer(SetResponseHeaderLayer::overriding(DATE, date));
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(port) = grpc_port {
+        let listener = tokio::net::TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).await.unwrap();
+        eprintln!("Listening for gRPC on http://{}", listener.local_addr().unwrap());
+        tokio::spawn(grpc::serve(listener, shutdown.clone()));
+    }
+
+    LazyLock::force(&info::STARTED);
+    (app, shutdown)
+}
+
+pub(crate) async fn start_server(listener: tokio::net::TcpListener, config: Config) {
+    let options = serve::Options {
+        header_read_timeout: config.header_read_timeout,
+        keepalive_timeout: config.keepalive_timeout,
+        keepalive_max_requests: config.keepalive_max_requests,
+    };
+    #[cfg(feature = "http3")]
+    let (http3, max_body_bytes) = (config.http3, config.max_body_bytes);
+    #[cfg(feature = "tls")]
+    let scheme = if config.tls { "https" } else { "http" };
+    #[cfg(not(feature = "tls"))]
+    let scheme = "http";
+    let listener = listener::Listener::new(listener, &config).unwrap();
+    let addr = axum::serve::Listener::local_addr(&listener).unwrap();
+    let ready = health::Ready::default();
+    let (app, shutdown) = server(config, ready.clone()).await;
+
+    #[cfg(feature = "http3")]
+    let app = if http3 {
+        tokio::spawn(http3::serve(addr, app.clone(), max_body_bytes, shutdown.clone()));
+        let alt_svc = HeaderValue::from_str(&format!("h3=\":{}\"; ma=86400", addr.port())).unwrap();
+        app.layer(SetResponseHeaderLayer::if_not_present(ALT_SVC, alt_svc))
+    } else {
+        app
+    };
+
+    eprintln!("Listening on {scheme}://{addr}");
+    // the listener already accepts on its own task, accepted connections wait in its queue for serve
+    ready.set();
+    serve::serve(listener, app, options, shutdown_signal(shutdown), |_, addr: &SocketAddr| *addr).await;
+}
 
-    eprintln!("Listening on http://{}", listener.local_addr().unwrap());
-    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
-        .await
-        .unwrap();
+#[cfg(unix)]
+pub(crate) async fn start_uds_server(listener: tokio::net::UnixListener, config: Config) {
+    let options = serve::Options {
+        header_read_timeout: config.header_read_timeout,
+        keepalive_timeout: config.keepalive_timeout,
+        keepalive_max_requests: config.keepalive_max_requests,
+    };
+    let ready = health::Ready::default();
+    let (app, shutdown) = server(config, ready.clone()).await;
+    let app = app.layer(middleware::from_fn(uds::connect_info));
+    let path = listener.local_addr().unwrap().as_pathname().map(|path| path.display().to_string());
+    eprintln!("Listening on unix:{}", path.unwrap_or_default());
+    ready.set();
+    serve::serve(listener, app, options, shutdown_signal(shutdown), |io, _| {
+        uds::UdsConnectInfo::new(io)
+    })
+    .await;
 }
 
-async fn user_agent(user_agent: Option<TypedHeader<UserAgent>>) -> impl IntoResponse {
-    ErasedJson::pretty(data::UserAgent {
+async fn user_agent(pretty: Pretty, user_agent: Option<TypedHeader<UserAgent>>) -> impl IntoResponse {
+    pretty.json(data::UserAgent {
         user_agent: user_agent.map(|TypedHeader(h)| h.to_string()).unwrap_or_default(),
     })
 }
 
-async fn headers(header_map: HeaderMap) -> impl IntoResponse {
-    ErasedJson::pretty(get_headers(&header_map))
+async fn headers(pretty: Pretty, header_map: HeaderMap) -> impl IntoResponse {
+    pretty.json(get_headers(&header_map))
+}
+
+#[derive(Serialize)]
+struct ServerHeaders {
+    /// `null` without `--server-header`, no `Server` is sent then
+    server: Option<String>,
+    date: String,
+    /// whether `date` is the `--date-header` value rather than the clock
+    date_fixed: bool,
+}
+
+/// The `Server` and `Date` headers responses carry, as set by `--server-header` and `--date-header`.
+async fn server_headers(pretty: Pretty, Extension(config): Extension<Arc<Config>>) -> impl IntoResponse {
+    let value = |it: &HeaderValue| String::from_utf8_lossy(it.as_bytes()).into_owned();
+    pretty.json(ServerHeaders {
+        server: config.server_header.as_ref().map(value),
+        date: config
+            .date_header
+            .as_ref()
+            .map(value)
+            .unwrap_or_else(|| jiff::Timestamp::now().strftime("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+        date_fixed: config.date_header.is_some(),
+    })
+}
+
+#[derive(Serialize)]
+struct RawHeader {
+    name: String,
+    hex: String,
+    lossy: String,
+}
+
+#[derive(Serialize)]
+struct RawHeaders {
+    /// whether `headers` are in the order and casing they were sent in, see [`header_order::ordered`]
+    preserved: bool,
+    headers: Vec<RawHeader>,
+}
+
+/// Header values exactly as received, so bytes mangled by the UTF-8 reflection of `/headers` can be inspected, in
+/// the order and casing they were sent in when the connection recorded them.
+async fn raw_headers(
+    pretty: Pretty,
+    version: Version,
+    header_map: HeaderMap,
+    raw_heads: Option<Extension<header_order::RawHeads>>,
+    reuse: Option<Extension<serve::ConnectionReuse>>,
+) -> impl IntoResponse {
+    let (preserved, ordered) = header_order::ordered(version, &header_map, raw_heads, reuse);
+    let mut seen = HashMap::<String, usize>::new();
+    let headers = ordered
+        .into_iter()
+        .map(|(name, lossy)| {
+            // the recorded values went through a lossy conversion, the bytes come from the n-th value of that name
+            let nth = seen.entry(name.to_ascii_lowercase()).or_default();
+            let value = header_map.get_all(name.as_str()).iter().nth(*nth).map(HeaderValue::as_bytes);
+            *nth += 1;
+            let bytes = value.unwrap_or(lossy.as_bytes());
+            RawHeader {
+                hex: bytes.iter().map(|b| format!("{b:02x}")).collect(),
+                lossy: String::from_utf8_lossy(bytes).to_string(),
+                name,
+            }
+        })
+        .collect();
+    pretty.json(RawHeaders { preserved, headers })
 }
 
 #[derive(Debug, Validate, Deserialize)]
@@ -368,22 +892,162 @@ mod cookies {
         }
         (StatusCode::FOUND, (jar, Redirect::to("/cookies")))
     }
+
+    /// The cookies set by `/samesite-test`, by name and `SameSite` attribute.
+    const SAMESITE: [(&str, cookie::SameSite); 3] = [
+        ("samesite_strict", cookie::SameSite::Strict),
+        ("samesite_lax", cookie::SameSite::Lax),
+        ("samesite_none", cookie::SameSite::None),
+    ];
+
+    #[derive(Serialize)]
+    struct SameSiteSet {
+        set: Vec<String>,
+        report: &'static str,
+    }
+
+    #[derive(Serialize)]
+    struct SameSiteReport {
+        /// whether each cookie came back with this request
+        cookies: BTreeMap<&'static str, bool>,
+        /// how the browser classifies the request, `cross-site` is where the attributes differ
+        sec_fetch_site: Option<String>,
+    }
+
+    pub async fn samesite_test() -> impl IntoResponse {
+        let mut jar = CookieJar::new();
+        let mut set = Vec::new();
+        for (name, same_site) in SAMESITE {
+            // browsers drop `SameSite=None` cookies that are not `Secure`
+            let cookie = cookie::Cookie::build((name, "1"))
+                .path("/")
+                .same_site(same_site)
+                .secure(same_site == cookie::SameSite::None)
+                .build();
+            set.push(cookie.to_string());
+            jar = jar.add(cookie);
+        }
+        (
+            jar,
+            ErasedJson::pretty(SameSiteSet {
+                set,
+                report: "/samesite-test/report",
+            }),
+        )
+    }
+
+    pub async fn samesite_report(jar: CookieJar, header_map: HeaderMap) -> impl IntoResponse {
+        ErasedJson::pretty(SameSiteReport {
+            cookies: SAMESITE.iter().map(|(name, _)| (*name, jar.get(name).is_some())).collect(),
+            sec_fetch_site: header_map
+                .get("sec-fetch-site")
+                .map(|it| String::from_utf8_lossy(it.as_bytes()).into_owned()),
+        })
+    }
+
+    /// The largest `name=value` pair `/cookie-size-limit` sets, 16 times what browsers keep.
+    pub const MAX_COOKIE_BYTES: usize = 64 * 1024;
+    /// The most `Set-Cookie` bytes one `/cookie-size-limit` response carries.
+    pub const MAX_TOTAL_BYTES: usize = 1024 * 1024;
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct SizeLimitParam {
+        /// the size of each `name=value` pair, 4096 by default
+        #[garde(range(min = 16, max = MAX_COOKIE_BYTES))]
+        pub bytes: Option<usize>,
+        /// cookies of that size, `size_0` to `size_{count-1}`, 1 by default
+        #[garde(range(min = 1, max = 200))]
+        pub count: Option<usize>,
+    }
+
+    #[derive(Serialize)]
+    struct SizeLimitSet {
+        names: Vec<String>,
+        cookie_bytes: usize,
+        count: usize,
+        /// the size of the `Cookie` header value sending them all back, `; ` separated
+        cookie_header_bytes: usize,
+        report: &'static str,
+    }
+
+    /// Sets `count` cookies whose `name=value` is `bytes` long, to find where a client or a proxy truncates or
+    /// drops a cookie, or the `Cookie` header carrying them all. `/whoami` reports the length of each cookie sent
+    /// back. 400 past [`MAX_COOKIE_BYTES`] per cookie or [`MAX_TOTAL_BYTES`] in all.
+    pub async fn size_limit(Garde(Query(p)): Garde<Query<SizeLimitParam>>) -> Response {
+        let bytes = p.bytes.unwrap_or(4096);
+        let count = p.count.unwrap_or(1);
+        if bytes * count > MAX_TOTAL_BYTES {
+            let detail = format!("{count} cookies of {bytes} bytes are more than the {MAX_TOTAL_BYTES} bytes cap");
+            return (
+                StatusCode::BAD_REQUEST,
+                ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", detail)),
+            )
+                .into_response();
+        }
+        let mut jar = CookieJar::new();
+        let mut names = Vec::new();
+        for index in 0..count {
+            let name = format!("size_{index}");
+            let value = "a".repeat(bytes - name.len() - 1);
+            jar = jar.add(cookie::Cookie::build((name.clone(), value)).path("/").build());
+            names.push(name);
+        }
+        (
+            jar,
+            ErasedJson::pretty(SizeLimitSet {
+                names,
+                cookie_bytes: bytes,
+                count,
+                cookie_header_bytes: bytes * count + 2 * (count - 1),
+                report: "/whoami",
+            }),
+        )
+            .into_response()
+    }
 }
+/// Request target form as defined in RFC 9112 section 3.2.
+fn target_form(uri: &Uri) -> &'static str {
+    if uri.scheme().is_some() {
+        "absolute-form"
+    } else if uri.authority().is_some() {
+        "authority-form"
+    } else if uri.path() == "*" {
+        "asterisk-form"
+    } else {
+        "origin-form"
+    }
+}
+
 async fn anything(
+    pretty: Pretty,
     method: Method,
     uri: Uri,
     Query(query): Query<Vec<(String, Vec<String>)>>,
     header_map: HeaderMap,
     content_type: Option<TypedHeader<ContentType>>,
     InsecureClientIp(origin): InsecureClientIp,
+    peer: Option<Extension<data::PeerCred>>,
+    vhost: Option<Extension<vhost::Matched>>,
+    Extension(config): Extension<Arc<Config>>,
     body: Bytes,
 ) -> Response {
     let headers = get_headers(&header_map);
+    let (body, content_encoding) = match decompress::decode(&header_map, body, config.max_body_bytes) {
+        Ok(decoded) => decoded,
+        Err(response) => return response,
+    };
 
+    // repeated keys become an array in the order they were sent, unless `?flatten=true` keeps the last one
+    let flatten = query.iter().any(|(k, v)| k == "flatten" && v.last().is_some_and(|it| it == "true"));
     let mut queries = Queries::default();
     for (k, v) in query {
         let vec = queries.entry(k).or_default();
-        vec.extend(v);
+        if flatten {
+            vec.clear();
+            vec.extend(v.into_iter().last());
+        } else {
+            vec.extend(v);
+        }
     }
 
     let body_string = match std::str::from_utf8(&body) {
@@ -444,18 +1108,23 @@ async fn anything(
         }
     }
 
-    ErasedJson::pretty(Http {
-        method: method.to_string(),
-        uri: uri.to_string(),
-        headers,
-        origin: origin.into(),
-        args: queries,
-        data: body_string,
-        json,
-        form,
-        files,
-    })
-    .into_response()
+    pretty
+        .json(Http {
+            method: method.to_string(),
+            uri: uri.to_string(),
+            target_form: target_form(&uri),
+            headers,
+            origin: origin.into(),
+            peer: peer.map(|Extension(peer)| peer),
+            args: queries,
+            data: body_string,
+            json,
+            form,
+            files,
+            content_encoding,
+            vhost: vhost.map(|Extension(vhost::Matched(vhost))| vhost),
+        })
+        .into_response()
 }
 
 async fn index() -> Html<String> {
@@ -504,6 +1173,195 @@ async fn uuid() -> impl IntoResponse {
     })
 }
 
+/// Name-based UUIDv5, the namespace is a UUID or one of the RFC 9562 `dns`, `url`, `oid` and `x500` aliases.
+async fn uuid_v5(Path((namespace, name)): Path<(String, String)>) -> Response {
+    let namespace = match namespace.as_str() {
+        "dns" => Uuid::NAMESPACE_DNS,
+        "url" => Uuid::NAMESPACE_URL,
+        "oid" => Uuid::NAMESPACE_OID,
+        "x500" => Uuid::NAMESPACE_X500,
+        _ => match Uuid::try_parse(&namespace) {
+            Ok(namespace) => namespace,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(data::ErrorDetail::new(
+                        400,
+                        "Bad Request",
+                        format!("invalid namespace `{namespace}`: {e}"),
+                    )),
+                )
+                    .into_response();
+            }
+        },
+    };
+    ErasedJson::pretty(UuidResponse {
+        uuid: Uuid::new_v5(&namespace, name.as_bytes()).to_string(),
+    })
+    .into_response()
+}
+
+#[derive(Serialize)]
+struct UuidValidation {
+    value: String,
+    valid: bool,
+    /// `hyphenated`, `simple`, `braced` or `urn`
+    format: Option<&'static str>,
+    canonical: Option<String>,
+    urn: Option<String>,
+    variant: Option<&'static str>,
+    /// only for the RFC 9562 variant, which the version field belongs to
+    version: Option<usize>,
+    version_name: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Parses the segment like the `uuid` crate does, in the hyphenated, simple (32 hex digits), braced (`{…}`) or
+/// URN (`urn:uuid:…`) form, and reports its variant and version instead of only whether it is valid.
+async fn uuid_validate(Path(value): Path<String>) -> impl IntoResponse {
+    let uuid = match Uuid::try_parse(&value) {
+        Ok(uuid) => uuid,
+        Err(e) => {
+            return ErasedJson::pretty(UuidValidation {
+                value,
+                valid: false,
+                format: None,
+                canonical: None,
+                urn: None,
+                variant: None,
+                version: None,
+                version_name: None,
+                error: Some(e.to_string()),
+            });
+        }
+    };
+    let format = if value.starts_with('{') {
+        "braced"
+    } else if value.len() > 9 && value[..9].eq_ignore_ascii_case("urn:uuid:") {
+        "urn"
+    } else if value.len() == 32 {
+        "simple"
+    } else {
+        "hyphenated"
+    };
+    let variant = match uuid.get_variant() {
+        uuid::Variant::NCS => "NCS",
+        uuid::Variant::RFC4122 => "RFC 9562",
+        uuid::Variant::Microsoft => "Microsoft",
+        _ => "future",
+    };
+    let version = (uuid.get_variant() == uuid::Variant::RFC4122).then(|| uuid.get_version_num());
+    let version_name = match version {
+        _ if uuid.is_nil() => Some("nil"),
+        _ if uuid.as_u128() == u128::MAX => Some("max"),
+        Some(1) => Some("Gregorian time-based"),
+        Some(2) => Some("DCE security"),
+        Some(3) => Some("name-based MD5"),
+        Some(4) => Some("random"),
+        Some(5) => Some("name-based SHA-1"),
+        Some(6) => Some("reordered Gregorian time-based"),
+        Some(7) => Some("Unix time-based"),
+        Some(8) => Some("custom"),
+        _ => None,
+    };
+    ErasedJson::pretty(UuidValidation {
+        value,
+        valid: true,
+        format: Some(format),
+        canonical: Some(uuid.hyphenated().to_string()),
+        urn: Some(uuid.urn().to_string()),
+        variant: Some(variant),
+        version,
+        version_name,
+        error: None,
+    })
+}
+
+pub const MAX_BULK_UUIDS: usize = 100_000;
+/// Above this many the array is streamed in chunks of as many UUIDs, without a `Content-Length`.
+const BULK_UUIDS_CHUNK: usize = 1000;
+
+#[derive(Debug, Deserialize, Validate)]
+struct UuidBulkPath {
+    #[garde(range(min = 0, max = MAX_BULK_UUIDS))]
+    n: usize,
+}
+
+#[derive(Deserialize)]
+struct UuidBulkParam {
+    /// `4` (the default) or `7`
+    version: Option<u8>,
+    /// makes the random bits reproducible, and the UUIDv7 timestamps start at the Unix epoch
+    seed: Option<u64>,
+}
+
+/// A JSON array of `n` UUIDv4 or UUIDv7 strings, 400 past 100000. The UUIDv7s of a response count up in
+/// milliseconds from the time of the request, so they sort in the order they were generated.
+async fn uuid_bulk(Garde(Path(UuidBulkPath { n })): Garde<Path<UuidBulkPath>>, Query(p): Query<UuidBulkParam>) -> Response {
+    let version = p.version.unwrap_or(4);
+    if !matches!(version, 4 | 7) {
+        return (
+            StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(data::ErrorDetail::new(
+                400,
+                "Bad Request",
+                format!("invalid version {version}: only 4 and 7 are generated"),
+            )),
+        )
+            .into_response();
+    }
+    let start = match p.seed {
+        Some(_) => 0,
+        None => jiff::Timestamp::now().as_millisecond().max(0) as u64,
+    };
+    let mut rng = p.seed.map(fastrand::Rng::with_seed).unwrap_or_default();
+    let mut chunk = move |offset: usize| {
+        let uuids: Vec<_> = (offset..(offset + BULK_UUIDS_CHUNK).min(n))
+            .map(|index| {
+                let uuid = match version {
+                    4 => uuid::Builder::from_random_bytes(rng.u128(..).to_le_bytes()),
+                    _ => {
+                        let random: [u8; 10] = std::array::from_fn(|_| rng.u8(..));
+                        uuid::Builder::from_unix_timestamp_millis(start + index as u64, &random)
+                    }
+                };
+                format!("\"{}\"", uuid.into_uuid())
+            })
+            .collect();
+        let separator = if offset == 0 { "[" } else { "," };
+        let end = if offset + BULK_UUIDS_CHUNK >= n { "]" } else { "" };
+        format!("{separator}{}{end}", uuids.join(","))
+    };
+
+    let content_type = [(CONTENT_TYPE, APPLICATION_JSON.to_string())];
+    if n <= BULK_UUIDS_CHUNK {
+        let json = if n == 0 { "[]".to_string() } else { chunk(0) };
+        return (content_type, json).into_response();
+    }
+    let chunks = (0..n)
+        .step_by(BULK_UUIDS_CHUNK)
+        .map(move |offset| Ok::<_, std::convert::Infallible>(chunk(offset)));
+    (content_type, Body::from_stream(tokio_stream::iter(chunks))).into_response()
+}
+
+#[derive(Serialize)]
+struct CacheBust {
+    nonce: String,
+    timestamp: jiff::Timestamp,
+}
+
+/// Never cacheable: `no-store`, and a body that differs on every call.
+async fn cache_bust() -> impl IntoResponse {
+    (
+        [(CACHE_CONTROL, "no-store"), (PRAGMA, "no-cache")],
+        ErasedJson::pretty(CacheBust {
+            nonce: Uuid::new_v4().to_string(),
+            timestamp: jiff::Timestamp::now(),
+        }),
+    )
+}
+
 async fn response_headers(Query(query): Query<BTreeMap<String, Vec<String>>>) -> impl IntoResponse {
     let mut headers = HeaderMap::new();
 
@@ -525,6 +1383,40 @@ fn into_response(content_type: impl AsRef<str>, body: impl IntoResponse) -> Resp
     ([(CONTENT_TYPE, content_type.as_ref())], body).into_response()
 }
 
+mod health {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    /// Set by `start_server()` and `start_uds_server()` once the app is built and its listener accepts
+    /// connections, each app has its own.
+    #[derive(Clone, Default)]
+    pub struct Ready(Arc<AtomicBool>);
+
+    impl Ready {
+        pub fn set(&self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Status {
+        status: &'static str,
+    }
+
+    pub async fn health() -> impl IntoResponse {
+        ErasedJson::pretty(Status { status: "ok" })
+    }
+
+    pub async fn ready(Extension(ready): Extension<Ready>) -> Response {
+        if ready.0.load(Ordering::SeqCst) {
+            ErasedJson::pretty(Status { status: "ready" }).into_response()
+        } else {
+            (StatusCode::SERVICE_UNAVAILABLE, ErasedJson::pretty(Status { status: "starting" })).into_response()
+        }
+    }
+}
+
 mod resp_data {
     use super::*;
     pub async fn json() -> impl IntoResponse {
@@ -539,6 +1431,31 @@ mod resp_data {
     pub async fn forms_post() -> impl IntoResponse {
         into_response(TEXT_HTML_UTF_8, include_str!("../assets/forms_post.html"))
     }
+
+    #[derive(Deserialize, Default, Clone, Copy)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum InvalidJson {
+        #[default]
+        TrailingComma,
+        Unclosed,
+        BadEscape,
+    }
+
+    #[derive(Deserialize)]
+    pub struct InvalidJsonParam {
+        #[serde(default)]
+        kind: InvalidJson,
+    }
+
+    /// Malformed JSON labelled `application/json`, for the deserialization error paths of clients.
+    pub async fn invalid_json(Query(p): Query<InvalidJsonParam>) -> impl IntoResponse {
+        let body = match p.kind {
+            InvalidJson::TrailingComma => r#"{"slideshow": {"title": "Sample Slide Show", "slides": [1, 2, 3,],}}"#,
+            InvalidJson::Unclosed => r#"{"slideshow": {"title": "Sample Slide Show", "slides": [1, 2, 3"#,
+            InvalidJson::BadEscape => r#"{"slideshow": {"title": "Sample \q Slide Show \ud800"}}"#,
+        };
+        into_response(APPLICATION_JSON, body)
+    }
 }
 
 mod image {
@@ -584,8 +1501,11 @@ mod image {
     }
 }
 
-async fn ip(InsecureClientIp(origin): InsecureClientIp) -> impl IntoResponse {
-    ErasedJson::pretty(data::Ip { origin })
+async fn ip(pretty: Pretty, InsecureClientIp(origin): InsecureClientIp, peer: Option<Extension<data::PeerCred>>) -> impl IntoResponse {
+    pretty.json(data::Ip {
+        origin,
+        peer: peer.map(|Extension(peer)| peer),
+    })
 }
 
 #[derive(Serialize, Deserialize)]
@@ -607,46 +1527,649 @@ async fn bearer(header_map: HeaderMap) -> impl IntoResponse {
     }
 }
 
-mod redirect {
+mod status {
     use super::*;
 
-    pub async fn redirect(Path(n): Path<i32>) -> Response {
-        match n {
-            ..=0 => (StatusCode::BAD_REQUEST, bad_redirect_request()).into_response(),
-            1 => (StatusCode::FOUND, Redirect::to("/get")).into_response(),
-            2.. => (StatusCode::FOUND, Redirect::to(&format!("/redirect/{}", n - 1))).into_response(),
-        }
+    const SUCCESS: &[u16] = &[200, 201, 202, 203, 204, 205, 206, 207, 208, 226];
+    const REDIRECTION: &[u16] = &[300, 301, 302, 303, 304, 305, 307, 308];
+    const CLIENT_ERROR: &[u16] = &[
+        400, 401, 402, 403, 404, 405, 406, 407, 408, 409, 410, 411, 412, 413, 414, 415, 416, 417, 418, 421, 422, 423, 424, 425, 426, 428,
+        429, 431, 451,
+    ];
+    const SERVER_ERROR: &[u16] = &[500, 501, 502, 503, 504, 505, 506, 507, 508, 510, 511];
+
+    #[derive(Deserialize)]
+    pub struct RandomParam {
+        pub class: Option<String>,
     }
 
-    fn bad_redirect_request() -> ErasedJson {
-        ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", "redirect count must be > 0".to_string()))
+    /// Picks uniformly among the registered final status codes of the class, or of all classes.
+    /// 1xx codes are informational only and are never picked.
+    pub async fn random(Query(RandomParam { class }): Query<RandomParam>) -> Response {
+        let codes: Vec<u16> = match class.as_deref() {
+            None => [SUCCESS, REDIRECTION, CLIENT_ERROR, SERVER_ERROR].concat(),
+            Some("2xx") => SUCCESS.to_vec(),
+            Some("3xx") => REDIRECTION.to_vec(),
+            Some("4xx") => CLIENT_ERROR.to_vec(),
+            Some("5xx") => SERVER_ERROR.to_vec(),
+            Some(class) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(data::ErrorDetail::new(
+                        400,
+                        "Bad Request",
+                        format!("invalid status class: {class} not one of 2xx, 3xx, 4xx, 5xx"),
+                    )),
+                )
+                    .into_response();
+            }
+        };
+
+        let code = codes[fastrand::usize(..codes.len())];
+        status_response(StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))
     }
 
-    pub async fn relative_redirect(Path(n): Path<i32>) -> Response {
-        match n {
-            ..=0 => (StatusCode::BAD_REQUEST, bad_redirect_request()).into_response(),
-            1 => (StatusCode::FOUND, Redirect::to("/get")).into_response(),
-            2.. => (StatusCode::FOUND, [(LOCATION, format!("./{}", n - 1))]).into_response(),
+    /// A response for the status code, with the headers the status code calls for.
+    pub fn status_response(status: StatusCode) -> Response {
+        let mut headers = HeaderMap::new();
+        match status.as_u16() {
+            300..=303 | 305 | 307 | 308 => {
+                headers.insert(LOCATION, HeaderValue::from_static("/redirect/1"));
+            }
+            401 => {
+                headers.insert(WWW_AUTHENTICATE, HeaderValue::from_static(r#"Basic realm="Fake Realm""#));
+            }
+            405 => {
+                headers.insert(ALLOW, HeaderValue::from_static("GET"));
+            }
+            407 => {
+                headers.insert(PROXY_AUTHENTICATE, HeaderValue::from_static(r#"Basic realm="Fake Realm""#));
+            }
+            416 => {
+                headers.insert(CONTENT_RANGE, HeaderValue::from_static("bytes */0"));
+            }
+            426 => {
+                headers.insert(UPGRADE, HeaderValue::from_static("HTTP/2.0"));
+            }
+            429 | 503 => {
+                headers.insert(RETRY_AFTER, HeaderValue::from_static("1"));
+            }
+            _ => {}
         }
-    }
 
-    pub async fn absolute_redirect(Path(n): Path<i32>, uri: Uri, Host(host): Host, _req: Request) -> Response {
-        match n {
-            ..=0 => (StatusCode::BAD_REQUEST, bad_redirect_request()).into_response(),
-            1 => (StatusCode::FOUND, Redirect::to("/get")).into_response(),
-            2.. => (
-                StatusCode::FOUND,
-                Redirect::to(&format!(
-                    "{}://{host}/absolute-redirect/{}",
-                    uri.scheme_str().unwrap_or("http"),
-                    n - 1
-                )),
-            )
-                .into_response(),
+        match status.as_u16() {
+            204 | 205 | 304 => (status, headers).into_response(),
+            _ => (headers, data::ErrorDetail::response(status, "")).into_response(),
         }
     }
+}
 
-    #[derive(Debug, Deserialize, Validate)]
+mod header_size {
+    use super::*;
+
+    /// Upper bound for the summed size of the generated header names and values.
+    pub const MAX_TOTAL_BYTES: usize = 256 * 1024;
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct SetSizeParam {
+        #[garde(range(min = 0, max = 10_000))]
+        pub count: Option<usize>,
+        #[garde(range(min = 0, max = MAX_TOTAL_BYTES))]
+        pub size: Option<usize>,
+    }
+
+    #[derive(Serialize)]
+    struct SetSize {
+        count: usize,
+        size: usize,
+        total_bytes: usize,
+    }
+
+    /// `count` headers named `X-Large-Header-<i>` whose values are `size` bytes long.
+    pub async fn set_size(pretty: Pretty, Garde(Query(p)): Garde<Query<SetSizeParam>>) -> Response {
+        let count = p.count.unwrap_or(10);
+        let size = p.size.unwrap_or(1024);
+
+        let names: Vec<_> = (0..count).map(|i| format!("x-large-header-{i}")).collect();
+        let total_bytes = names.iter().map(|name| name.len() + size).sum();
+        if total_bytes > MAX_TOTAL_BYTES {
+            return (
+                StatusCode::BAD_REQUEST,
+                ErasedJson::pretty(data::ErrorDetail::new(
+                    400,
+                    "Bad Request",
+                    format!("{total_bytes} header bytes requested, at most {MAX_TOTAL_BYTES} are allowed"),
+                )),
+            )
+                .into_response();
+        }
+
+        let value = HeaderValue::from_str(&"a".repeat(size)).unwrap_or(HeaderValue::from_static(""));
+        let mut headers = HeaderMap::new();
+        for name in names {
+            if let Ok(name) = HeaderName::from_str(&name) {
+                headers.insert(name, value.clone());
+            }
+        }
+
+        (headers, pretty.json(SetSize { count, size, total_bytes })).into_response()
+    }
+
+    /// Leaves 512 bytes of an 8KiB buffer for the status line and the headers added by the server.
+    pub const NEAR_LIMIT_BYTES: usize = 8 * 1024 - 512;
+    /// Length of a `x-pad-NNN: \r\n` line without its value.
+    const PAD_OVERHEAD: usize = "x-pad-000: \r\n".len();
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct NearLimitParam {
+        #[garde(range(min = 0, max = MAX_TOTAL_BYTES))]
+        pub bytes: Option<usize>,
+    }
+
+    #[derive(Serialize)]
+    struct NearLimit {
+        bytes: usize,
+        headers: usize,
+        padding_bytes: usize,
+    }
+
+    /// `x-pad-NNN` headers of at most 1KiB per line summing to `bytes`, counted as `name: value\r\n`.
+    pub async fn near_limit(pretty: Pretty, Garde(Query(p)): Garde<Query<NearLimitParam>>) -> Response {
+        let bytes = p.bytes.unwrap_or(NEAR_LIMIT_BYTES);
+        let count = bytes.div_ceil(1024);
+        let values = bytes.saturating_sub(count * PAD_OVERHEAD);
+        // too little room for even one line
+        let count = if values == 0 && bytes < PAD_OVERHEAD { 0 } else { count };
+
+        let mut headers = HeaderMap::new();
+        for i in 0..count {
+            // spread the remainder over the first lines
+            let size = values / count + usize::from(i < values % count);
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_str(&format!("x-pad-{i:03}")),
+                HeaderValue::from_str(&"a".repeat(size)),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+        let padding_bytes = headers.iter().map(|(name, value)| name.as_str().len() + value.len() + 4).sum();
+
+        (
+            headers,
+            pretty.json(NearLimit {
+                bytes,
+                headers: count,
+                padding_bytes,
+            }),
+        )
+            .into_response()
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct DuplicatesParam {
+        #[garde(range(min = 1, max = 100))]
+        pub count: Option<usize>,
+    }
+
+    #[derive(Serialize)]
+    struct Duplicates {
+        count: usize,
+        set_cookie: Vec<String>,
+        x_duplicate: Vec<String>,
+    }
+
+    /// `count` `Set-Cookie` and `X-Duplicate` header lines each, listed in the body for comparison with what
+    /// the client kept. A repeated `Set-Cookie` cannot be joined with commas, so it is never folded.
+    pub async fn duplicates(pretty: Pretty, Garde(Query(p)): Garde<Query<DuplicatesParam>>) -> Response {
+        let count = p.count.unwrap_or(3);
+        let set_cookie: Vec<_> = (1..=count).map(|i| format!("dup{i}=value{i}; Path=/")).collect();
+        let x_duplicate: Vec<_> = (1..=count).map(|i| format!("value{i}")).collect();
+
+        let mut headers = HeaderMap::new();
+        for value in &set_cookie {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.append(SET_COOKIE, value);
+            }
+        }
+        for value in &x_duplicate {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.append(HeaderName::from_static("x-duplicate"), value);
+            }
+        }
+
+        (
+            headers,
+            pretty.json(Duplicates {
+                count,
+                set_cookie,
+                x_duplicate,
+            }),
+        )
+            .into_response()
+    }
+}
+
+mod connection {
+    use axum::http::Version;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct ConnectionBehavior {
+        protocol: String,
+        connection: &'static str,
+    }
+
+    /// `Connection` is a hop-by-hop HTTP/1.x header, HTTP/2 and later drop it and keep the connection open.
+    fn connection_response(version: Version, connection: &'static str) -> Response {
+        (
+            [(CONNECTION, connection)],
+            ErasedJson::pretty(ConnectionBehavior {
+                protocol: format!("{version:?}"),
+                connection,
+            }),
+        )
+            .into_response()
+    }
+
+    pub async fn close(version: Version) -> Response {
+        connection_response(version, "close")
+    }
+
+    pub async fn keep_alive(version: Version) -> Response {
+        connection_response(version, "keep-alive")
+    }
+
+    #[derive(Serialize)]
+    struct Reuse {
+        protocol: String,
+        connection_id: Option<u64>,
+        requests_on_connection: Option<u64>,
+        reused: bool,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Http10Param {
+        #[serde(default)]
+        pub keep_alive: bool,
+    }
+
+    #[derive(Serialize)]
+    struct Http10 {
+        request_protocol: String,
+        response_protocol: &'static str,
+        connection: &'static str,
+    }
+
+    /// Answers with an `HTTP/1.0` status line, a `Content-Length` instead of chunked encoding and
+    /// `Connection: close`, unless `?keep_alive=true` opts in to a persistent HTTP/1.0 connection.
+    pub async fn http10(version: Version, Query(p): Query<Http10Param>) -> Response {
+        let connection = if p.keep_alive { "keep-alive" } else { "close" };
+        let body = serde_json::to_vec_pretty(&Http10 {
+            request_protocol: format!("{version:?}"),
+            response_protocol: "HTTP/1.0",
+            connection,
+        })
+        .unwrap_or_default();
+        let mut response = (
+            [(CONNECTION, connection.to_string()), (CONTENT_LENGTH, body.len().to_string())],
+            body,
+        )
+            .into_response();
+        // hyper writes the status line with the response version, HTTP/2 and HTTP/3 ignore it
+        if matches!(version, Version::HTTP_10 | Version::HTTP_11) {
+            *response.version_mut() = Version::HTTP_10;
+        }
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        response
+    }
+
+    #[derive(Serialize)]
+    struct SocketOpts {
+        /// `null` when the request did not come over TCP
+        tcp: Option<listener::SocketOptions>,
+    }
+
+    pub async fn socket_opts(options: Option<Extension<listener::SocketOptions>>) -> Response {
+        ErasedJson::pretty(SocketOpts {
+            tcp: options.map(|Extension(options)| options),
+        })
+        .into_response()
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct SlowFrameParam {
+        #[garde(range(min = 0, max = 1000))]
+        pub delay_ms: Option<u64>,
+        #[garde(range(min = 1, max = 64 * 1024))]
+        pub chunk_size: Option<usize>,
+    }
+
+    #[derive(Serialize)]
+    struct SlowFrame {
+        protocol: String,
+        /// whether the response is actually written in pieces, only HTTP/1 over TCP is
+        split: bool,
+        delay_ms: u64,
+        chunk_size: usize,
+        #[serde(flatten)]
+        headers: data::Headers,
+    }
+
+    /// Reflects the request headers, writing the status line, every header line, the empty line and then
+    /// each `chunk_size` bytes of the body in a separate TCP write, `delay_ms` apart.
+    pub async fn slow_frame(
+        version: Version,
+        header_map: HeaderMap,
+        slow_frame: Option<Extension<listener::SlowFrame>>,
+        Garde(Query(p)): Garde<Query<SlowFrameParam>>,
+    ) -> Response {
+        let delay_ms = p.delay_ms.unwrap_or(10);
+        let chunk_size = p.chunk_size.unwrap_or(16);
+        let slow_frame = slow_frame.filter(|_| matches!(version, Version::HTTP_10 | Version::HTTP_11));
+        if let Some(Extension(slow_frame)) = &slow_frame {
+            slow_frame.enable(Duration::from_millis(delay_ms), chunk_size);
+        }
+        let body = ErasedJson::pretty(SlowFrame {
+            protocol: format!("{version:?}"),
+            split: slow_frame.is_some(),
+            delay_ms,
+            chunk_size,
+            headers: get_headers(&header_map),
+        });
+        // the connection keeps splitting its writes, so it is not reused
+        ([(CONNECTION, "close")], body).into_response()
+    }
+
+    #[derive(Debug, Deserialize, Clone, Copy)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Mismatch {
+        /// `Content-Length` below the body size, the rest reads as the start of the next response
+        Short,
+        /// `Content-Length` above the body size, the connection closes before that much arrives
+        Long,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct MismatchParam {
+        #[garde(skip)]
+        pub mode: Mismatch,
+        #[garde(range(min = 1, max = 64 * 1024))]
+        pub size: Option<usize>,
+        #[garde(range(min = 1, max = 64 * 1024))]
+        pub by: Option<usize>,
+    }
+
+    /// Sends `size` bytes (64 by default) of body under a `Content-Length` `by` bytes (16 by default) shorter or
+    /// longer. hyper always frames a body by its actual size, so the head is rewritten as it is written to the
+    /// socket, which only an HTTP/1 connection over TCP allows; `X-Misframed` says whether it was.
+    pub async fn content_length_mismatch(
+        version: Version,
+        misframed_length: Option<Extension<listener::MisframedLength>>,
+        Garde(Query(p)): Garde<Query<MismatchParam>>,
+    ) -> Response {
+        let size = p.size.unwrap_or(64);
+        let by = p.by.unwrap_or(16);
+        let content_length = match p.mode {
+            Mismatch::Short if by > size => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", "`by` is larger than `size`")),
+                )
+                    .into_response();
+            }
+            Mismatch::Short => size - by,
+            Mismatch::Long => size + by,
+        };
+        let misframed_length = misframed_length.filter(|_| matches!(version, Version::HTTP_10 | Version::HTTP_11));
+        if let Some(Extension(misframed_length)) = &misframed_length {
+            misframed_length.enable(content_length as u64);
+        }
+        let body: Vec<u8> = (b'a'..=b'z').cycle().take(size).collect();
+        let mut response = (
+            [
+                (CONNECTION, HeaderValue::from_static("close")),
+                (CONTENT_TYPE, HeaderValue::from_static("text/plain")),
+                (HeaderName::from_static("x-body-bytes"), size.into()),
+                (
+                    HeaderName::from_static("x-misframed"),
+                    HeaderValue::from_static(if misframed_length.is_some() { "true" } else { "false" }),
+                ),
+            ],
+            body,
+        )
+            .into_response();
+        // a compressed body would be chunked, without a `Content-Length` to rewrite
+        response.extensions_mut().insert(negotiate::Negotiated);
+        response
+    }
+
+    #[derive(Deserialize)]
+    pub struct NotModifiedParam {
+        #[serde(default)]
+        pub broken: bool,
+    }
+
+    const NOT_MODIFIED_BODY: &str = "this body of a 304 response must not be read\n";
+
+    /// A 304 Not Modified without a body, as RFC 9110 requires. With `broken=true` the response is a 200 with a
+    /// body whose status line is rewritten to 304 on the socket, which only an HTTP/1 connection over TCP allows;
+    /// `X-Broken` says whether it was. A client reading the body anyway takes it for the next response.
+    pub async fn not_modified(
+        version: Version,
+        misframed_length: Option<Extension<listener::MisframedLength>>,
+        Query(p): Query<NotModifiedParam>,
+    ) -> Response {
+        let misframed_length = misframed_length.filter(|_| p.broken && matches!(version, Version::HTTP_10 | Version::HTTP_11));
+        let headers = [
+            (ETAG, HeaderValue::from_static("\"304-without-body\"")),
+            (
+                HeaderName::from_static("x-broken"),
+                HeaderValue::from_static(if misframed_length.is_some() { "true" } else { "false" }),
+            ),
+        ];
+        let Some(Extension(misframed_length)) = misframed_length else {
+            return (StatusCode::NOT_MODIFIED, headers).into_response();
+        };
+        misframed_length.enable_status(if version == Version::HTTP_10 {
+            "HTTP/1.0 304 Not Modified"
+        } else {
+            "HTTP/1.1 304 Not Modified"
+        });
+        let mut response = (headers, [(CONTENT_TYPE, "text/plain")], NOT_MODIFIED_BODY).into_response();
+        // a compressed body would be chunked instead of announced by `Content-Length`
+        response.extensions_mut().insert(negotiate::Negotiated);
+        response
+    }
+
+    #[derive(Serialize)]
+    struct KeepAlive {
+        protocol: String,
+        /// seconds, `null` when idle connections are kept open
+        timeout: Option<f64>,
+        max_requests: Option<u64>,
+        /// this request on its connection, the first is `1`
+        request: Option<u64>,
+        /// requests left on the connection after this one, `null` without a maximum
+        remaining: Option<u64>,
+    }
+
+    /// The `--keepalive-timeout` and `--keepalive-max-requests` the server closes connections at, and where this
+    /// request stands. HTTP/1 responses also announce them in `Keep-Alive: timeout=75, max=99`.
+    pub async fn keepalive_timeout(
+        version: Version,
+        Extension(config): Extension<Arc<Config>>,
+        reuse: Option<Extension<serve::ConnectionReuse>>,
+    ) -> Response {
+        let request = reuse.map(|Extension(reuse)| reuse.request);
+        ErasedJson::pretty(KeepAlive {
+            protocol: format!("{version:?}"),
+            timeout: config.keepalive_timeout.map(|it| it.as_secs_f64()),
+            max_requests: config.keepalive_max_requests,
+            request,
+            remaining: config
+                .keepalive_max_requests
+                .zip(request)
+                .map(|(max, request)| max.saturating_sub(request)),
+        })
+        .into_response()
+    }
+
+    /// Requests multiplexed on one HTTP/2 or HTTP/3 connection share its counter too.
+    pub async fn reuse(version: Version, reuse: Option<Extension<serve::ConnectionReuse>>) -> Response {
+        let reuse = reuse.map(|Extension(reuse)| reuse);
+        ErasedJson::pretty(Reuse {
+            protocol: format!("{version:?}"),
+            connection_id: reuse.map(|it| it.connection_id),
+            requests_on_connection: reuse.map(|it| it.request),
+            reused: reuse.is_some_and(|it| it.request > 1),
+        })
+        .into_response()
+    }
+
+    #[derive(Serialize)]
+    struct ConnectionInfo {
+        protocol: String,
+        /// `tcp`, `unix` or `quic`, `null` when the server cannot tell
+        transport: Option<&'static str>,
+        /// the ALPN protocol id the client negotiated, with the TLS of HTTP/3 or in front of a TLS terminating proxy
+        alpn: Option<String>,
+        /// `tls`, or `proxy-protocol` for the ALPN TLV of a PROXY v2 header
+        alpn_source: Option<&'static str>,
+        /// the ALPN protocol id of the HTTP version the request used
+        expected_alpn: &'static str,
+        /// `null` without ALPN: plain text connections pick HTTP/2 by prior knowledge instead
+        alpn_mismatch: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        warning: Option<String>,
+        connection_id: Option<u64>,
+        request: Option<u64>,
+    }
+
+    fn alpn_of(version: Version) -> &'static str {
+        match version {
+            Version::HTTP_09 => "http/0.9",
+            Version::HTTP_10 => "http/1.0",
+            Version::HTTP_11 => "http/1.1",
+            Version::HTTP_2 => "h2",
+            Version::HTTP_3 => "h3",
+            _ => "",
+        }
+    }
+
+    /// The transport and protocol of the connection, flagging a request whose HTTP version is not the one the
+    /// client negotiated with ALPN. `http/1.1` also covers HTTP/1.0 requests, as RFC 9112 lets a client send them.
+    pub async fn connection(
+        version: Version,
+        tls: Option<Extension<data::TlsInfo>>,
+        proxied: Option<Extension<proxy_protocol::ProxiedAlpn>>,
+        socket_options: Option<Extension<listener::SocketOptions>>,
+        unix_peer: Option<Extension<data::PeerCred>>,
+        reuse: Option<Extension<serve::ConnectionReuse>>,
+    ) -> Response {
+        let transport = match (&tls, &socket_options, &unix_peer) {
+            (Some(_), _, _) => Some("quic"),
+            (_, Some(_), _) => Some("tcp"),
+            (_, _, Some(_)) => Some("unix"),
+            _ => None,
+        };
+        let (alpn, alpn_source) = match (tls.and_then(|Extension(tls)| tls.alpn), proxied) {
+            (Some(alpn), _) => (Some(alpn), Some("tls")),
+            (None, Some(Extension(proxy_protocol::ProxiedAlpn(alpn)))) => (Some(alpn), Some("proxy-protocol")),
+            (None, None) => (None, None),
+        };
+        let expected_alpn = alpn_of(version);
+        let alpn_mismatch = alpn.as_deref().map(|alpn| match alpn {
+            "http/1.1" => !matches!(version, Version::HTTP_10 | Version::HTTP_11),
+            alpn => alpn != expected_alpn,
+        });
+        let warning = alpn.as_deref().filter(|_| alpn_mismatch == Some(true)).map(|alpn| {
+            let warning = format!("the client negotiated `{alpn}` with ALPN but sent a {version:?} request");
+            tracing::warn!("{warning}");
+            warning
+        });
+        let reuse = reuse.map(|Extension(reuse)| reuse);
+        ErasedJson::pretty(ConnectionInfo {
+            protocol: format!("{version:?}"),
+            transport,
+            alpn,
+            alpn_source,
+            expected_alpn,
+            alpn_mismatch,
+            warning,
+            connection_id: reuse.map(|it| it.connection_id),
+            request: reuse.map(|it| it.request),
+        })
+        .into_response()
+    }
+}
+
+mod redirect {
+    use tower::ServiceExt as _;
+
+    use super::*;
+
+    pub async fn redirect(Path(n): Path<i32>) -> Response {
+        match n {
+            ..=0 => (StatusCode::BAD_REQUEST, bad_redirect_request()).into_response(),
+            1 => (StatusCode::FOUND, Redirect::to("/get")).into_response(),
+            2.. => (StatusCode::FOUND, Redirect::to(&format!("/redirect/{}", n - 1))).into_response(),
+        }
+    }
+
+    fn bad_redirect_request() -> ErasedJson {
+        ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", "redirect count must be > 0".to_string()))
+    }
+
+    pub async fn relative_redirect(Path(n): Path<i32>) -> Response {
+        match n {
+            ..=0 => (StatusCode::BAD_REQUEST, bad_redirect_request()).into_response(),
+            1 => (StatusCode::FOUND, Redirect::to("/get")).into_response(),
+            2.. => (StatusCode::FOUND, [(LOCATION, format!("./{}", n - 1))]).into_response(),
+        }
+    }
+
+    pub async fn absolute_redirect(Path(n): Path<i32>, uri: Uri, Host(host): Host, _req: Request) -> Response {
+        match n {
+            ..=0 => (StatusCode::BAD_REQUEST, bad_redirect_request()).into_response(),
+            1 => (StatusCode::FOUND, Redirect::to("/get")).into_response(),
+            2.. => (
+                StatusCode::FOUND,
+                Redirect::to(&format!(
+                    "{}://{host}/absolute-redirect/{}",
+                    uri.scheme_str().unwrap_or("http"),
+                    n - 1
+                )),
+            )
+                .into_response(),
+        }
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct LoopParam {
+        #[garde(range(min = 0, max = 100))]
+        n: u32,
+    }
+
+    /// Redirects to the same path with `?n=` one lower, keeping the other query parameters, and answers like
+    /// `/get` once `n` reaches 0. Every hop differs only in its query, unlike `/redirect/{n}` whose path changes.
+    pub async fn relative_loop(Garde(Query(p)): Garde<Query<LoopParam>>, uri: Uri, request: Request) -> Response {
+        if p.n == 0 {
+            return get(anything).oneshot(request).await.into_response();
+        }
+        let query: Vec<_> = form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+            .filter(|(name, _)| name != "n")
+            .collect();
+        let location = form_urlencoded::Serializer::new(String::from("?"))
+            .append_pair("n", &(p.n - 1).to_string())
+            .extend_pairs(query)
+            .finish();
+        (StatusCode::FOUND, [(LOCATION, location)]).into_response()
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
     pub struct Params {
         #[garde(length(min = 0))]
         url: String,
@@ -654,8 +2177,64 @@ mod redirect {
         status_code: Option<u16>,
     }
 
-    pub async fn redirect_to(Garde(Query(p)): Garde<Query<Params>>) -> Response {
+    /// The host a browser would follow `url` to, `None` for a target on the same origin. Browsers skip leading
+    /// spaces and control characters, drop tabs and newlines, take `\` for `/`, and read `https:host` and
+    /// `https:/host` as `https://host`, so the check does too instead of trusting a URL parser.
+    fn target_host(url: &str) -> Result<Option<String>, String> {
+        let url: String = url
+            .trim_start_matches(|c: char| c <= ' ')
+            .chars()
+            .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+            .map(|c| if c == '\\' { '/' } else { c })
+            .collect();
+        let authority = if let Some(rest) = url.strip_prefix("//") {
+            rest
+        } else {
+            let scheme = url.split_once(':').map(|(scheme, _)| scheme).filter(|scheme| {
+                scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            });
+            match scheme {
+                None => return Ok(None),
+                Some(scheme) if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") => {
+                    url[scheme.len() + 1..].trim_start_matches('/')
+                }
+                Some(scheme) => return Err(format!("the `{scheme}:` scheme is not allowed, only http and https")),
+            }
+        };
+        let authority = authority.split(['/', '?', '#']).next().unwrap_or_default();
+        let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        let host = match host.strip_prefix('[') {
+            Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+            None => host.split(':').next().unwrap_or_default(),
+        };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        match host.is_empty() {
+            true => Err("the redirect target has no host".to_string()),
+            false => Ok(Some(host)),
+        }
+    }
+
+    /// Redirects to `url` with `status_code`, 302 by default. With `--redirect-allowlist`, a `url` leading to
+    /// another host than those listed is answered 400 rather than followed, relative ones always are.
+    pub async fn redirect_to(Extension(config): Extension<Arc<Config>>, Garde(Query(p)): Garde<Query<Params>>) -> Response {
         let Params { url, status_code } = p;
+        if !config.redirect_allowlist.is_empty() {
+            let detail = match target_host(&url) {
+                Ok(Some(host)) if !config::host_allowed(&config.redirect_allowlist, &host) => {
+                    Some(format!("the redirect target host `{host}` is not in --redirect-allowlist"))
+                }
+                Ok(_) => None,
+                Err(detail) => Some(detail),
+            };
+            if let Some(detail) = detail {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", detail)),
+                )
+                    .into_response();
+            }
+        }
         let status_code = status_code.unwrap_or(302);
         (
             StatusCode::from_u16(status_code)
@@ -684,6 +2263,7 @@ mod base_64 {
 }
 
 mod sse {
+    use axum::response::sse::KeepAlive;
     use jiff::SignedDuration;
 
     use super::*;
@@ -695,26 +2275,1910 @@ mod sse {
         pub duration: Option<SignedDuration>,
         #[serde(default)]
         pub delay: Option<SignedDuration>,
+        /// a `:` comment line whenever no event was sent for this long, off by default
+        #[serde(default)]
+        pub ping: Option<SignedDuration>,
     }
 
-    pub async fn sse_handler(Query(SeeParam { delay, duration, count }): Query<SeeParam>) -> Response {
+    /// The shortest `?ping=` interval, shorter ones would flood the stream with comments.
+    pub const MIN_PING: Duration = Duration::from_millis(10);
+
+    /// Reconnection time sent to clients whose `Last-Event-ID` could not be resumed.
+    const RETRY: Duration = Duration::from_secs(3);
+
+    pub async fn sse_handler(
+        Query(SeeParam {
+            delay,
+            duration,
+            count,
+            ping,
+        }): Query<SeeParam>,
+        headers: HeaderMap,
+    ) -> Response {
         use tokio_stream::StreamExt as _;
+        let count = count.unwrap_or(10_usize);
+        let ping = ping.map(SignedDuration::unsigned_abs);
+        if ping.is_some_and(|ping| ping < MIN_PING) {
+            let detail = format!("`ping` must be at least {MIN_PING:?}");
+            return (
+                StatusCode::BAD_REQUEST,
+                ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", detail)),
+            )
+                .into_response();
+        }
+        // Event ids run from 1 to `count`, a reconnecting EventSource resumes right after the last id it saw.
+        let last_event_id = headers
+            .get("last-event-id")
+            .map(|id| id.to_str().ok().and_then(|id| id.trim().parse::<usize>().ok()));
+        let (start, restarted) = match last_event_id {
+            None => (1, false),
+            Some(Some(id @ 1..)) if id <= count => (id + 1, false),
+            Some(_) => (1, true),
+        };
+
         tokio::time::sleep(delay.unwrap_or(SignedDuration::ZERO).unsigned_abs()).await;
         let sec = duration.unwrap_or(SignedDuration::from_secs(1)).unsigned_abs().as_secs_f32();
-        let stream = tokio_stream::iter(1..)
-            .take(count.unwrap_or(10_usize))
+        let stream = tokio_stream::iter(start..=count)
             .throttle(Duration::from_secs_f32(sec))
-            .map(|id| {
+            .map(move |id| {
                 let timestamp = jiff::Timestamp::now().as_millisecond();
-                #[allow(clippy::useless_conversion)]
-                Event::default()
+                let mut event = Event::default()
+                    .id(id.to_string())
                     .data(serde_json::to_string(&data::SseData { id, timestamp }).unwrap_or_default())
-                    .event("ping")
-                    .try_into()
+                    .event("ping");
+                if restarted && id == start {
+                    event = event.retry(RETRY);
+                }
+                #[allow(clippy::useless_conversion)]
+                event.try_into()
+            });
+
+        match ping {
+            // an empty comment, `:` alone on its line
+            Some(ping) => Sse::new(stream).keep_alive(KeepAlive::new().interval(ping)).into_response(),
+            None => Sse::new(stream).into_response(),
+        }
+    }
+
+    pub const MAX_TICKS: usize = 1000;
+
+    #[derive(Deserialize, Validate)]
+    pub struct SseJsonParam {
+        #[garde(range(min = 0, max = MAX_TICKS))]
+        pub ticks: Option<usize>,
+        /// a `status` event after every this many ticks, besides the one at the start
+        #[garde(range(min = 1, max = MAX_TICKS))]
+        pub status_every: Option<usize>,
+        /// pause between events
+        #[garde(skip)]
+        #[serde(default)]
+        pub duration: Option<SignedDuration>,
+    }
+
+    /// Named events with JSON data: `status` `{"state":"started"}`, `ticks` (3 by default) `tick` events, a
+    /// `status` `{"state":"running"}` after every `status_every` ticks, and a final `done` before the stream ends.
+    pub async fn sse_json(Garde(Query(p)): Garde<Query<SseJsonParam>>) -> Response {
+        use tokio_stream::StreamExt as _;
+        let ticks = p.ticks.unwrap_or(3);
+        let mut events = vec![("status", serde_json::json!({"state": "started", "ticks": ticks}))];
+        for tick in 1..=ticks {
+            events.push(("tick", serde_json::json!({"tick": tick, "of": ticks})));
+            if p.status_every.is_some_and(|every| tick % every == 0 && tick < ticks) {
+                events.push((
+                    "status",
+                    serde_json::json!({"state": "running", "ticks_sent": tick, "ticks_left": ticks - tick}),
+                ));
+            }
+        }
+        events.push(("done", serde_json::json!({"ticks": ticks})));
+
+        let pause = p.duration.unwrap_or(SignedDuration::from_secs(1)).unsigned_abs();
+        let stream = tokio_stream::iter(events.into_iter().enumerate())
+            .throttle(pause)
+            .map(|(index, (name, mut data))| {
+                data["timestamp"] = jiff::Timestamp::now().as_millisecond().into();
+                Ok::<_, std::convert::Infallible>(Event::default().id((index + 1).to_string()).event(name).data(data.to_string()))
             });
 
         Sse::new(stream).into_response()
     }
+
+    pub const MAX_PROGRESS_DURATION: SignedDuration = SignedDuration::from_secs(300);
+
+    #[derive(Deserialize, Validate)]
+    pub struct DelayProgressParam {
+        /// how long the operation takes, 5s by default and at most 5 minutes
+        #[garde(skip)]
+        #[serde(default)]
+        pub duration: Option<SignedDuration>,
+        /// `progress` events after the one at 0%
+        #[garde(range(min = 1, max = 100))]
+        pub steps: Option<u32>,
+    }
+
+    /// The simulated operation, stopped when the client goes away and the stream is dropped before the end.
+    struct Operation {
+        step: u32,
+        steps: u32,
+    }
+
+    impl Drop for Operation {
+        fn drop(&mut self) {
+            if self.step < self.steps {
+                tracing::debug!(step = self.step, steps = self.steps, "client disconnected, operation stopped");
+            }
+        }
+    }
+
+    /// A long operation of `duration` reporting its progress: `progress` events `{"percent"}` from 0 to 100 in
+    /// `steps` (10 by default) evenly spaced steps, then a `result` event before the stream ends.
+    pub async fn delay_progress(Garde(Query(p)): Garde<Query<DelayProgressParam>>) -> Response {
+        use tokio_stream::StreamExt as _;
+        let duration = p.duration.unwrap_or(SignedDuration::from_secs(5));
+        if duration.is_negative() || duration > MAX_PROGRESS_DURATION {
+            let detail = format!("`duration` must be between 0s and {MAX_PROGRESS_DURATION:#}");
+            return (
+                StatusCode::BAD_REQUEST,
+                ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", detail)),
+            )
+                .into_response();
+        }
+        let steps = p.steps.unwrap_or(10);
+        let duration = duration.unsigned_abs();
+        let started = Instant::now();
+
+        let mut operation = Operation { step: 0, steps };
+        let progress = tokio_stream::iter(0..=steps).throttle(duration / steps).map(move |step| {
+            operation.step = step;
+            let data = serde_json::json!({
+                "percent": f64::from(step) * 100.0 / f64::from(steps),
+                "step": step,
+                "of": steps,
+                "elapsed_ms": started.elapsed().as_millis() as u64,
+            });
+            Event::default().id(step.to_string()).event("progress").data(data.to_string())
+        });
+        let result = tokio_stream::once(()).map(move |()| {
+            let data = serde_json::json!({"state": "done", "duration_ms": started.elapsed().as_millis() as u64});
+            Event::default().id((steps + 1).to_string()).event("result").data(data.to_string())
+        });
+
+        Sse::new(progress.chain(result).map(Ok::<_, std::convert::Infallible>)).into_response()
+    }
+
+    pub const MAX_BACKPRESSURE_EVENTS: u64 = 100_000;
+    pub const MAX_BACKPRESSURE_BUFFER: usize = 10_000;
+
+    #[derive(Deserialize, Validate)]
+    pub struct BackpressureParam {
+        /// events produced, 1000 by default
+        #[garde(range(min = 1, max = MAX_BACKPRESSURE_EVENTS))]
+        pub count: Option<u64>,
+        /// events held between the producer and the response, 16 by default
+        #[garde(range(min = 1, max = MAX_BACKPRESSURE_BUFFER))]
+        pub buffer: Option<usize>,
+        /// pause of the producer between events, 1ms by default
+        #[garde(skip)]
+        #[serde(default)]
+        pub interval: Option<SignedDuration>,
+        /// pause before writing each event, a slow consumer on the server side, none by default
+        #[garde(skip)]
+        #[serde(default)]
+        pub consumer_delay: Option<SignedDuration>,
+    }
+
+    /// The response side of `/sse-backpressure`, finding the dropped events from the gaps in their ids.
+    struct Consumer {
+        rx: tokio::sync::mpsc::Receiver<u64>,
+        next: u64,
+        count: u64,
+        delivered: u64,
+        dropped: u64,
+        delay: Duration,
+        done: bool,
+    }
+
+    impl Consumer {
+        fn dropped_event(&mut self, until: u64) -> Option<Event> {
+            let gap = until - self.next;
+            self.dropped += gap;
+            let data = serde_json::json!({"from": self.next, "to": until - 1, "count": gap, "total": self.dropped});
+            (gap > 0).then(|| Event::default().event("dropped").data(data.to_string()))
+        }
+    }
+
+    /// A producer task emitting `count` events every `interval` into a channel of `buffer` events, dropping the
+    /// events that find it full, while the response writes them as fast as the client reads. A `dropped` event
+    /// reports each gap before the next event delivered, and a `summary` event ends the stream.
+    pub async fn sse_backpressure(Garde(Query(p)): Garde<Query<BackpressureParam>>) -> Response {
+        use futures_util::StreamExt as _;
+        let count = p.count.unwrap_or(1000);
+        let buffer = p.buffer.unwrap_or(16);
+        let interval = p.interval.unwrap_or(SignedDuration::from_millis(1)).unsigned_abs();
+        let delay = p.consumer_delay.unwrap_or(SignedDuration::ZERO).unsigned_abs();
+        if interval > Duration::from_secs(10) || delay > Duration::from_secs(10) {
+            let detail = "`interval` and `consumer_delay` must be at most 10s";
+            return (
+                StatusCode::BAD_REQUEST,
+                ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", detail)),
+            )
+                .into_response();
+        }
+
+        let (tx, rx) = tokio::sync::mpsc::channel(buffer);
+        tokio::spawn(async move {
+            for seq in 1..=count {
+                match tx.try_send(seq) {
+                    Ok(()) | Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {}
+                    // the client went away
+                    Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => return,
+                }
+                match interval.is_zero() {
+                    true => tokio::task::yield_now().await,
+                    false => tokio::time::sleep(interval).await,
+                }
+            }
+        });
+
+        let consumer = Consumer {
+            rx,
+            next: 1,
+            count,
+            delivered: 0,
+            dropped: 0,
+            delay,
+            done: false,
+        };
+        let events = futures_util::stream::unfold(consumer, move |mut consumer| async move {
+            if consumer.done {
+                return None;
+            }
+            if !consumer.delay.is_zero() {
+                tokio::time::sleep(consumer.delay).await;
+            }
+            let mut events = Vec::new();
+            match consumer.rx.recv().await {
+                Some(seq) => {
+                    events.extend(consumer.dropped_event(seq));
+                    consumer.delivered += 1;
+                    consumer.next = seq + 1;
+                    let data = serde_json::json!({"seq": seq, "of": consumer.count, "buffered": consumer.rx.len()});
+                    events.push(Event::default().id(seq.to_string()).event("tick").data(data.to_string()));
+                }
+                None => {
+                    events.extend(consumer.dropped_event(consumer.count + 1));
+                    consumer.done = true;
+                    let data = serde_json::json!({
+                        "produced": consumer.count,
+                        "delivered": consumer.delivered,
+                        "dropped": consumer.dropped,
+                        "buffer": buffer,
+                    });
+                    events.push(Event::default().event("summary").data(data.to_string()));
+                }
+            }
+            Some((events, consumer))
+        });
+
+        Sse::new(events.flat_map(futures_util::stream::iter).map(Ok::<_, std::convert::Infallible>)).into_response()
+    }
+}
+
+mod multipart_stream {
+    use std::convert::Infallible;
+
+    use jiff::SignedDuration;
+
+    use super::*;
+
+    pub const MAX_PARTS: usize = 100;
+
+    #[derive(Deserialize)]
+    pub struct MultipartParam {
+        /// pause between parts
+        #[serde(default)]
+        pub duration: Option<SignedDuration>,
+    }
+
+    #[derive(Serialize)]
+    struct Part {
+        part: usize,
+        of: usize,
+        timestamp: i64,
+    }
+
+    /// Streams `min(n, 100)` JSON parts of a `multipart/mixed` body, one chunk per part.
+    pub async fn multipart_stream(Path(n): Path<usize>, Query(p): Query<MultipartParam>) -> Response {
+        use tokio_stream::StreamExt as _;
+        let n = n.min(MAX_PARTS);
+        let boundary = Uuid::new_v4().simple().to_string();
+        let pause = p.duration.unwrap_or(SignedDuration::ZERO).unsigned_abs();
+
+        let parts = {
+            let boundary = boundary.clone();
+            tokio_stream::iter(1..=n).throttle(pause).map(move |part| {
+                let json = serde_json::to_string(&Part {
+                    part,
+                    of: n,
+                    timestamp: jiff::Timestamp::now().as_millisecond(),
+                })
+                .unwrap_or_default();
+                let chunk = format!(
+                    "--{boundary}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{json}\r\n",
+                    json.len()
+                );
+                Ok::<_, Infallible>(Bytes::from(chunk))
+            })
+        };
+        let end = tokio_stream::once(Ok(Bytes::from(format!("--{boundary}--\r\n"))));
+
+        (
+            [(CONTENT_TYPE, format!("multipart/mixed; boundary={boundary}"))],
+            Body::from_stream(parts.chain(end)),
+        )
+            .into_response()
+    }
+}
+
+mod multistatus {
+    use serde_json::Value;
+
+    use super::*;
+
+    pub const MAX_OPERATIONS: usize = 1000;
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Operation {
+        /// echoed in the result, to match results to operations
+        #[serde(default)]
+        id: Value,
+        /// the simulated outcome, 200 by default
+        status: Option<u16>,
+        /// returned as the result of a successful operation
+        #[serde(default)]
+        body: Value,
+        /// returned as the detail of a failed operation
+        #[serde(default)]
+        detail: String,
+    }
+
+    #[derive(Serialize)]
+    struct Outcome {
+        index: usize,
+        id: Value,
+        status: u16,
+        #[serde(skip_serializing_if = "Value::is_null")]
+        body: Value,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<data::ErrorDetail>,
+    }
+
+    /// Answers a JSON array of operations, such as `[{"id": "a"}, {"id": "b", "status": 409, "detail": "exists"}]`,
+    /// with 207 Multi-Status and a result per operation at the status it asks for.
+    pub async fn multistatus(body: Bytes) -> Response {
+        let operations: Vec<Operation> = match serde_json::from_slice(&body) {
+            Ok(operations) => operations,
+            Err(e) => return data::ErrorDetail::response(StatusCode::BAD_REQUEST, format!("expected a JSON array of operations: {e}")),
+        };
+        if operations.len() > MAX_OPERATIONS {
+            return data::ErrorDetail::response(StatusCode::BAD_REQUEST, format!("at most {MAX_OPERATIONS} operations are allowed"));
+        }
+
+        let mut results = Vec::with_capacity(operations.len());
+        for (index, operation) in operations.into_iter().enumerate() {
+            let status = match operation.status.map(StatusCode::from_u16) {
+                None => StatusCode::OK,
+                Some(Ok(status)) if (200..600).contains(&status.as_u16()) => status,
+                Some(_) => {
+                    return data::ErrorDetail::response(
+                        StatusCode::BAD_REQUEST,
+                        format!("operation {index}: `status` must be between 200 and 599"),
+                    );
+                }
+            };
+            let success = status.is_success();
+            results.push(Outcome {
+                index,
+                id: operation.id,
+                status: status.as_u16(),
+                body: if success { operation.body } else { Value::Null },
+                error: (!success).then(|| {
+                    data::ErrorDetail::new(
+                        status.as_u16().into(),
+                        status.canonical_reason().unwrap_or_default(),
+                        operation.detail,
+                    )
+                }),
+            });
+        }
+        let succeeded = results.iter().filter(|outcome| outcome.error.is_none()).count();
+        let failed = results.len() - succeeded;
+
+        (
+            StatusCode::MULTI_STATUS,
+            ErasedJson::pretty(serde_json::json!({
+                "results": results,
+                "summary": {"total": succeeded + failed, "succeeded": succeeded, "failed": failed},
+            })),
+        )
+            .into_response()
+    }
+}
+
+mod generate {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    /// Upper bound of every generated body, whatever the path and `max_size` ask for.
+    pub const MAX_SIZE: usize = 10 * 1024 * 1024;
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct GenerateParam {
+        /// makes the random bytes reproducible
+        #[garde(skip)]
+        pub seed: Option<u64>,
+        /// caps the body below the size from the path
+        #[garde(range(min = 0, max = MAX_SIZE))]
+        pub max_size: Option<usize>,
+        /// bytes per chunk of `/stream-bytes`, and of `/bytes` with `framing=chunked`
+        #[garde(range(min = 1, max = MAX_SIZE))]
+        pub chunk_size: Option<usize>,
+        /// how `/bytes` delimits its body on HTTP/1.1
+        #[garde(skip)]
+        #[serde(default)]
+        pub framing: Framing,
+    }
+
+    #[derive(Debug, Default, Clone, Copy, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum Framing {
+        #[default]
+        ContentLength,
+        Chunked,
+    }
+
+    /// The size to produce: the path size, capped by `max_size` and then `MAX_SIZE`,
+    /// and whether that is less than the path asked for.
+    fn size(n: usize, max_size: Option<usize>) -> (usize, bool) {
+        let size = n.min(max_size.unwrap_or(usize::MAX)).min(MAX_SIZE);
+        (size, size < n)
+    }
+
+    fn random_bytes(size: usize, seed: Option<u64>) -> Vec<u8> {
+        let mut rng = seed.map(fastrand::Rng::with_seed).unwrap_or_default();
+        let mut bytes = vec![0; size];
+        rng.fill(&mut bytes);
+        bytes
+    }
+
+    fn truncated(truncated: bool) -> Option<[(HeaderName, &'static str); 1]> {
+        truncated.then_some([(HeaderName::from_static("x-truncated"), "true")])
+    }
+
+    /// `size` bytes in `chunk_size` chunks (10KiB by default), a body of unknown length that HTTP/1.1 sends with
+    /// `Transfer-Encoding: chunked`.
+    fn chunked(bytes: Vec<u8>, chunk_size: Option<usize>) -> Body {
+        let size = bytes.len();
+        let chunk_size = chunk_size.unwrap_or(10 * 1024);
+        let bytes = Bytes::from(bytes);
+        let chunks = (0..size)
+            .step_by(chunk_size)
+            .map(move |offset| Ok::<_, Infallible>(bytes.slice(offset..(offset + chunk_size).min(size))));
+        Body::from_stream(tokio_stream::iter(chunks))
+    }
+
+    /// With `framing=chunked` the same bytes as the default `Content-Length` framing are sent chunked, HTTP/2 and
+    /// HTTP/3 frame both alike.
+    pub async fn bytes(Path(n): Path<usize>, Garde(Query(p)): Garde<Query<GenerateParam>>) -> Response {
+        let (size, is_truncated) = size(n, p.max_size);
+        let bytes = random_bytes(size, p.seed);
+        let body = match p.framing {
+            Framing::ContentLength => Body::from(bytes),
+            Framing::Chunked => chunked(bytes, p.chunk_size),
+        };
+        (
+            truncated(is_truncated),
+            [(CONTENT_TYPE, mime::APPLICATION_OCTET_STREAM.to_string())],
+            body,
+        )
+            .into_response()
+    }
+
+    /// Like `/bytes`, sent in `chunk_size` chunks (10KiB by default) without a `Content-Length`.
+    pub async fn stream_bytes(Path(n): Path<usize>, Garde(Query(p)): Garde<Query<GenerateParam>>) -> Response {
+        let (size, is_truncated) = size(n, p.max_size);
+        (
+            truncated(is_truncated),
+            [(CONTENT_TYPE, mime::APPLICATION_OCTET_STREAM.to_string())],
+            chunked(random_bytes(size, p.seed), p.chunk_size),
+        )
+            .into_response()
+    }
+
+    /// Upper bound of `/entropy`, which streams rather than buffers its body.
+    pub const MAX_ENTROPY_BYTES: usize = 1024 * 1024 * 1024;
+    /// Upper bound of the `/entropy` chunks, given or derived from `rate`.
+    pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct EntropyParam {
+        /// 10KiB by default
+        #[garde(range(min = 0, max = MAX_ENTROPY_BYTES))]
+        pub bytes: Option<usize>,
+        /// a random one by default, echoed in `X-Entropy-Seed`
+        #[garde(skip)]
+        pub seed: Option<u64>,
+        /// bytes per second, as fast as possible by default
+        #[garde(range(min = 1, max = MAX_ENTROPY_BYTES))]
+        pub rate: Option<usize>,
+        /// a tenth of `rate` by default, at most 1MiB, or 64KiB without one
+        #[garde(range(min = 1, max = MAX_CHUNK_SIZE))]
+        pub chunk_size: Option<usize>,
+    }
+
+    /// The next `len` bytes of `rng`, filled 8 at a time like [`fastrand::Rng::fill`] and carrying the rest of the
+    /// last 8 to the next chunk, so the bytes do not depend on how they are chunked.
+    fn next_chunk(rng: &mut fastrand::Rng, carry: &mut Vec<u8>, len: usize) -> Bytes {
+        let mut chunk = std::mem::take(carry);
+        if chunk.len() < len {
+            let start = chunk.len();
+            chunk.resize(start + (len - start).next_multiple_of(8), 0);
+            rng.fill(&mut chunk[start..]);
+        }
+        *carry = chunk.split_off(len);
+        Bytes::from(chunk)
+    }
+
+    /// Streams `bytes` pseudo-random bytes, at most 1GiB, generated chunk by chunk and the same as `/bytes/{n}` sends
+    /// for a `seed`. `chunk_size` wins over the chunks derived from `rate`, which then paces them so the sustained
+    /// throughput is `rate`.
+    pub async fn entropy(Garde(Query(p)): Garde<Query<EntropyParam>>) -> Response {
+        use tokio_stream::StreamExt as _;
+        let size = p.bytes.unwrap_or(10 * 1024);
+        let seed = p.seed.unwrap_or_else(|| fastrand::u64(..));
+        let chunk_size = match (p.chunk_size, p.rate) {
+            (Some(chunk_size), _) => chunk_size,
+            (None, Some(rate)) => (rate / 10).clamp(1, MAX_CHUNK_SIZE),
+            (None, None) => 64 * 1024,
+        };
+
+        let (mut rng, mut carry) = (fastrand::Rng::with_seed(seed), Vec::new());
+        let chunks = (0..size)
+            .step_by(chunk_size)
+            .map(move |offset| Ok::<_, Infallible>(next_chunk(&mut rng, &mut carry, chunk_size.min(size - offset))));
+        let body = match p.rate {
+            Some(rate) => {
+                let tick = Duration::from_secs_f64(chunk_size as f64 / rate as f64);
+                Body::from_stream(tokio_stream::iter(chunks).throttle(tick))
+            }
+            None => Body::from_stream(tokio_stream::iter(chunks)),
+        };
+        (
+            [
+                (CONTENT_TYPE, mime::APPLICATION_OCTET_STREAM.to_string()),
+                (CONTENT_LENGTH, size.to_string()),
+                (HeaderName::from_static("x-entropy-seed"), seed.to_string()),
+            ],
+            body,
+        )
+            .into_response()
+    }
+
+    #[derive(Serialize)]
+    struct Item {
+        id: usize,
+        name: String,
+        value: f64,
+    }
+
+    /// A JSON array of `n` items, cut to `max_size` bytes without regard for the JSON syntax.
+    pub async fn large_json(Path(n): Path<usize>, Garde(Query(p)): Garde<Query<GenerateParam>>) -> Response {
+        let items = (0..n.min(MAX_SIZE / 32)).map(|id| Item {
+            id,
+            name: format!("item-{id}"),
+            value: id as f64 / 8.0,
+        });
+        let mut json = serde_json::to_vec(&items.collect::<Vec<_>>()).unwrap_or_default();
+        let (size, is_truncated) = size(json.len(), p.max_size);
+        json.truncate(size);
+        (truncated(is_truncated), [(CONTENT_TYPE, APPLICATION_JSON.to_string())], json).into_response()
+    }
+
+    const LOREM: &[&str] = &[
+        "lorem",
+        "ipsum",
+        "dolor",
+        "sit",
+        "amet",
+        "consectetur",
+        "adipiscing",
+        "elit",
+        "sed",
+        "do",
+        "eiusmod",
+        "tempor",
+        "incididunt",
+        "ut",
+        "labore",
+        "et",
+        "dolore",
+        "magna",
+        "aliqua",
+        "enim",
+        "ad",
+        "minim",
+        "veniam",
+        "quis",
+        "nostrud",
+        "exercitation",
+        "ullamco",
+        "laboris",
+        "nisi",
+        "aliquip",
+        "ex",
+        "ea",
+        "commodo",
+        "consequat",
+        "duis",
+        "aute",
+        "irure",
+        "in",
+        "reprehenderit",
+        "voluptate",
+        "velit",
+        "esse",
+        "cillum",
+        "eu",
+        "fugiat",
+        "nulla",
+        "pariatur",
+        "excepteur",
+        "sint",
+        "occaecat",
+        "cupidatat",
+        "non",
+        "proident",
+        "sunt",
+        "culpa",
+        "qui",
+        "officia",
+        "deserunt",
+        "mollit",
+        "anim",
+        "id",
+        "est",
+        "laborum",
+    ];
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct LoremParam {
+        #[garde(range(min = 1, max = 100))]
+        pub paragraphs: Option<usize>,
+        /// per paragraph
+        #[garde(range(min = 1, max = 1000))]
+        pub words: Option<usize>,
+        #[garde(skip)]
+        pub seed: Option<u64>,
+    }
+
+    /// Placeholder paragraphs of sentences with 4 to 16 words, the same for the same parameters and `seed`.
+    pub async fn lorem(Garde(Query(p)): Garde<Query<LoremParam>>) -> Response {
+        let mut rng = fastrand::Rng::with_seed(p.seed.unwrap_or_default());
+        let mut text = String::new();
+        for paragraph in 0..p.paragraphs.unwrap_or(3) {
+            if paragraph > 0 {
+                text.push_str("\n\n");
+            }
+            let mut sentence = 0;
+            for word in 0..p.words.unwrap_or(50) {
+                let mut picked = rng.choice(LOREM).copied().unwrap_or_default().to_string();
+                if sentence == 0 {
+                    if word > 0 {
+                        text.push(' ');
+                    }
+                    picked[..1].make_ascii_uppercase();
+                    sentence = rng.usize(4..=16);
+                } else {
+                    text.push_str(if rng.u8(..10) == 0 { ", " } else { " " });
+                }
+                text.push_str(&picked);
+                sentence -= 1;
+                if sentence == 0 {
+                    text.push('.');
+                }
+            }
+            if sentence > 0 {
+                text.push('.');
+            }
+        }
+        text.push('\n');
+        ([(CONTENT_TYPE, mime::TEXT_PLAIN_UTF_8.to_string())], text).into_response()
+    }
+}
+
+mod reset {
+    use super::*;
+
+    pub const MAX_BYTES: usize = 10 * 1024 * 1024;
+
+    /// Promises twice `after_bytes` in `Content-Length`, sends `after_bytes` and then fails the body, on which
+    /// hyper closes the connection mid-response. This is a deliberate protocol error.
+    pub async fn reset_mid_response(Path(after_bytes): Path<usize>) -> Response {
+        let after_bytes = after_bytes.min(MAX_BYTES);
+        let chunks = [
+            Ok(Bytes::from(vec![b'*'; after_bytes])),
+            Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset mid-response")),
+        ];
+        (
+            [
+                (CONTENT_TYPE, mime::APPLICATION_OCTET_STREAM.to_string()),
+                (CONTENT_LENGTH, (after_bytes * 2).max(1).to_string()),
+            ],
+            Body::from_stream(tokio_stream::iter(chunks)),
+        )
+            .into_response()
+    }
+}
+
+mod throttle {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct ThrottleParam {
+        /// bytes per second
+        #[garde(range(min = 1, max = 10 * 1024 * 1024))]
+        pub rate: Option<usize>,
+        /// total bytes
+        #[garde(range(min = 0, max = 100 * 1024 * 1024))]
+        pub size: Option<usize>,
+    }
+
+    pub async fn throttle(Garde(Query(p)): Garde<Query<ThrottleParam>>) -> Response {
+        use tokio_stream::StreamExt as _;
+        let rate = p.rate.unwrap_or(1024);
+        let size = p.size.unwrap_or(10 * 1024);
+
+        // send a tenth of the rate at a time, paced so that the sustained throughput is `rate`
+        let chunk = (rate / 10).max(1);
+        let tick = Duration::from_secs_f64(chunk as f64 / rate as f64);
+        let chunks = (0..size)
+            .step_by(chunk)
+            .map(move |offset| Ok::<_, Infallible>(Bytes::from(vec![b'*'; chunk.min(size - offset)])));
+        let stream = tokio_stream::iter(chunks).throttle(tick);
+
+        (
+            [
+                (CONTENT_TYPE, mime::APPLICATION_OCTET_STREAM.to_string()),
+                (CONTENT_LENGTH, size.to_string()),
+            ],
+            Body::from_stream(stream),
+        )
+            .into_response()
+    }
+}
+
+mod clock {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct ClockParam {
+        pub client_time: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    struct Clock {
+        server_time: jiff::Timestamp,
+        server_time_ms: i64,
+        client_time: Option<jiff::Timestamp>,
+        /// server minus client, positive when the client clock is behind
+        drift_ms: Option<i64>,
+    }
+
+    /// Epoch milliseconds or an ISO-8601 / RFC 3339 timestamp with an offset.
+    fn parse(client_time: &str) -> Result<jiff::Timestamp, String> {
+        match client_time.parse::<i64>() {
+            Ok(millis) => jiff::Timestamp::from_millisecond(millis).map_err(|e| e.to_string()),
+            Err(_) => client_time.parse().map_err(|e: jiff::Error| e.to_string()),
+        }
+    }
+
+    pub async fn clock(Query(p): Query<ClockParam>) -> Response {
+        let server_time = jiff::Timestamp::now();
+        let client_time = match p.client_time.as_deref().map(parse).transpose() {
+            Ok(client_time) => client_time,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", format!("invalid client_time: {e}"))),
+                )
+                    .into_response();
+            }
+        };
+        ErasedJson::pretty(Clock {
+            server_time,
+            server_time_ms: server_time.as_millisecond(),
+            drift_ms: client_time.map(|it| server_time.as_millisecond() - it.as_millisecond()),
+            client_time,
+        })
+        .into_response()
+    }
+}
+
+mod http_date {
+    use jiff::{Timestamp, fmt::strtime, tz::Offset};
+
+    use super::*;
+
+    const IMF_FIXDATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+    #[derive(Deserialize)]
+    pub struct HttpDateParam {
+        pub value: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    struct HttpDate {
+        value: String,
+        /// `imf-fixdate`, or one of the obsolete `rfc850` and `asctime`
+        format: &'static str,
+        imf_fixdate: String,
+        epoch: i64,
+        timestamp: Timestamp,
+    }
+
+    /// The two digit year of an RFC 850 date is the most recent with these digits that is at most 50 years
+    /// in the future, as RFC 9110 section 5.6.7 says, not the 1969 pivot of `%y`.
+    fn rfc850_year(two_digits: i16, now: Timestamp) -> i16 {
+        let current = now.to_zoned(jiff::tz::TimeZone::UTC).year();
+        let year = current - current % 100 + two_digits;
+        if year > current + 50 { year - 100 } else { year }
+    }
+
+    pub fn parse(value: &str, now: Timestamp) -> Result<(&'static str, Timestamp), String> {
+        let (format, mut parsed) = if let Ok(parsed) = strtime::parse(IMF_FIXDATE, value) {
+            ("imf-fixdate", parsed)
+        } else if let Ok(parsed) = strtime::parse("%A, %d-%b-%y %H:%M:%S GMT", value) {
+            ("rfc850", parsed)
+        } else {
+            // the day of month is padded with a space, `Sun Nov  6 08:49:37 1994`
+            let value = value.split(' ').filter(|it| !it.is_empty()).collect::<Vec<_>>().join(" ");
+            let parsed = strtime::parse("%a %b %d %H:%M:%S %Y", &value).map_err(|_| {
+                "not an IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`), RFC 850 (`Sunday, 06-Nov-94 08:49:37 GMT`) or \
+                 asctime (`Sun Nov  6 08:49:37 1994`) date"
+                    .to_string()
+            })?;
+            ("asctime", parsed)
+        };
+        if format == "rfc850" {
+            let year = parsed.year().unwrap_or_default();
+            parsed.set_year(Some(rfc850_year(year % 100, now))).map_err(|e| e.to_string())?;
+        }
+        // checks the day of week against the date too
+        let datetime = parsed.to_datetime().map_err(|e| e.to_string())?;
+        let timestamp = Offset::UTC.to_timestamp(datetime).map_err(|e| e.to_string())?;
+        Ok((format, timestamp))
+    }
+
+    /// Parses `value` as any of the three HTTP-date formats of RFC 9110, and returns it as an IMF-fixdate and
+    /// epoch seconds.
+    pub async fn http_date(Query(p): Query<HttpDateParam>) -> Response {
+        let Some(value) = p.value else {
+            return data::ErrorDetail::response(StatusCode::BAD_REQUEST, "`value` is required");
+        };
+        let (format, timestamp) = match parse(&value, Timestamp::now()) {
+            Ok(parsed) => parsed,
+            Err(e) => return data::ErrorDetail::response(StatusCode::BAD_REQUEST, format!("invalid date `{value}`: {e}")),
+        };
+        ErasedJson::pretty(HttpDate {
+            value,
+            format,
+            imf_fixdate: timestamp.strftime(IMF_FIXDATE).to_string(),
+            epoch: timestamp.as_second(),
+            timestamp,
+        })
+        .into_response()
+    }
+}
+
+mod json_fragments {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct FragmentsParam {
+        /// number of pieces the document is written in
+        #[garde(range(min = 1, max = 1000))]
+        pub splits: Option<usize>,
+    }
+
+    /// Pause between the pieces so that they leave in separate writes instead of being coalesced.
+    const PAUSE: Duration = Duration::from_millis(10);
+
+    /// A document mixing strings, escapes, multi-byte characters, numbers and literals.
+    fn document() -> Vec<u8> {
+        let items: Vec<_> = (0..200)
+            .map(|id| {
+                serde_json::json!({
+                    "id": id,
+                    "name": format!("item-{id}"),
+                    "price": id as f64 * 1.25 - 0.5,
+                    "tags": ["αβγ", "emoji 🦀", "quote \"inside\""],
+                    "escaped": "line\nbreak\ttab \\ backslash \u{e9}",
+                    "nested": {"active": id % 2 == 0, "missing": null, "exponent": 6.02e23},
+                })
+            })
+            .collect();
+        serde_json::to_vec(&serde_json::json!({"count": items.len(), "items": items})).unwrap_or_default()
+    }
+
+    /// Offsets between two bytes of the same token: inside a string, number or literal,
+    /// including the middle of escapes and of multi-byte UTF-8 sequences.
+    fn mid_token_offsets(document: &[u8]) -> Vec<usize> {
+        let mut in_string = false;
+        let mut escaped = false;
+        let token: Vec<bool> = document
+            .iter()
+            .map(|&byte| {
+                if in_string {
+                    match (escaped, byte) {
+                        (true, _) => escaped = false,
+                        (false, b'\\') => escaped = true,
+                        (false, b'"') => in_string = false,
+                        _ => {}
+                    }
+                    true
+                } else if byte == b'"' {
+                    in_string = true;
+                    true
+                } else {
+                    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'+' | b'.')
+                }
+            })
+            .collect();
+        (1..document.len()).filter(|&i| token[i - 1] && token[i]).collect()
+    }
+
+    pub async fn stream_json_fragments(Garde(Query(p)): Garde<Query<FragmentsParam>>) -> Response {
+        use tokio_stream::StreamExt as _;
+        let splits = p.splits.unwrap_or(10);
+        let document = Bytes::from(document());
+
+        let candidates = mid_token_offsets(&document);
+        let mut offsets = vec![0];
+        for piece in 1..splits {
+            let target = piece * document.len() / splits;
+            let previous = offsets[offsets.len() - 1];
+            if let Some(&offset) = candidates.iter().find(|&&it| it >= target && it > previous) {
+                offsets.push(offset);
+            }
+        }
+        offsets.push(document.len());
+
+        let pieces: Vec<_> = offsets
+            .windows(2)
+            .map(|window| Ok::<_, Infallible>(document.slice(window[0]..window[1])))
+            .collect();
+        let stream = tokio_stream::iter(pieces).throttle(PAUSE);
+
+        (
+            [
+                (CONTENT_TYPE, APPLICATION_JSON.to_string()),
+                (CONTENT_LENGTH, document.len().to_string()),
+            ],
+            Body::from_stream(stream),
+        )
+            .into_response()
+    }
+}
+
+/// `HTTP/3` rather than the `HTTP/3.0` of `Version`'s debug output.
+fn version_name(version: axum::http::Version) -> &'static str {
+    use axum::http::Version;
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_11 => "HTTP/1.1",
+        Version::HTTP_2 => "HTTP/2",
+        Version::HTTP_3 => "HTTP/3",
+        _ => "unknown",
+    }
+}
+
+#[derive(Serialize)]
+struct HttpVersion {
+    version: &'static str,
+}
+
+async fn http_version(version: axum::http::Version) -> impl IntoResponse {
+    ErasedJson::pretty(HttpVersion {
+        version: version_name(version),
+    })
+}
+
+mod host {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Host {
+        /// the `:authority` of HTTP/2 and HTTP/3 or an absolute-form target, falling back to `Host`
+        host: Option<String>,
+        source: Option<&'static str>,
+        /// the `Host` header as received, which may disagree with the `:authority`
+        host_header: Option<String>,
+        /// the `--vhost` the host matched, when there are any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        vhost: Option<String>,
+    }
+
+    fn host(uri: &Uri, header_map: &HeaderMap) -> Host {
+        let host_header = header_map
+            .get(HOST)
+            .map(|host| String::from_utf8_lossy(host.as_bytes()).into_owned());
+        let (host, source) = match uri.authority() {
+            Some(authority) => (Some(authority.to_string()), Some("authority")),
+            None => (host_header.clone(), host_header.as_ref().map(|_| "host")),
+        };
+        Host {
+            host,
+            source,
+            host_header,
+            vhost: None,
+        }
+    }
+
+    /// Splits `host[:port]`, keeping the brackets of an IPv6 literal.
+    fn split_port(host: &str) -> (&str, Option<&str>) {
+        let end = if host.starts_with('[') {
+            host.find(']').map_or(0, |end| end + 1)
+        } else {
+            0
+        };
+        match host[end..].rsplit_once(':') {
+            Some((_, port)) => (&host[..host.len() - port.len() - 1], Some(port)),
+            None => (host, None),
+        }
+    }
+
+    /// Compares hosts case-insensitively, the port only counts when `expected` has one.
+    fn matches(expected: &str, host: &str) -> bool {
+        let (expected_host, expected_port) = split_port(expected);
+        let (host, port) = split_port(host);
+        expected_host.eq_ignore_ascii_case(host) && (expected_port.is_none() || expected_port == port)
+    }
+
+    #[derive(Deserialize)]
+    pub struct HostCheckParam {
+        expected: String,
+    }
+
+    pub async fn reflect(uri: Uri, header_map: HeaderMap, vhost: Option<Extension<vhost::Matched>>) -> impl IntoResponse {
+        let mut host = host(&uri, &header_map);
+        host.vhost = vhost.map(|Extension(vhost::Matched(vhost))| vhost);
+        ErasedJson::pretty(host)
+    }
+
+    /// Answers 421 Misdirected Request unless the request is for the `expected` host.
+    pub async fn check(uri: Uri, header_map: HeaderMap, Query(p): Query<HostCheckParam>) -> Response {
+        let host = host(&uri, &header_map);
+        match &host.host {
+            Some(received) if matches(&p.expected, received) => ErasedJson::pretty(host).into_response(),
+            received => {
+                let detail = match received {
+                    Some(received) => format!("this server is not configured for `{received}`, expected `{}`", p.expected),
+                    None => format!("the request has no host, expected `{}`", p.expected),
+                };
+                (
+                    StatusCode::MISDIRECTED_REQUEST,
+                    ErasedJson::pretty(data::ErrorDetail::new(421, "Misdirected Request", detail)),
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+mod auth_scheme {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Reflected {
+        authorization: Option<AuthScheme>,
+    }
+
+    /// The shape of the `Authorization` header, leaving out anything that could be a secret.
+    #[derive(Serialize)]
+    struct AuthScheme {
+        scheme: String,
+        /// `token68` (Basic, Bearer), `auth-params` (Digest and the like) or `none`
+        format: &'static str,
+        credentials_bytes: usize,
+        /// the names of the auth-params, without their values
+        params: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        basic: Option<Basic>,
+        /// the number of dot separated segments of a token that looks like a JWT
+        #[serde(skip_serializing_if = "Option::is_none")]
+        jwt_segments: Option<usize>,
+    }
+
+    #[derive(Serialize)]
+    struct Basic {
+        valid: bool,
+        username_bytes: usize,
+        password_bytes: usize,
+    }
+
+    fn basic(credentials: &str) -> Basic {
+        let decoded = BASE64_STANDARD.decode(credentials).ok();
+        let split = decoded.as_ref().and_then(|it| {
+            let colon = it.iter().position(|byte| *byte == b':')?;
+            Some((colon, it.len() - colon - 1))
+        });
+        Basic {
+            valid: split.is_some(),
+            username_bytes: split.map_or(0, |(username, _)| username),
+            password_bytes: split.map_or(0, |(_, password)| password),
+        }
+    }
+
+    /// Reported for an `Authorization` header that does not start with a scheme, see [`split`].
+    pub const UNKNOWN: &str = "unknown";
+    /// Longer than any registered scheme (`AWS4-HMAC-SHA256` is 16), a longer word is more likely a secret.
+    const MAX_SCHEME_LEN: usize = 32;
+
+    /// The scheme and credentials of an `Authorization` header, when it starts with an RFC 7235 token of at most
+    /// [`MAX_SCHEME_LEN`] characters followed by a space and credentials. A value without a space, such as a bare
+    /// API key, is all credentials and has no scheme.
+    pub fn split(authorization: &str) -> Option<(&str, &str)> {
+        let (scheme, credentials) = authorization.trim().split_once(' ')?;
+        let is_token = scheme.len() <= MAX_SCHEME_LEN
+            && scheme
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte));
+        is_token.then(|| (scheme, credentials.trim()))
+    }
+
+    fn parse(authorization: &str) -> AuthScheme {
+        let (scheme, credentials) = split(authorization).unwrap_or((UNKNOWN, authorization.trim()));
+        let is_token68 = !credentials.is_empty()
+            && credentials
+                .trim_end_matches('=')
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'+' | b'/'));
+        let params = if is_token68 || credentials.is_empty() {
+            Vec::new()
+        } else {
+            credentials
+                .split(',')
+                .filter_map(|param| Some(param.split_once('=')?.0.trim().to_ascii_lowercase()))
+                .collect()
+        };
+        let jwt_segments = credentials.split('.').count();
+        AuthScheme {
+            format: match (credentials.is_empty(), is_token68) {
+                (true, _) => "none",
+                (_, true) => "token68",
+                _ => "auth-params",
+            },
+            credentials_bytes: credentials.len(),
+            params,
+            basic: scheme.eq_ignore_ascii_case("basic").then(|| basic(credentials)),
+            jwt_segments: (is_token68 && credentials.starts_with("eyJ") && jwt_segments >= 3).then_some(jwt_segments),
+            scheme: scheme.to_string(),
+        }
+    }
+
+    /// Reflects the scheme and structure of the `Authorization` header, never its credentials, so the output
+    /// is safe to log or share.
+    pub async fn auth_scheme(header_map: HeaderMap) -> impl IntoResponse {
+        let authorization = header_map
+            .get(AUTHORIZATION)
+            .map(|it| parse(&String::from_utf8_lossy(it.as_bytes())));
+        ErasedJson::pretty(Reflected { authorization })
+    }
+}
+
+mod h2_pseudo {
+    use axum::http::Version;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Pseudo {
+        version: String,
+        /// whether the values below were sent as pseudo-headers, otherwise they come from the request line and `Host`
+        pseudo_headers: bool,
+        #[serde(rename = ":method")]
+        method: String,
+        #[serde(rename = ":scheme")]
+        scheme: Option<String>,
+        #[serde(rename = ":authority")]
+        authority: Option<String>,
+        #[serde(rename = ":path")]
+        path: Option<String>,
+        host: Option<String>,
+    }
+
+    pub async fn h2_pseudo(version: Version, method: Method, uri: Uri, header_map: HeaderMap) -> impl IntoResponse {
+        let host = header_map
+            .get(HOST)
+            .map(|host| String::from_utf8_lossy(host.as_bytes()).into_owned());
+        let pseudo_headers = version >= Version::HTTP_2;
+        ErasedJson::pretty(Pseudo {
+            version: format!("{version:?}"),
+            pseudo_headers,
+            method: method.to_string(),
+            // HTTP/1.1 has no scheme on the wire unless the target is in absolute-form
+            scheme: uri.scheme_str().map(str::to_string),
+            authority: uri
+                .authority()
+                .map(|it| it.to_string())
+                .or_else(|| host.clone().filter(|_| !pseudo_headers)),
+            path: uri.path_and_query().map(|it| it.to_string()),
+            host,
+        })
+    }
+}
+
+mod method {
+    use super::*;
+
+    pub const STANDARD: [Method; 9] = [
+        Method::GET,
+        Method::HEAD,
+        Method::POST,
+        Method::PUT,
+        Method::DELETE,
+        Method::CONNECT,
+        Method::OPTIONS,
+        Method::TRACE,
+        Method::PATCH,
+    ];
+
+    #[derive(Serialize)]
+    struct MethodInfo {
+        method: String,
+        /// methods are case-sensitive, `get` is not `GET`
+        standard: bool,
+        casing: &'static str,
+        uppercase: String,
+        uppercase_is_standard: bool,
+    }
+
+    pub async fn method(method: Method) -> impl IntoResponse {
+        let name = method.as_str();
+        let casing = match (
+            name.bytes().any(|b| b.is_ascii_lowercase()),
+            name.bytes().any(|b| b.is_ascii_uppercase()),
+        ) {
+            (true, true) => "mixed",
+            (true, false) => "lower",
+            (false, true) => "upper",
+            (false, false) => "none",
+        };
+        let uppercase = name.to_ascii_uppercase();
+        ErasedJson::pretty(MethodInfo {
+            method: name.to_string(),
+            standard: STANDARD.contains(&method),
+            casing,
+            uppercase_is_standard: STANDARD.iter().any(|it| it.as_str() == uppercase),
+            uppercase,
+        })
+    }
+}
+
+mod ratelimit_headers {
+    use super::*;
+
+    #[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Form {
+        /// `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` of the IETF draft, reset in seconds
+        #[default]
+        Ietf,
+        /// `X-RateLimit-*` as GitHub and many others send them, reset as a Unix timestamp
+        X,
+        Both,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct RateLimitParam {
+        #[garde(skip)]
+        #[serde(default)]
+        pub form: Form,
+        /// 60 by default
+        #[garde(range(max = 1_000_000_000))]
+        pub limit: Option<u64>,
+        /// `limit` by default
+        #[garde(range(max = 1_000_000_000))]
+        pub remaining: Option<u64>,
+        /// seconds until the window resets, 60 by default
+        #[garde(range(max = 366 * 24 * 3600))]
+        pub reset: Option<u64>,
+    }
+
+    /// Answers 200 with rate-limit headers of the given values, without counting or limiting anything, and the
+    /// same headers as JSON. 400 when `remaining` is above `limit`.
+    pub async fn ratelimit_headers(Garde(Query(p)): Garde<Query<RateLimitParam>>) -> Response {
+        let limit = p.limit.unwrap_or(60);
+        let remaining = p.remaining.unwrap_or(limit);
+        let reset = p.reset.unwrap_or(60);
+        if remaining > limit {
+            let detail = format!("remaining {remaining} is above limit {limit}");
+            return (
+                StatusCode::BAD_REQUEST,
+                ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", detail)),
+            )
+                .into_response();
+        }
+
+        let mut headers = BTreeMap::new();
+        if matches!(p.form, Form::Ietf | Form::Both) {
+            headers.insert("RateLimit-Limit", limit);
+            headers.insert("RateLimit-Remaining", remaining);
+            headers.insert("RateLimit-Reset", reset);
+        }
+        if matches!(p.form, Form::X | Form::Both) {
+            let at = jiff::Timestamp::now().as_second().unsigned_abs() + reset;
+            headers.insert("X-RateLimit-Limit", limit);
+            headers.insert("X-RateLimit-Remaining", remaining);
+            headers.insert("X-RateLimit-Reset", at);
+        }
+        let mut response = ErasedJson::pretty(&headers).into_response();
+        for (name, value) in headers {
+            response
+                .headers_mut()
+                .insert(HeaderName::from_str(name).expect("a token"), value.into());
+        }
+        response
+    }
+}
+
+mod retry_after {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    static ROTATION: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, Deserialize)]
+    pub struct RetryAfterParam {
+        /// answers `Retry-After: <seconds>`
+        pub seconds: Option<u32>,
+        /// answers `Retry-After: <HTTP-date>`, this many seconds from now
+        pub date: Option<u32>,
+        /// `rotate` cycles through [`rotation`] on every request
+        pub mode: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    struct RetryAfter {
+        retry_after: String,
+        valid: bool,
+    }
+
+    fn http_date(seconds: u32) -> String {
+        let at = jiff::Timestamp::now() + jiff::SignedDuration::from_secs(seconds.into());
+        at.strftime("%a, %d %b %Y %H:%M:%S GMT").to_string()
+    }
+
+    /// Values emitted by `?mode=rotate`, in order, with whether they are valid per RFC 9110.
+    pub fn rotation() -> [(String, bool); 6] {
+        [
+            ("120".to_string(), true),
+            (http_date(120), true),
+            ("-5".to_string(), false),
+            ("1.5".to_string(), false),
+            ("soon".to_string(), false),
+            ("Thu, 01 Jan 1970 00:00:00".to_string(), false),
+        ]
+    }
+
+    pub async fn retry_after(Query(p): Query<RetryAfterParam>) -> Response {
+        let (retry_after, valid) = match (p.seconds, p.date, p.mode.as_deref()) {
+            (None, None, Some("rotate")) => {
+                let rotation = rotation();
+                let i = ROTATION.fetch_add(1, Ordering::Relaxed) % rotation.len();
+                rotation[i].clone()
+            }
+            (Some(seconds), None, None) => (seconds.to_string(), true),
+            (None, Some(seconds), None) => (http_date(seconds), true),
+            (None, None, None) => ("5".to_string(), true),
+            _ => {
+                let detail = "expected one of `seconds`, `date` or `mode=rotate`";
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", detail)),
+                )
+                    .into_response();
+            }
+        };
+
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(RETRY_AFTER, retry_after.clone())],
+            ErasedJson::pretty(RetryAfter { retry_after, valid }),
+        )
+            .into_response()
+    }
+}
+
+mod sleep_random {
+    use super::*;
+
+    /// Same cap as `/delay/{n}`.
+    pub const MAX_DELAY_MS: f64 = 10_000.0;
+
+    #[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Dist {
+        #[default]
+        Uniform,
+        Normal,
+    }
+
+    /// All durations are in milliseconds.
+    #[derive(Debug, Deserialize)]
+    pub struct SleepParam {
+        #[serde(default)]
+        pub dist: Dist,
+        pub min: Option<f64>,
+        pub max: Option<f64>,
+        pub mean: Option<f64>,
+        pub stddev: Option<f64>,
+        pub seed: Option<u64>,
+    }
+
+    #[derive(Serialize)]
+    struct Slept {
+        dist: Dist,
+        seed: Option<u64>,
+        delay_ms: u64,
+    }
+
+    /// Samples a delay in milliseconds, normal samples are clamped into `[min, max]`.
+    fn sample(p: &SleepParam, rng: &mut fastrand::Rng) -> Result<f64, &'static str> {
+        let min = p.min.unwrap_or(0.0);
+        let max = p.max.unwrap_or(match p.dist {
+            Dist::Uniform => 1000.0,
+            Dist::Normal => MAX_DELAY_MS,
+        });
+        if !(0.0..=MAX_DELAY_MS).contains(&min) || !(0.0..=MAX_DELAY_MS).contains(&max) {
+            return Err("`min` and `max` must be within [0, 10000] ms");
+        }
+        if min > max {
+            return Err("`min` must not be greater than `max`");
+        }
+
+        match p.dist {
+            Dist::Uniform => {
+                if p.mean.is_some() || p.stddev.is_some() {
+                    return Err("`mean` and `stddev` only apply to `dist=normal`");
+                }
+                Ok(min + rng.f64() * (max - min))
+            }
+            Dist::Normal => {
+                let (Some(mean), stddev) = (p.mean, p.stddev.unwrap_or(0.0)) else {
+                    return Err("`dist=normal` requires `mean`");
+                };
+                if stddev < 0.0 || !mean.is_finite() || !stddev.is_finite() {
+                    return Err("`stddev` must be a non-negative number");
+                }
+                // Box-Muller, `1 - f64()` keeps the logarithm away from zero
+                let z = (-2.0 * (1.0 - rng.f64()).ln()).sqrt() * (std::f64::consts::TAU * rng.f64()).cos();
+                Ok((mean + z * stddev).clamp(min, max))
+            }
+        }
+    }
+
+    pub async fn sleep_random(Query(p): Query<SleepParam>) -> Response {
+        let mut rng = p.seed.map(fastrand::Rng::with_seed).unwrap_or_else(fastrand::Rng::new);
+        let delay = match sample(&p, &mut rng) {
+            Ok(delay) => Duration::from_secs_f64(delay / 1000.0),
+            Err(detail) => return data::ErrorDetail::response(StatusCode::BAD_REQUEST, detail),
+        };
+        tokio::time::sleep(delay).await;
+
+        (
+            AppendHeaders([("Server-Timing", format!("delay;dur={}", delay.as_millis()))]),
+            ErasedJson::pretty(Slept {
+                dist: p.dist,
+                seed: p.seed,
+                delay_ms: delay.as_millis() as u64,
+            }),
+        )
+            .into_response()
+    }
+}
+
+mod compute {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use sha2::{Digest as _, Sha256};
+    use tokio::sync::Semaphore;
+
+    use super::*;
+
+    pub const MAX_ITERATIONS: u64 = 10_000_000;
+    /// Loops running at once, the others are answered 503 rather than queued on the blocking pool.
+    pub const MAX_CONCURRENT: usize = 4;
+    /// Rounds between two looks at whether the client is still waiting.
+    const CHECK_EVERY: u64 = 4096;
+
+    static PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT);
+
+    /// Tells the loop to stop when the handler is dropped, as when the client disconnects.
+    struct CancelOnDrop(Arc<AtomicBool>);
+
+    impl Drop for CancelOnDrop {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::Relaxed);
+        }
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    pub struct ComputeParam {
+        /// SHA-256 rounds, 100000 by default
+        #[garde(range(min = 1, max = MAX_ITERATIONS))]
+        pub iterations: Option<u64>,
+    }
+
+    #[derive(Serialize)]
+    struct Computed {
+        algorithm: &'static str,
+        iterations: u64,
+        /// the last digest in hex, the same for the same `iterations` on every server
+        result: String,
+        elapsed_ms: f64,
+    }
+
+    /// Burns CPU by hashing the empty string with SHA-256 and then each digest again, `iterations` times in all.
+    /// The loop runs on the blocking pool, the async workers keep serving other requests meanwhile. At most
+    /// `MAX_CONCURRENT` loops run at once, and a loop stops when its client goes away.
+    pub async fn compute(Garde(Query(p)): Garde<Query<ComputeParam>>) -> Response {
+        let iterations = p.iterations.unwrap_or(100_000);
+        let Ok(permit) = PERMITS.try_acquire() else {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(RETRY_AFTER, "1")],
+                ErasedJson::pretty(data::ErrorDetail::new(
+                    503,
+                    "Service Unavailable",
+                    format!("{MAX_CONCURRENT} computations are running already"),
+                )),
+            )
+                .into_response();
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let _cancel = CancelOnDrop(cancelled.clone());
+        let task = tokio::task::spawn_blocking(move || {
+            // held until the loop stops, not until the handler is dropped
+            let _permit = permit;
+            let started = Instant::now();
+            let mut digest = Sha256::digest(b"");
+            for i in 1..iterations {
+                if i % CHECK_EVERY == 0 && cancelled.load(Ordering::Relaxed) {
+                    return None;
+                }
+                digest = Sha256::digest(digest);
+            }
+            Some((format!("{digest:x}"), started.elapsed()))
+        });
+        let (result, elapsed) = match task.await {
+            Ok(Some(computed)) => computed,
+            // only once the handler is dropped, nobody is there for the response
+            Ok(None) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ErasedJson::pretty(data::ErrorDetail::new(500, "Internal Server Error", e.to_string())),
+                )
+                    .into_response();
+            }
+        };
+
+        (
+            AppendHeaders([("Server-Timing", format!("compute;dur={}", elapsed.as_millis()))]),
+            ErasedJson::pretty(Computed {
+                algorithm: "sha256",
+                iterations,
+                result,
+                elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+            }),
+        )
+            .into_response()
+    }
+}
+
+mod compression {
+    use std::{
+        convert::Infallible,
+        ops::RangeInclusive,
+        sync::atomic::{AtomicU64, Ordering},
+    };
+
+    use axum::body::HttpBody;
+    use tower::ServiceExt as _;
+    use tower_http::CompressionLevel;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct LevelParam {
+        pub level: Option<i32>,
+    }
+
+    /// Whether the server wide compression compressed a response, `compressed` or `skipped; reason=...`.
+    pub static X_COMPRESSION: HeaderName = HeaderName::from_static("x-compression");
+
+    /// Why the server wide compression leaves a response as it is, `None` when it compresses it. These are the
+    /// checks of `CompressionLayer` and its `DefaultPredicate`, with `min_size` in place of its 32 bytes.
+    fn skip_reason(min_size: usize, accept_encoding: Option<&HeaderValue>, response: &Response) -> Option<String> {
+        let headers = response.headers();
+        if headers.contains_key(CONTENT_ENCODING) || response.extensions().get::<negotiate::Negotiated>().is_some() {
+            return Some("already-encoded".to_string());
+        }
+        if headers.contains_key(CONTENT_RANGE) {
+            return Some("range".to_string());
+        }
+        let content_type = headers.get(CONTENT_TYPE).and_then(|it| it.to_str().ok()).unwrap_or_default();
+        let incompressible = ["application/grpc", "text/event-stream"]
+            .iter()
+            .any(|it| content_type.starts_with(it))
+            || (content_type.starts_with("image/") && !content_type.starts_with("image/svg+xml"));
+        if incompressible {
+            return Some(format!("content-type; type={content_type}"));
+        }
+        let size = response.body().size_hint().exact().or_else(|| {
+            headers
+                .get(CONTENT_LENGTH)
+                .and_then(|it| it.to_str().ok())
+                .and_then(|it| it.parse().ok())
+        });
+        if let Some(size) = size.filter(|size| *size < min_size as u64) {
+            return Some(format!("below-min-size; size={size}; min={min_size}"));
+        }
+        // `CompressionLayer` picks the coding of highest quality, identity included
+        let preferences = negotiate::preferences(accept_encoding.and_then(|it| it.to_str().ok()).unwrap_or_default());
+        let quality = |codings: &[&str]| {
+            preferences
+                .iter()
+                .filter(|it| codings.iter().any(|coding| it.value.eq_ignore_ascii_case(coding)))
+                .map(|it| it.q)
+                .fold(0.0, f32::max)
+        };
+        let compressed = quality(&["gzip", "x-gzip", "deflate", "br", "zstd"]);
+        if compressed == 0.0 || compressed <= quality(&["identity"]) {
+            return Some("not-accepted".to_string());
+        }
+        None
+    }
+
+    /// Reports in `X-Compression` whether the server wide compression compresses the response and why not, for
+    /// [`Reported`] to follow.
+    pub async fn report(min_size: usize, request: Request, next: middleware::Next) -> Response {
+        let accept_encoding = request.headers().get(ACCEPT_ENCODING).cloned();
+        let mut response = next.run(request).await;
+        let value = match skip_reason(min_size, accept_encoding.as_ref(), &response) {
+            None => "compressed".to_string(),
+            Some(reason) => format!("skipped; reason={reason}"),
+        };
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers_mut().insert(X_COMPRESSION.clone(), value);
+        }
+        response
+    }
+
+    /// Compression predicate following the decision [`report`] put in `X-Compression`.
+    #[derive(Clone, Copy)]
+    pub struct Reported;
+
+    impl tower_http::compression::Predicate for Reported {
+        fn should_compress<B: HttpBody>(&self, response: &axum::http::Response<B>) -> bool {
+            response.headers().get(&X_COMPRESSION).is_some_and(|it| it == "compressed")
+        }
+    }
+
+    /// Levels accepted by each encoder, without a level the encoder defaults are used:
+    /// 6 for gzip and deflate, 11 for br and 3 for zstd.
+    fn levels(format: &str) -> RangeInclusive<i32> {
+        match format {
+            "gzip" | "deflate" => 0..=9,
+            "br" => 0..=11,
+            "zstd" => 1..=22,
+            _ => 0..=0,
+        }
+    }
+
+    fn quality(format: &str, level: Option<i32>) -> Result<CompressionLevel, Response> {
+        match level {
+            None => Ok(CompressionLevel::Default),
+            Some(level) if levels(format).contains(&level) => Ok(CompressionLevel::Precise(level)),
+            Some(level) => {
+                let levels = levels(format);
+                Err((
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(data::ErrorDetail::new(
+                        400,
+                        "Bad Request",
+                        format!(
+                            "invalid {format} level: {level} not in range [{}, {}]",
+                            levels.start(),
+                            levels.end()
+                        ),
+                    )),
+                )
+                    .into_response())
+            }
+        }
+    }
+
+    pub async fn compressed(format: &'static str, level: Option<i32>, request: Request) -> Response {
+        let quality = match quality(format, level) {
+            Ok(quality) => quality,
+            Err(response) => return response,
+        };
+
+        ServiceBuilder::default()
+            .layer(SetRequestHeaderLayer::overriding(ACCEPT_ENCODING, HeaderValue::from_static(format)))
+            .layer(CompressionLayer::new().quality(quality))
+            .service(get(anything))
+            .oneshot(request)
+            .await
+            .into_response()
+    }
+
+    #[derive(Deserialize, Debug, Clone, Copy)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Algo {
+        Gzip,
+        Br,
+        Zstd,
+        Deflate,
+    }
+
+    impl Algo {
+        fn as_str(self) -> &'static str {
+            match self {
+                Algo::Gzip => "gzip",
+                Algo::Br => "br",
+                Algo::Zstd => "zstd",
+                Algo::Deflate => "deflate",
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct CompressRequestParam {
+        pub algo: Option<Algo>,
+        pub level: Option<i32>,
+    }
+
+    #[derive(Serialize)]
+    struct CompressedSize {
+        algo: &'static str,
+        level: Option<i32>,
+        original_bytes: u64,
+        compressed_bytes: u64,
+        /// compressed over original bytes, `null` for an empty body
+        ratio: Option<f64>,
+    }
+
+    /// Compresses the request body with `algo` (gzip by default) as the `/gzip` family of endpoints does, and
+    /// answers the sizes before and after. The body is compressed as it streams in and only counted, so its
+    /// size is not bounded by memory.
+    pub async fn compress_request(Query(p): Query<CompressRequestParam>, request: Request) -> Response {
+        use tokio_stream::StreamExt as _;
+        let algo = p.algo.unwrap_or(Algo::Gzip);
+        let quality = match quality(algo.as_str(), p.level) {
+            Ok(quality) => quality,
+            Err(response) => return response,
+        };
+
+        let original_bytes = Arc::new(AtomicU64::new(0));
+        // the body goes through the compression layer as the response of a service echoing it
+        let echo = tower::service_fn(|request: Request| async move { Ok::<_, Infallible>(Response::new(request.into_body())) });
+        let compressed = ServiceBuilder::default()
+            .layer(SetRequestHeaderLayer::overriding(
+                ACCEPT_ENCODING,
+                HeaderValue::from_static(algo.as_str()),
+            ))
+            .layer(
+                CompressionLayer::new()
+                    .quality(quality)
+                    .compress_when(tower_http::compression::predicate::SizeAbove::new(0)),
+            )
+            .service(echo)
+            .oneshot(request.map(|body| {
+                let original_bytes = original_bytes.clone();
+                Body::from_stream(body.into_data_stream().map(move |chunk| {
+                    if let Ok(chunk) = &chunk {
+                        original_bytes.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                    }
+                    chunk
+                }))
+            }))
+            .await;
+        let Ok(compressed) = compressed;
+
+        let mut compressed_bytes = 0;
+        let mut stream = compressed.into_body().into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => compressed_bytes += chunk.len() as u64,
+                Err(e) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        ErasedJson::pretty(data::ErrorDetail::new(400, "Bad Request", format!("cannot read the body: {e}"))),
+                    )
+                        .into_response();
+                }
+            }
+        }
+        let original_bytes = original_bytes.load(Ordering::Relaxed);
+
+        ErasedJson::pretty(CompressedSize {
+            algo: algo.as_str(),
+            level: p.level,
+            original_bytes,
+            compressed_bytes,
+            ratio: (original_bytes > 0).then(|| compressed_bytes as f64 / original_bytes as f64),
+        })
+        .into_response()
+    }
+
+    #[derive(Deserialize, Validate)]
+    pub struct GzipStreamParam {
+        #[garde(range(min = 1, max = 100_000))]
+        pub lines: Option<usize>,
+        /// leaves out the CRC-32 and ISIZE trailer ending the gzip member
+        #[garde(skip)]
+        #[serde(default)]
+        pub truncate: bool,
+    }
+
+    /// Streams `lines` lines of text (100 by default) gzip compressed, a chunk per line, with or without the
+    /// trailer of the gzip member. The deflate stream itself is complete either way, so a client only notices
+    /// the truncation if it checks for the trailer.
+    pub async fn gzip_stream(Garde(Query(p)): Garde<Query<GzipStreamParam>>) -> Response {
+        use std::io::Write as _;
+
+        use flate2::{Compression, write::GzEncoder};
+
+        let lines = p.lines.unwrap_or(100);
+        let truncate = p.truncate;
+        let mut encoder = Some(GzEncoder::new(Vec::new(), Compression::default()));
+        let chunks = (0..=lines).map(move |line| {
+            let Some(gzip) = encoder.as_mut() else {
+                return Ok::<_, std::io::Error>(Bytes::new());
+            };
+            if line < lines {
+                writeln!(gzip, "line {line} of {lines}: the quick brown fox jumps over the lazy dog")?;
+                gzip.flush()?;
+                return Ok(Bytes::from(std::mem::take(gzip.get_mut())));
+            }
+            let mut end = encoder.take().map(GzEncoder::finish).transpose()?.unwrap_or_default();
+            if truncate {
+                // CRC-32 and ISIZE, 4 bytes each
+                end.truncate(end.len().saturating_sub(8));
+            }
+            Ok(Bytes::from(end))
+        });
+
+        (
+            [
+                (CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8")),
+                (CONTENT_ENCODING, HeaderValue::from_static("gzip")),
+            ],
+            Body::from_stream(tokio_stream::iter(chunks)),
+        )
+            .into_response()
+    }
 }
 
 mod links {
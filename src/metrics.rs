@@ -0,0 +1,51 @@
+use std::{
+    fmt::Write as _,
+    sync::{Arc, atomic::Ordering},
+};
+
+use axum::{Extension, http::header::CONTENT_TYPE, response::IntoResponse};
+
+use crate::{config::Config, listener, stream_abort};
+
+/// Prometheus text exposition of the server's own counters.
+pub async fn metrics(Extension(config): Extension<Arc<Config>>) -> impl IntoResponse {
+    let mut body = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+        writeln!(body, "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}").expect("writing to a String");
+    };
+    metric(
+        "httpbin_connections_active",
+        "gauge",
+        "Open TCP connections.",
+        listener::ACTIVE.load(Ordering::Relaxed).to_string(),
+    );
+    if let Some(max) = config.max_connections {
+        metric("httpbin_connections_max", "gauge", "The --max-connections limit.", max.to_string());
+    }
+    metric(
+        "httpbin_connections_rejected_total",
+        "counter",
+        "Connections answered with 503 beyond --max-connections.",
+        listener::REJECTED.load(Ordering::Relaxed).to_string(),
+    );
+    metric(
+        "httpbin_streams_active",
+        "gauge",
+        "/stream-abort responses being streamed.",
+        stream_abort::ACTIVE.load(Ordering::Relaxed).to_string(),
+    );
+    metric(
+        "httpbin_streams_completed_total",
+        "counter",
+        "/stream-abort responses sent to the end.",
+        stream_abort::COMPLETED.load(Ordering::Relaxed).to_string(),
+    );
+    metric(
+        "httpbin_streams_aborted_total",
+        "counter",
+        "/stream-abort responses dropped because the client disconnected first.",
+        stream_abort::ABORTED.load(Ordering::Relaxed).to_string(),
+    );
+
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Extension, Json,
+    body::Bytes,
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri, header::ALLOW},
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{admin, config::Config, data::ErrorDetail};
+
+/// How long a mock is served when it is registered without a `ttl`, in seconds.
+pub const DEFAULT_TTL: u64 = 3600;
+pub const MAX_TTL: u64 = 7 * 24 * 3600;
+pub const MAX_DELAY_MS: u64 = 10_000;
+/// Mocks registered at once, one per method, registering more is answered 507 until some expire or are removed.
+pub const CAPACITY: usize = 1000;
+
+/// Registered mocks by method and path, `*` as the method matches any.
+static MOCKS: LazyLock<Arc<Mutex<HashMap<(String, String), Mock>>>> = LazyLock::new(Default::default);
+
+struct Mock {
+    spec: MockSpec,
+    expires: Instant,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct MockSpec {
+    /// path below `/mock`, `/users/1` is served at `/mock/users/1`
+    pub path: String,
+    #[serde(default = "any_method")]
+    pub method: String,
+    /// registers the mock for each of these methods instead of `method`, other methods get a 405
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+    #[serde(default = "ok")]
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// seconds
+    #[serde(default = "default_ttl")]
+    pub ttl: u64,
+    /// expands `{{ ... }}` in the body with the request data, see [`render`]
+    #[serde(default)]
+    pub template: bool,
+}
+
+fn any_method() -> String {
+    "*".to_string()
+}
+
+fn ok() -> u16 {
+    200
+}
+
+fn default_ttl() -> u64 {
+    DEFAULT_TTL
+}
+
+#[derive(Deserialize)]
+pub struct MockKey {
+    pub path: String,
+    pub method: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Registered {
+    registered: MockSpec,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct Mocks {
+    mocks: Vec<MockSpec>,
+}
+
+#[derive(Serialize)]
+struct Removed {
+    removed: bool,
+}
+
+fn normalize(path: &str) -> String {
+    format!("/{}", path.trim_start_matches('/'))
+}
+
+fn validate(spec: &MockSpec) -> Result<(), String> {
+    if spec.method != "*" {
+        Method::from_bytes(spec.method.as_bytes()).map_err(|e| format!("invalid method `{}`: {e}", spec.method))?;
+        if !spec.methods.is_empty() {
+            return Err("`method` and `methods` cannot both be set".to_string());
+        }
+    }
+    for method in &spec.methods {
+        if method == "*" {
+            return Err("`methods` cannot list `*`".to_string());
+        }
+        Method::from_bytes(method.as_bytes()).map_err(|e| format!("invalid method `{method}`: {e}"))?;
+    }
+    StatusCode::from_u16(spec.status).map_err(|e| format!("invalid status {}: {e}", spec.status))?;
+    for (name, value) in &spec.headers {
+        HeaderName::try_from(name).map_err(|e| format!("invalid header name `{name}`: {e}"))?;
+        HeaderValue::try_from(value).map_err(|e| format!("invalid header value `{value}`: {e}"))?;
+    }
+    if spec.delay_ms > MAX_DELAY_MS {
+        return Err(format!("delay_ms {} is larger than {MAX_DELAY_MS}", spec.delay_ms));
+    }
+    if spec.ttl > MAX_TTL {
+        return Err(format!("ttl {} is larger than {MAX_TTL}", spec.ttl));
+    }
+    if spec.template {
+        for variable in variables(&spec.body)? {
+            match variable.split_once('.') {
+                Some(("args" | "headers", name)) if !name.is_empty() => {}
+                None if matches!(variable, "method" | "path" | "body") => {}
+                _ => return Err(format!("unknown template variable `{variable}`")),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The trimmed `{{ ... }}` expressions of a template.
+fn variables(template: &str) -> Result<Vec<&str>, String> {
+    let mut variables = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}").ok_or("unterminated `{{` in template")?;
+        variables.push(rest[start + 2..start + end].trim());
+        rest = &rest[start + end + 2..];
+    }
+    Ok(variables)
+}
+
+/// The request data a templated body can use.
+struct Context<'a> {
+    method: &'a Method,
+    uri: &'a Uri,
+    header_map: &'a HeaderMap,
+    body: &'a str,
+}
+
+/// Expands `{{ method }}`, `{{ path }}`, `{{ body }}`, `{{ args.<name> }}` and `{{ headers.<name> }}`.
+///
+/// Deliberately nothing more: no expressions, filters or access to anything but the request,
+/// and a query argument or header the request does not have is an error.
+fn render(template: &str, context: &Context) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}").ok_or("unterminated `{{` in template")?;
+        let variable = rest[start + 2..start + end].trim();
+        rendered.push_str(&rest[..start]);
+        let value = match variable.split_once('.') {
+            None if variable == "method" => Some(context.method.to_string()),
+            None if variable == "path" => Some(context.uri.path().to_string()),
+            None if variable == "body" => Some(context.body.to_string()),
+            Some(("args", name)) => form_urlencoded::parse(context.uri.query().unwrap_or_default().as_bytes())
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned()),
+            Some(("headers", name)) => context
+                .header_map
+                .get(name)
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned()),
+            _ => None,
+        };
+        rendered.push_str(&value.ok_or_else(|| format!("unknown template variable `{variable}`"))?);
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Registers a canned response, replacing the one with the same method and path.
+pub async fn register(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap, Json(mut spec): Json<MockSpec>) -> Response {
+    if let Err(response) = admin::authorize(&config, &header_map) {
+        return response;
+    }
+    if let Err(detail) = validate(&spec) {
+        return ErrorDetail::response(StatusCode::BAD_REQUEST, detail);
+    }
+    spec.path = normalize(&spec.path);
+    spec.method = spec.method.to_ascii_uppercase();
+    spec.methods = spec.methods.iter().map(|it| it.to_ascii_uppercase()).collect();
+    spec.methods.sort();
+    spec.methods.dedup();
+
+    let methods = if spec.methods.is_empty() {
+        vec![spec.method.clone()]
+    } else {
+        spec.methods.clone()
+    };
+    let now = Instant::now();
+    let expires = now + Duration::from_secs(spec.ttl);
+    let mut mocks = MOCKS.lock().await;
+    mocks.retain(|_, mock| mock.expires > now);
+    let added = methods
+        .iter()
+        .filter(|method| !mocks.contains_key(&(method.to_string(), spec.path.clone())))
+        .count();
+    if mocks.len() + added > CAPACITY {
+        let detail = format!("already {} of {CAPACITY} mocks registered, remove some first", mocks.len());
+        return (
+            StatusCode::INSUFFICIENT_STORAGE,
+            ErasedJson::pretty(ErrorDetail::new(507, "Insufficient Storage", detail)),
+        )
+            .into_response();
+    }
+    for method in methods {
+        let spec = MockSpec {
+            method: method.clone(),
+            ..spec.clone()
+        };
+        mocks.insert((method, spec.path.clone()), Mock { spec, expires });
+    }
+    drop(mocks);
+
+    let url = format!("/mock{}", spec.path);
+    (StatusCode::CREATED, ErasedJson::pretty(Registered { registered: spec, url })).into_response()
+}
+
+pub async fn list(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap) -> Response {
+    if let Err(response) = admin::authorize(&config, &header_map) {
+        return response;
+    }
+    let now = Instant::now();
+    let mut mocks = MOCKS.lock().await;
+    mocks.retain(|_, mock| mock.expires > now);
+    let mut mocks: Vec<_> = mocks.values().map(|mock| mock.spec.clone()).collect();
+    mocks.sort_by(|a, b| (&a.path, &a.method).cmp(&(&b.path, &b.method)));
+    ErasedJson::pretty(Mocks { mocks }).into_response()
+}
+
+pub async fn unregister(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap, Query(key): Query<MockKey>) -> Response {
+    if let Err(response) = admin::authorize(&config, &header_map) {
+        return response;
+    }
+    let method = key.method.map(|it| it.to_ascii_uppercase()).unwrap_or_else(any_method);
+    let removed = MOCKS.lock().await.remove(&(method, normalize(&key.path))).is_some();
+    let status = if removed { StatusCode::OK } else { StatusCode::NOT_FOUND };
+    (status, ErasedJson::pretty(Removed { removed })).into_response()
+}
+
+/// Serves the mock registered for the method and path, falling back to one registered for any method. A path
+/// registered with `methods` answers the other methods 405 with their `Allow`.
+pub async fn serve(method: Method, uri: Uri, header_map: HeaderMap, body: Bytes) -> Response {
+    let path = normalize(uri.path().strip_prefix("/mock").unwrap_or_default());
+    let spec = {
+        let now = Instant::now();
+        let mut mocks = MOCKS.lock().await;
+        mocks.retain(|_, mock| mock.expires > now);
+        [method.as_str(), "*"]
+            .into_iter()
+            .find_map(|method| mocks.get(&(method.to_string(), path.clone())))
+            .map(|mock| mock.spec.clone())
+            .ok_or_else(|| {
+                let mut allow: Vec<_> = mocks
+                    .values()
+                    .filter(|mock| mock.spec.path == path && !mock.spec.methods.is_empty())
+                    .map(|mock| mock.spec.method.clone())
+                    .collect();
+                allow.sort();
+                allow
+            })
+    };
+    let spec = match spec {
+        Ok(spec) => spec,
+        Err(allow) if !allow.is_empty() => {
+            let detail = format!("the mock for {path} accepts {}", allow.join(", "));
+            return (
+                StatusCode::METHOD_NOT_ALLOWED,
+                [(ALLOW, allow.join(", "))],
+                ErasedJson::pretty(ErrorDetail::new(405, "Method Not Allowed", detail)),
+            )
+                .into_response();
+        }
+        Err(_) => {
+            let detail = format!("no mock registered for {method} {path}");
+            return (
+                StatusCode::NOT_FOUND,
+                ErasedJson::pretty(ErrorDetail::new(404, "Not Found", detail)),
+            )
+                .into_response();
+        }
+    };
+
+    let body = if spec.template {
+        let context = Context {
+            method: &method,
+            uri: &uri,
+            header_map: &header_map,
+            body: &String::from_utf8_lossy(&body),
+        };
+        match render(&spec.body, &context) {
+            Ok(body) => body,
+            Err(detail) => return ErrorDetail::response(StatusCode::BAD_REQUEST, detail),
+        }
+    } else {
+        spec.body
+    };
+
+    tokio::time::sleep(Duration::from_millis(spec.delay_ms)).await;
+    let mut response = (StatusCode::from_u16(spec.status).unwrap_or_default(), body).into_response();
+    for (name, value) in spec.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+}
@@ -0,0 +1,208 @@
+use std::convert::Infallible;
+
+use axum::{
+    body::{Bytes, HttpBody},
+    extract::Request,
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{ACCEPT, ACCEPT_CHARSET, ACCEPT_ENCODING, ACCEPT_LANGUAGE, CONTENT_TYPE, HeaderName},
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+use tower::{ServiceBuilder, ServiceExt as _};
+use tower_http::{compression::CompressionLayer, set_header::SetRequestHeaderLayer};
+
+/// What the server can produce for each negotiated dimension, most preferred first.
+const MEDIA_TYPES: &[&str] = &["application/json", "text/html", "application/xml", "text/plain"];
+const ENCODINGS: &[&str] = &["br", "zstd", "gzip", "deflate", "identity"];
+const LANGUAGES: &[&str] = &["en"];
+const CHARSETS: &[&str] = &["utf-8"];
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Preference {
+    pub value: String,
+    pub q: f32,
+}
+
+#[derive(Serialize)]
+struct Dimension {
+    header: Option<String>,
+    preferences: Vec<Preference>,
+    available: &'static [&'static str],
+    chosen: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct Negotiation {
+    accept: Dimension,
+    accept_encoding: Dimension,
+    accept_language: Dimension,
+    accept_charset: Dimension,
+}
+
+/// Parses a comma separated header into its values sorted by descending quality,
+/// values with the same quality keep the order they were sent in.
+pub fn preferences(header: &str) -> Vec<Preference> {
+    let mut preferences: Vec<_> = header
+        .split(',')
+        .filter_map(|item| {
+            let mut params = item.split(';').map(str::trim);
+            let value = params.next().filter(|it| !it.is_empty())?;
+            let mut q = 1.0;
+            let mut rest = Vec::new();
+            for param in params {
+                match param.split_once('=') {
+                    Some((name, weight)) if name.trim().eq_ignore_ascii_case("q") => {
+                        q = weight.trim().parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))?;
+                    }
+                    _ => rest.push(param),
+                }
+            }
+            let value = std::iter::once(value).chain(rest).collect::<Vec<_>>().join(";");
+            Some(Preference { value, q })
+        })
+        .collect();
+    preferences.sort_by(|a, b| b.q.total_cmp(&a.q));
+    preferences
+}
+
+/// How closely `range` matches `candidate`, `None` when it does not match at all.
+fn specificity(header: &HeaderName, range: &str, candidate: &str) -> Option<usize> {
+    let range = range.split(';').next().unwrap_or_default().to_ascii_lowercase();
+    if range == candidate {
+        return Some(usize::MAX);
+    }
+    if *header == ACCEPT {
+        let (kind, _) = candidate.split_once('/')?;
+        return match range.split_once('/')? {
+            ("*", "*") => Some(0),
+            (range, "*") if range == kind => Some(1),
+            _ => None,
+        };
+    }
+    if range == "*" {
+        return Some(0);
+    }
+    if *header != ACCEPT_LANGUAGE {
+        return None;
+    }
+    // `en` matches the more specific `en-us`, and `en-us` falls back to `en`
+    let (short, long) = if range.len() < candidate.len() {
+        (range.as_str(), candidate)
+    } else {
+        (candidate, range.as_str())
+    };
+    long.strip_prefix(short)?.starts_with('-').then_some(range.len())
+}
+
+/// Picks the available value with the highest quality, using the most specific matching
+/// preference for each, and the server order to break ties.
+pub fn choose(header: &HeaderName, preferences: &[Preference], available: &'static [&'static str]) -> Option<&'static str> {
+    if preferences.is_empty() {
+        return available.first().copied();
+    }
+    let quality = |candidate: &str| {
+        let q = preferences
+            .iter()
+            .filter_map(|it| Some((specificity(header, &it.value, candidate)?, it.q)))
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, q)| q);
+        // identity is acceptable unless it is explicitly excluded
+        match q {
+            None if *header == ACCEPT_ENCODING && candidate == "identity" => f32::MIN_POSITIVE,
+            q => q.unwrap_or_default(),
+        }
+    };
+    available
+        .iter()
+        .map(|candidate| (*candidate, quality(candidate)))
+        .filter(|(_, q)| *q > 0.0)
+        .fold(None, |best: Option<(&str, f32)>, (candidate, q)| match best {
+            Some((_, best_q)) if best_q >= q => best,
+            _ => Some((candidate, q)),
+        })
+        .map(|(candidate, _)| candidate)
+}
+
+fn dimension(header_map: &HeaderMap, header: HeaderName, available: &'static [&'static str]) -> Dimension {
+    let values: Vec<_> = header_map.get_all(&header).iter().filter_map(|it| it.to_str().ok()).collect();
+    let header_value = (!values.is_empty()).then(|| values.join(", "));
+    let preferences = header_value.as_deref().map(preferences).unwrap_or_default();
+    Dimension {
+        chosen: choose(&header, &preferences, available),
+        header: header_value,
+        preferences,
+        available,
+    }
+}
+
+pub async fn negotiate(header_map: HeaderMap) -> impl IntoResponse {
+    ErasedJson::pretty(Negotiation {
+        accept: dimension(&header_map, ACCEPT, MEDIA_TYPES),
+        accept_encoding: dimension(&header_map, ACCEPT_ENCODING, ENCODINGS),
+        accept_language: dimension(&header_map, ACCEPT_LANGUAGE, LANGUAGES),
+        accept_charset: dimension(&header_map, ACCEPT_CHARSET, CHARSETS),
+    })
+}
+
+/// Marks a response whose content coding an endpoint negotiated itself, see [`NotNegotiated`].
+#[derive(Clone, Copy)]
+pub struct Negotiated;
+
+/// Compression predicate keeping the server wide compression off the responses marked [`Negotiated`].
+#[derive(Clone, Copy)]
+pub struct NotNegotiated;
+
+impl tower_http::compression::Predicate for NotNegotiated {
+    fn should_compress<B: HttpBody>(&self, response: &axum::http::Response<B>) -> bool {
+        response.extensions().get::<Negotiated>().is_none()
+    }
+}
+
+#[derive(Serialize)]
+struct EncodingNegotiation {
+    #[serde(flatten)]
+    accept_encoding: Dimension,
+    /// `false` when `identity;q=0`, or `*;q=0` without identity, refuses an uncompressed response
+    identity_acceptable: bool,
+}
+
+/// Answers in the content coding chosen for `Accept-Encoding`, honouring `q=0` as a refusal, and 406 Not
+/// Acceptable when every coding including identity is refused. Without the header the response is not compressed.
+pub async fn negotiate_encoding(request: Request) -> Response {
+    let mut accept_encoding = dimension(request.headers(), ACCEPT_ENCODING, ENCODINGS);
+    if accept_encoding.header.is_none() {
+        accept_encoding.chosen = Some("identity");
+    }
+    let chosen = accept_encoding.chosen;
+    let identity_acceptable =
+        accept_encoding.header.is_none() || choose(&ACCEPT_ENCODING, &accept_encoding.preferences, &["identity"]).is_some();
+    let json = Bytes::from(
+        serde_json::to_vec_pretty(&EncodingNegotiation {
+            accept_encoding,
+            identity_acceptable,
+        })
+        .unwrap_or_default(),
+    );
+    let mut response = match chosen {
+        None => (StatusCode::NOT_ACCEPTABLE, [(CONTENT_TYPE, "application/json")], json).into_response(),
+        Some("identity") => ([(CONTENT_TYPE, "application/json")], json).into_response(),
+        Some(encoding) => ServiceBuilder::new()
+            .layer(SetRequestHeaderLayer::overriding(
+                ACCEPT_ENCODING,
+                HeaderValue::from_static(encoding),
+            ))
+            .layer(CompressionLayer::new())
+            .service_fn(move |_: Request| {
+                let json = json.clone();
+                async move { Ok::<_, Infallible>(([(CONTENT_TYPE, "application/json")], json).into_response()) }
+            })
+            .oneshot(request)
+            .await
+            .into_response(),
+    };
+    response.extensions_mut().insert(Negotiated);
+    response
+}
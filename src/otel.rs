@@ -0,0 +1,62 @@
+use std::sync::OnceLock;
+
+use axum::http::HeaderMap;
+use opentelemetry::{global, propagation::Extractor, trace::TracerProvider as _};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use tracing::{Span, Subscriber};
+use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+use tracing_subscriber::{Layer, registry::LookupSpan};
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// OTLP/HTTP trace export, only enabled when `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+///
+/// The exporter also honors the other standard `OTEL_*` variables, e.g. `OTEL_EXPORTER_OTLP_HEADERS`.
+pub fn layer<S>() -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT")?;
+
+    let exporter = match opentelemetry_otlp::SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("failed to create the OTLP exporter: {e}");
+            return None;
+        }
+    };
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(env!("CARGO_PKG_NAME")).build())
+        .build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    PROVIDER.set(provider).ok();
+
+    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Flushes the spans that are still buffered in the batch exporter.
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get() {
+        provider.shutdown().ok();
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Continues the trace of an incoming `traceparent` header, if there is one.
+pub fn set_parent(span: &Span, headers: &HeaderMap) {
+    let context = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    span.set_parent(context);
+}
@@ -0,0 +1,121 @@
+use axum::{
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::LINK},
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use axum_valid::Garde;
+use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
+use garde::Validate;
+use serde::{Deserialize, Serialize};
+
+use crate::data::ErrorDetail;
+
+pub const MAX_TOTAL: usize = 10_000;
+pub const MAX_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PaginateParam {
+    /// items per page, 10 by default
+    #[garde(range(min = 1, max = MAX_LIMIT))]
+    pub limit: Option<usize>,
+    #[garde(range(min = 0, max = MAX_TOTAL))]
+    pub offset: Option<usize>,
+    /// the opaque `next_cursor` or `prev_cursor` of a previous page, instead of `offset`
+    #[garde(skip)]
+    pub cursor: Option<String>,
+    /// items in the dataset, 100 by default
+    #[garde(range(min = 0, max = MAX_TOTAL))]
+    pub total: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct Item {
+    id: usize,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct Page {
+    items: Vec<Item>,
+    total: usize,
+    limit: usize,
+    /// `null` for cursor pagination
+    offset: Option<usize>,
+    next_cursor: Option<String>,
+    prev_cursor: Option<String>,
+    next: Option<String>,
+    prev: Option<String>,
+}
+
+fn encode_cursor(offset: usize) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(format!("offset:{offset}"))
+}
+
+fn decode_cursor(cursor: &str) -> Option<usize> {
+    let decoded = String::from_utf8(BASE64_URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+    decoded.strip_prefix("offset:")?.parse().ok()
+}
+
+/// A page of a synthetic dataset of `total` items numbered from 1, by `limit` and `offset` or by `cursor`, with
+/// `next` and `prev` links in the body and in an RFC 8288 `Link` header, and the total in `X-Total-Count`.
+pub async fn paginate(Garde(Query(p)): Garde<Query<PaginateParam>>) -> Response {
+    let limit = p.limit.unwrap_or(10);
+    let total = p.total.unwrap_or(100);
+    let (offset, cursor) = match (p.offset, p.cursor) {
+        (Some(_), Some(_)) => return ErrorDetail::response(StatusCode::BAD_REQUEST, "`offset` and `cursor` cannot be used together"),
+        (offset, None) => (offset.unwrap_or(0), false),
+        (None, Some(cursor)) => match decode_cursor(&cursor) {
+            Some(offset) => (offset, true),
+            None => return ErrorDetail::response(StatusCode::BAD_REQUEST, format!("invalid cursor `{cursor}`")),
+        },
+    };
+    if offset > total {
+        return ErrorDetail::response(StatusCode::BAD_REQUEST, format!("offset {offset} is past the {total} items"));
+    }
+
+    let end = (offset + limit).min(total);
+    let items = (offset..end)
+        .map(|index| Item {
+            id: index + 1,
+            name: format!("item {}", index + 1),
+        })
+        .collect();
+    let next_offset = (end < total).then_some(end);
+    let prev_offset = (offset > 0).then(|| offset.saturating_sub(limit));
+    let last_offset = total.saturating_sub(1) / limit * limit;
+
+    let url = |offset: usize| match cursor {
+        true => format!("/paginate?limit={limit}&total={total}&cursor={}", encode_cursor(offset)),
+        false => format!("/paginate?limit={limit}&total={total}&offset={offset}"),
+    };
+    let next = next_offset.map(url);
+    let prev = prev_offset.map(url);
+
+    let mut links = vec![format!(r#"<{}>; rel="first""#, url(0))];
+    links.extend(prev.iter().map(|prev| format!(r#"<{prev}>; rel="prev""#)));
+    links.extend(next.iter().map(|next| format!(r#"<{next}>; rel="next""#)));
+    // a cursor only leads to its neighbours
+    if !cursor {
+        links.push(format!(r#"<{}>; rel="last""#, url(last_offset)));
+    }
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&links.join(", ")) {
+        headers.insert(LINK, value);
+    }
+    headers.insert(HeaderName::from_static("x-total-count"), HeaderValue::from(total));
+
+    (
+        headers,
+        ErasedJson::pretty(Page {
+            items,
+            total,
+            limit,
+            offset: (!cursor).then_some(offset),
+            next_cursor: next_offset.filter(|_| cursor).map(encode_cursor),
+            prev_cursor: prev_offset.filter(|_| cursor).map(encode_cursor),
+            next,
+            prev,
+        }),
+    )
+        .into_response()
+}
@@ -0,0 +1,76 @@
+use axum::{
+    http::Uri,
+    response::{IntoResponse, Response},
+};
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+
+use crate::pretty::Pretty;
+
+const PREFIX: &str = "/path-info";
+
+#[derive(Serialize)]
+struct Segment {
+    raw: String,
+    decoded: String,
+}
+
+#[derive(Serialize)]
+struct PathInfo {
+    /// the path exactly as received, the router matches on it without decoding or normalizing it
+    path: String,
+    rest: String,
+    decoded: String,
+    segments: Vec<Segment>,
+    /// the path after removing dot segments (RFC 3986 section 5.2.4) and collapsing empty segments
+    normalized: String,
+    changed_by_normalization: bool,
+}
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().to_string()
+}
+
+/// Resolves `.` and `..` segments and collapses repeated slashes, never climbing above the root.
+pub fn normalize(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let trailing_slash = path.ends_with('/') || path.ends_with("/.") || path.ends_with("/..");
+    let mut normalized = format!("/{}", segments.join("/"));
+    if trailing_slash && !segments.is_empty() {
+        normalized.push('/');
+    }
+    normalized
+}
+
+pub async fn path_info(pretty: Pretty, uri: Uri) -> Response {
+    let path = uri.path();
+    let rest = path.strip_prefix(PREFIX).unwrap_or(path).trim_start_matches('/');
+    let normalized = normalize(path);
+
+    pretty
+        .json(PathInfo {
+            path: path.to_string(),
+            rest: rest.to_string(),
+            decoded: decode(rest),
+            segments: rest
+                .split('/')
+                .map(|raw| Segment {
+                    raw: raw.to_string(),
+                    decoded: decode(raw),
+                })
+                .collect(),
+            changed_by_normalization: normalized != path,
+            normalized,
+        })
+        .into_response()
+}
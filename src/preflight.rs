@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::{
+    extract::Request,
+    http::{
+        HeaderMap, HeaderValue, Method, StatusCode, Uri,
+        header::{
+            ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+            ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN, VARY,
+        },
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use serde::{Deserialize, Serialize};
+
+use crate::data::ErrorDetail;
+
+pub const PATH: &str = "/preflight-cache";
+/// Firefox caps `Access-Control-Max-Age` at a day, Chromium at two hours.
+pub const MAX_AGE: u64 = 86400;
+
+/// Preflights answered so far, a browser that caches them stops sending them.
+static PREFLIGHTS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Deserialize)]
+pub struct PreflightParam {
+    /// seconds
+    pub max_age: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct Preflights {
+    preflights: u64,
+}
+
+/// Answers `OPTIONS` with the requested `Access-Control-Max-Age`, reflecting the origin, method and headers
+/// of the preflight. Other methods return the number of preflights seen, to tell if the browser cached them.
+pub async fn preflight_cache(method: Method, uri: Uri, header_map: HeaderMap) -> Response {
+    if method != Method::OPTIONS {
+        return ErasedJson::pretty(Preflights {
+            preflights: PREFLIGHTS.load(Ordering::Relaxed),
+        })
+        .into_response();
+    }
+
+    let max_age = match Query::<PreflightParam>::try_from_uri(&uri) {
+        Ok(Query(PreflightParam { max_age })) if max_age.is_none_or(|it| it <= MAX_AGE) => max_age.unwrap_or(5),
+        _ => {
+            let detail = format!("max_age must be a number of seconds up to {MAX_AGE}");
+            return (
+                StatusCode::BAD_REQUEST,
+                ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", detail)),
+            )
+                .into_response();
+        }
+    };
+    let preflights = PREFLIGHTS.fetch_add(1, Ordering::Relaxed) + 1;
+
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    let headers = response.headers_mut();
+    match header_map.get(ORIGIN) {
+        Some(origin) => {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+            headers.insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+        }
+        None => {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        }
+    }
+    if let Some(method) = header_map.get(ACCESS_CONTROL_REQUEST_METHOD) {
+        headers.insert(ACCESS_CONTROL_ALLOW_METHODS, method.clone());
+    }
+    if let Some(request_headers) = header_map.get(ACCESS_CONTROL_REQUEST_HEADERS) {
+        headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, request_headers.clone());
+    }
+    headers.insert(ACCESS_CONTROL_MAX_AGE, max_age.into());
+    headers.insert(
+        VARY,
+        HeaderValue::from_static("origin, access-control-request-method, access-control-request-headers"),
+    );
+    headers.insert("x-preflight-count", preflights.into());
+    response
+}
+
+/// The global CORS layer answers every preflight itself, this sits in front of it so that
+/// `/preflight-cache` preflights reach [`preflight_cache`].
+pub async fn bypass_cors(request: Request, next: Next) -> Response {
+    if request.method() == Method::OPTIONS && request.uri().path() == PATH {
+        let (parts, _) = request.into_parts();
+        return preflight_cache(parts.method, parts.uri, parts.headers).await;
+    }
+    next.run(request).await
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use axum_extra::{
+    extract::{Query, QueryRejection},
+    response::ErasedJson,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+#[derive(Deserialize)]
+pub struct PrettyParam {
+    pub pretty: Option<bool>,
+}
+
+/// Whether a reflection endpoint indents its JSON: `?pretty=true`, or `--pretty` unless `?pretty=false`.
+/// Compact otherwise, chosen when serializing rather than by rewriting the body afterwards.
+#[derive(Debug, Clone, Copy)]
+pub struct Pretty(pub bool);
+
+impl Pretty {
+    pub fn json(self, value: impl Serialize) -> ErasedJson {
+        if self.0 {
+            ErasedJson::pretty(value)
+        } else {
+            ErasedJson::new(value)
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Pretty {
+    type Rejection = QueryRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(PrettyParam { pretty }) = Query::from_request_parts(parts, state).await?;
+        let default = parts.extensions.get::<Arc<Config>>().is_some_and(|config| config.pretty);
+        Ok(Pretty(pretty.unwrap_or(default)))
+    }
+}
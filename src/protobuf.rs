@@ -0,0 +1,72 @@
+use axum::{
+    body::Bytes,
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use prost::Message as _;
+use serde::{Deserialize, Serialize};
+
+use crate::data::ErrorDetail;
+
+/// The message accepted by `/protobuf`:
+///
+/// ```proto
+/// message Reflect {
+///   string message = 1;
+///   int64 number = 2;
+///   repeated string tags = 3;
+///   bytes payload = 4;
+/// }
+/// ```
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Reflect {
+    #[prost(string, tag = "1")]
+    pub message: String,
+    #[prost(int64, tag = "2")]
+    pub number: i64,
+    #[prost(string, repeated, tag = "3")]
+    pub tags: Vec<String>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub payload: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct ReflectJson {
+    message: String,
+    number: i64,
+    tags: Vec<String>,
+    /// base64 encoded
+    payload: String,
+}
+
+#[derive(Deserialize)]
+pub struct ProtobufParam {
+    #[serde(rename = "as")]
+    pub as_: Option<String>,
+}
+
+pub async fn protobuf(Query(ProtobufParam { as_ }): Query<ProtobufParam>, body: Bytes) -> Response {
+    let reflect = match Reflect::decode(body) {
+        Ok(reflect) => reflect,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", format!("invalid Reflect message: {e}"))),
+            )
+                .into_response();
+        }
+    };
+
+    match as_.as_deref() {
+        Some("json") => ErasedJson::pretty(ReflectJson {
+            payload: BASE64_STANDARD.encode(&reflect.payload),
+            message: reflect.message,
+            number: reflect.number,
+            tags: reflect.tags,
+        })
+        .into_response(),
+        _ => ([(CONTENT_TYPE, "application/x-protobuf")], reflect.encode_to_vec()).into_response(),
+    }
+}
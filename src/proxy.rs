@@ -0,0 +1,143 @@
+use std::{
+    io,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use axum::{
+    Extension,
+    body::Body,
+    extract::Request,
+    http::{
+        HeaderMap, HeaderName, StatusCode, Uri,
+        header::{CONNECTION, CONTENT_LENGTH, HOST, PROXY_AUTHENTICATE, PROXY_AUTHORIZATION, TE, TRAILER, TRANSFER_ENCODING, UPGRADE},
+    },
+    response::Response,
+};
+use futures_util::StreamExt as _;
+
+use crate::{
+    config::{self, Config},
+    data::ErrorDetail,
+    negotiate,
+};
+
+pub const PREFIX: &str = "/proxy/";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// For the whole exchange, the streamed response body included.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The headers of a single connection, never forwarded either way, RFC 9110 section 7.6.1.
+static HOP_BY_HOP: [HeaderName; 8] = [
+    CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    PROXY_AUTHENTICATE,
+    PROXY_AUTHORIZATION,
+    TE,
+    TRAILER,
+    TRANSFER_ENCODING,
+    UPGRADE,
+];
+
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(TIMEOUT)
+        // a redirect is reflected to the client, following it could leave the allowlist
+        .redirect(reqwest::redirect::Policy::none())
+        // and so could an `HTTP_PROXY` of the environment
+        .no_proxy()
+        .build()
+        .expect("a client without custom TLS settings")
+});
+
+/// Removes the hop-by-hop headers, those the `Connection` header names included.
+fn end_to_end(mut headers: HeaderMap) -> HeaderMap {
+    let named: Vec<HeaderName> = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|it| it.to_str().ok())
+        .flat_map(|it| it.split(','))
+        .filter_map(|it| HeaderName::try_from(it.trim()).ok())
+        .collect();
+    for name in HOP_BY_HOP.iter().chain(&named) {
+        headers.remove(name);
+    }
+    headers
+}
+
+/// Forwards the request to the http(s) URL after `/proxy/`, the query included, when its host is in
+/// `--allow-proxy`, and streams the upstream response back with its status and end-to-end headers unchanged.
+/// Redirects are not followed. Bodies are capped by `--max-body-bytes` both ways: a larger request is answered 413,
+/// a larger response 502 when its `Content-Length` says so, or cut short otherwise. The upstream has 5s to accept
+/// the connection and 30s for the whole exchange, 504 past them.
+pub async fn proxy(Extension(config): Extension<Arc<Config>>, request: Request) -> Response {
+    // the raw path, the `{*url}` capture would be percent-decoded
+    let url = request.uri().path().strip_prefix(PREFIX).unwrap_or_default();
+    let url = match request.uri().query() {
+        Some(query) => format!("{url}?{query}"),
+        None => url.to_string(),
+    };
+    let target: Uri = match url.parse() {
+        Ok(target) => target,
+        Err(e) => return ErrorDetail::response(StatusCode::BAD_REQUEST, format!("invalid target URL `{url}`: {e}")),
+    };
+    if !matches!(target.scheme_str(), Some("http" | "https")) {
+        return ErrorDetail::response(
+            StatusCode::BAD_REQUEST,
+            format!("the target URL `{url}` is not an http or https URL"),
+        );
+    }
+    let host = target.host().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if !config::host_allowed(&config.proxy_allowlist, &host) {
+        return ErrorDetail::response(StatusCode::FORBIDDEN, format!("the target host `{host}` is not in --allow-proxy"));
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, config.max_body_bytes).await {
+        Ok(body) => body,
+        Err(_) => {
+            let detail = format!("request body is larger than {} bytes", config.max_body_bytes);
+            return ErrorDetail::response(StatusCode::PAYLOAD_TOO_LARGE, detail);
+        }
+    };
+    let mut headers = end_to_end(parts.headers);
+    // both follow from the target URL and the body
+    headers.remove(HOST);
+    headers.remove(CONTENT_LENGTH);
+    let upstream = CLIENT
+        .request(parts.method, target.to_string())
+        .headers(headers)
+        .body(body)
+        .send()
+        .await;
+    let upstream = match upstream {
+        Ok(upstream) => upstream,
+        Err(e) if e.is_timeout() => return ErrorDetail::response(StatusCode::GATEWAY_TIMEOUT, format!("the upstream timed out: {e}")),
+        Err(e) => return ErrorDetail::response(StatusCode::BAD_GATEWAY, format!("the upstream failed: {e}")),
+    };
+
+    let max = config.max_body_bytes;
+    if upstream.content_length().is_some_and(|len| len > max as u64) {
+        return ErrorDetail::response(StatusCode::BAD_GATEWAY, format!("the upstream response is larger than {max} bytes"));
+    }
+    let status = upstream.status();
+    let headers = end_to_end(upstream.headers().clone());
+    let mut streamed = 0;
+    let body = upstream.bytes_stream().map(move |chunk| {
+        let chunk = chunk.map_err(io::Error::other)?;
+        streamed += chunk.len();
+        match streamed > max {
+            true => Err(io::Error::other(format!("the upstream response is larger than {max} bytes"))),
+            false => Ok(chunk),
+        }
+    });
+    let mut response = Response::new(Body::from_stream(body));
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    // the body is passed on as it came, not compressed again
+    response.extensions_mut().insert(negotiate::Negotiated);
+    response
+}
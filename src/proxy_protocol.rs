@@ -0,0 +1,123 @@
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Connections that have not sent a complete PROXY header by then are dropped.
+pub const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// `PROXY UNKNOWN ffff:f...f:ffff ffff:f...f:ffff 65535 65535\r\n`
+const V1_MAX_LENGTH: usize = 107;
+/// The v2 TLV carrying the protocol the proxy negotiated with the client, `send-proxy-v2-ssl` in HAProxy.
+const PP2_TYPE_ALPN: u8 = 0x01;
+
+/// What a PROXY header tells about the client side of the proxy.
+#[derive(Debug, Clone, Default)]
+pub struct Header {
+    /// `None` for `LOCAL`/`UNKNOWN` connections, e.g. health checks of the balancer itself
+    pub source: Option<SocketAddr>,
+    /// the `PP2_TYPE_ALPN` of a v2 header, sent by proxies that terminate TLS
+    pub alpn: Option<ProxiedAlpn>,
+}
+
+/// The ALPN protocol id a TLS terminating proxy negotiated with the client, added to the requests.
+#[derive(Debug, Clone)]
+pub struct ProxiedAlpn(pub String);
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Consumes the PROXY header, exactly, and returns the source address and ALPN it carries.
+pub async fn read_header<R: AsyncRead + Unpin>(stream: &mut R) -> io::Result<Header> {
+    let mut prefix = [0; 12];
+    stream.read_exact(&mut prefix[..5]).await?;
+    if &prefix[..5] == b"PROXY" {
+        let mut line = b"PROXY".to_vec();
+        while !line.ends_with(b"\r\n") {
+            if line.len() >= V1_MAX_LENGTH {
+                return Err(invalid("PROXY v1 header is too long"));
+            }
+            line.push(stream.read_u8().await?);
+        }
+        let line = std::str::from_utf8(&line).map_err(|_| invalid("PROXY v1 header is not ASCII"))?;
+        let source = parse_v1(line.trim_end_matches("\r\n"))?;
+        return Ok(Header { source, alpn: None });
+    }
+
+    stream.read_exact(&mut prefix[5..]).await?;
+    if prefix != V2_SIGNATURE {
+        return Err(invalid("missing PROXY header"));
+    }
+    let mut header = [0; 4];
+    stream.read_exact(&mut header).await?;
+    let [version_command, family, len @ ..] = header;
+    let mut addresses = vec![0; u16::from_be_bytes(len).into()];
+    stream.read_exact(&mut addresses).await?;
+    parse_v2(version_command, family, &addresses)
+}
+
+fn parse_v1(line: &str) -> io::Result<Option<SocketAddr>> {
+    let fields: Vec<_> = line.split(' ').collect();
+    match fields[..] {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", protocol @ ("TCP4" | "TCP6"), source, _destination, port, _destination_port] => {
+            let ip: IpAddr = source.parse().map_err(|_| invalid(format!("invalid source address `{source}`")))?;
+            if ip.is_ipv4() != (protocol == "TCP4") {
+                return Err(invalid(format!("`{source}` is not a {protocol} address")));
+            }
+            let port: u16 = port.parse().map_err(|_| invalid(format!("invalid source port `{port}`")))?;
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err(invalid(format!("invalid PROXY v1 header `{line}`"))),
+    }
+}
+
+fn parse_v2(version_command: u8, family: u8, addresses: &[u8]) -> io::Result<Header> {
+    if version_command >> 4 != 2 {
+        return Err(invalid(format!("unsupported PROXY version {}", version_command >> 4)));
+    }
+    match version_command & 0x0f {
+        0x0 => return Ok(Header::default()),
+        0x1 => {}
+        command => return Err(invalid(format!("unsupported PROXY v2 command {command}"))),
+    }
+
+    let port = |at: usize| u16::from_be_bytes([addresses[at], addresses[at + 1]]);
+    // the high nibble is the address family, the low one the transport; TLVs may follow the addresses
+    let (source, tlvs) = match family >> 4 {
+        0x1 if addresses.len() >= 12 => {
+            let ip = Ipv4Addr::from(<[u8; 4]>::try_from(&addresses[..4]).expect("length checked"));
+            (Some(SocketAddr::new(ip.into(), port(8))), &addresses[12..])
+        }
+        0x2 if addresses.len() >= 36 => {
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(&addresses[..16]).expect("length checked"));
+            (Some(SocketAddr::new(ip.into(), port(32))), &addresses[36..])
+        }
+        0x1 | 0x2 => return Err(invalid("truncated PROXY v2 addresses")),
+        // AF_UNSPEC and AF_UNIX, whose two paths take 216 bytes
+        0x0 => (None, addresses),
+        0x3 => (None, addresses.get(216..).unwrap_or_default()),
+        family => return Err(invalid(format!("unsupported PROXY v2 address family {family}"))),
+    };
+    Ok(Header {
+        source,
+        alpn: alpn(tlvs).map(ProxiedAlpn),
+    })
+}
+
+/// The value of the `PP2_TYPE_ALPN` TLV, each TLV is a type byte, a big-endian length and the value.
+fn alpn(mut tlvs: &[u8]) -> Option<String> {
+    while let [kind, high, low, rest @ ..] = tlvs {
+        let value = rest.get(..usize::from(u16::from_be_bytes([*high, *low])))?;
+        if *kind == PP2_TYPE_ALPN {
+            return Some(String::from_utf8_lossy(value).into_owned());
+        }
+        tlvs = &rest[value.len()..];
+    }
+    None
+}
@@ -0,0 +1,108 @@
+use axum::{
+    http::Uri,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::pretty::Pretty;
+
+/// Keys nested deeper than this, counting the top level, are reported as conflicts instead of parsed.
+pub const MAX_DEPTH: usize = 32;
+
+/// A step into the nested structure: `a.b` and `a[b]` are keys, `a[]` appends to an array.
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Key(&'a str),
+    Push,
+}
+
+fn tokenize(key: &str) -> Vec<Token<'_>> {
+    let end = key.find(['[', '.']).unwrap_or(key.len());
+    let mut tokens = vec![Token::Key(&key[..end])];
+
+    let mut rest = &key[end..];
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let Some(close) = after.find(']') else {
+                // an unclosed bracket is part of the name
+                tokens.push(Token::Key(rest));
+                break;
+            };
+            tokens.push(match &after[..close] {
+                "" => Token::Push,
+                name => Token::Key(name),
+            });
+            rest = &after[close + 1..];
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['[', '.']).unwrap_or(after.len());
+            tokens.push(Token::Key(&after[..end]));
+            rest = &after[end..];
+        } else {
+            tokens.push(Token::Key(rest));
+            break;
+        }
+    }
+    tokens
+}
+
+/// Returns `false` when the pair conflicts with what is already there, like `a=1&a.b=2`.
+fn insert(target: &mut Value, path: &[Token], leaf: &str) -> bool {
+    match path.split_first() {
+        None => match target {
+            Value::Null => *target = Value::String(leaf.to_string()),
+            Value::String(existing) => {
+                let existing = std::mem::take(existing);
+                *target = Value::Array(vec![Value::String(existing), Value::String(leaf.to_string())]);
+            }
+            Value::Array(values) => values.push(Value::String(leaf.to_string())),
+            _ => return false,
+        },
+        Some((Token::Key(key), rest)) => {
+            if target.is_null() {
+                *target = Value::Object(Map::new());
+            }
+            let Value::Object(map) = target else {
+                return false;
+            };
+            return insert(map.entry(key.to_string()).or_insert(Value::Null), rest, leaf);
+        }
+        Some((Token::Push, rest)) => {
+            if target.is_null() {
+                *target = Value::Array(Vec::new());
+            }
+            let Value::Array(values) = target else {
+                return false;
+            };
+            values.push(Value::Null);
+            return values.last_mut().is_some_and(|last| insert(last, rest, leaf));
+        }
+    }
+    true
+}
+
+#[derive(Serialize)]
+struct ParsedQuery {
+    parsed: Value,
+    pairs: Vec<(String, String)>,
+    conflicts: Vec<(String, String)>,
+}
+
+/// Parses bracket (`a[]=1`, `a[b]=c`) and dot (`a.b=c`) notation into nested JSON,
+/// repeated plain keys become arrays. Pairs nested deeper than [`MAX_DEPTH`] go to the conflicts.
+pub async fn query(pretty: Pretty, uri: Uri) -> Response {
+    let pairs: Vec<(String, String)> = form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+    let mut parsed = Value::Object(Map::new());
+    let mut conflicts = Vec::new();
+    for (k, v) in &pairs {
+        let path = tokenize(k);
+        if path.len() > MAX_DEPTH || !insert(&mut parsed, &path, v) {
+            conflicts.push((k.clone(), v.clone()));
+        }
+    }
+
+    pretty.json(ParsedQuery { parsed, pairs, conflicts }).into_response()
+}
@@ -0,0 +1,90 @@
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::Path,
+    http::{
+        HeaderMap, HeaderName, HeaderValue, StatusCode,
+        header::{CONTENT_TYPE, TRAILER},
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use axum_valid::Garde;
+use garde::Validate;
+use hyper::body::Frame;
+use serde::Deserialize;
+
+use crate::data::ErrorDetail;
+
+pub const MAX_LINES: usize = 10_000;
+
+static X_LINES_INTENDED: HeaderName = HeaderName::from_static("x-lines-intended");
+static X_LINES_SENT: HeaderName = HeaderName::from_static("x-lines-sent");
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RandomDropParam {
+    /// the probability of leaving out each line, 0.1 by default
+    #[garde(range(min = 0.0, max = 1.0))]
+    pub drop_rate: Option<f64>,
+    #[garde(skip)]
+    pub seed: Option<u64>,
+}
+
+/// The lines kept, one frame each, followed by the trailers counting them.
+struct Lossy {
+    lines: std::vec::IntoIter<Bytes>,
+    trailers: Option<HeaderMap>,
+}
+
+impl HttpBody for Lossy {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        if let Some(line) = self.lines.next() {
+            return Poll::Ready(Some(Ok(Frame::data(line))));
+        }
+        Poll::Ready(self.trailers.take().map(|trailers| Ok(Frame::trailers(trailers))))
+    }
+}
+
+/// Streams `n` JSON lines numbered by `seq`, leaving each out with probability `drop_rate`, and ends with the
+/// `X-Lines-Intended` and `X-Lines-Sent` trailers. hyper only sends HTTP/1.1 trailers to a client that asked for
+/// them with `TE: trailers`, HTTP/2 always gets them.
+pub async fn random_drop(Path(n): Path<usize>, Garde(Query(p)): Garde<Query<RandomDropParam>>) -> Response {
+    if !(1..=MAX_LINES).contains(&n) {
+        let detail = format!("n must be in 1..={MAX_LINES}");
+        return (
+            StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", detail)),
+        )
+            .into_response();
+    }
+    let drop_rate = p.drop_rate.unwrap_or(0.1);
+    let mut rng = p.seed.map(fastrand::Rng::with_seed).unwrap_or_default();
+    let lines: Vec<_> = (1..=n)
+        .filter(|_| rng.f64() >= drop_rate)
+        .map(|seq| Bytes::from(format!("{{\"seq\":{seq},\"of\":{n}}}\n")))
+        .collect();
+
+    let mut trailers = HeaderMap::new();
+    trailers.insert(X_LINES_INTENDED.clone(), HeaderValue::from(n));
+    trailers.insert(X_LINES_SENT.clone(), HeaderValue::from(lines.len()));
+    let body = Body::new(Lossy {
+        lines: lines.into_iter(),
+        trailers: Some(trailers),
+    });
+    (
+        [
+            (CONTENT_TYPE, HeaderValue::from_static("application/x-ndjson")),
+            (TRAILER, HeaderValue::from_static("x-lines-intended, x-lines-sent")),
+        ],
+        body,
+    )
+        .into_response()
+}
@@ -0,0 +1,142 @@
+use std::ops::Range;
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Path, Request},
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Resolved {
+    /// an unparseable header, or several ranges, which may be ignored
+    Full,
+    Partial(Range<usize>),
+    Unsatisfiable,
+}
+
+/// Resolves a `Range` header against a body of `len` bytes.
+pub fn resolve(range: &str, len: usize) -> Resolved {
+    parse(range, len).map_or(Resolved::Full, |range| {
+        if range.is_empty() {
+            Resolved::Unsatisfiable
+        } else {
+            Resolved::Partial(range)
+        }
+    })
+}
+
+fn parse(range: &str, len: usize) -> Option<Range<usize>> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.trim().split_once('-')?;
+    let range = match (start.trim(), end.trim()) {
+        ("", "") => return None,
+        ("", suffix) => {
+            let suffix: usize = suffix.parse().ok()?;
+            len.saturating_sub(suffix)..len
+        }
+        (start, end) => {
+            let start: usize = start.parse().ok()?;
+            let end = match end {
+                "" => usize::MAX,
+                end => end.parse::<usize>().ok()?.saturating_add(1),
+            };
+            if end <= start {
+                return None;
+            }
+            // empty, so unsatisfiable, when starting past the end
+            start.min(len)..end.min(len)
+        }
+    };
+    Some(range)
+}
+
+/// Whether an `If-Range` validator is the current one of a response: its strong `ETag`, compared strongly so a
+/// weak `W/"x"` never matches, or exactly its `Last-Modified` date.
+pub fn if_range_matches(if_range: &str, headers: &HeaderMap) -> bool {
+    let if_range = if_range.trim();
+    let header = |name| headers.get(name).and_then(|it: &HeaderValue| it.to_str().ok());
+    if if_range.starts_with('"') {
+        header(ETAG).is_some_and(|etag| etag == if_range)
+    } else {
+        header(LAST_MODIFIED).is_some_and(|last_modified| last_modified == if_range)
+    }
+}
+
+/// Whether the request turned range support off with `?ranges=off`.
+fn ranges_off(request: &Request) -> bool {
+    let query = request.uri().query().unwrap_or_default();
+    form_urlencoded::parse(query.as_bytes()).any(|(name, value)| name == "ranges" && value == "off")
+}
+
+/// Serves ranges of successful responses: 206 with `Content-Range` for a satisfiable `Range`, 416 otherwise. An
+/// `If-Range` that is not the current validator gets the full 200 body instead, as the client's copy is stale.
+/// With `?ranges=off` the response is left as a server without range support sends it: no `Accept-Ranges`, and
+/// the full 200 body whatever the `Range`.
+pub async fn ranges(request: Request, next: Next) -> Response {
+    if ranges_off(&request) {
+        return next.run(request).await;
+    }
+    let header = |name| request.headers().get(name).and_then(|it| it.to_str().ok()).map(str::to_string);
+    let (range, if_range) = (header(RANGE), header(IF_RANGE));
+    let mut response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    response.headers_mut().insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    let Some(range) = range else {
+        return response;
+    };
+    if if_range.is_some_and(|if_range| !if_range_matches(&if_range, response.headers())) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let len = bytes.len();
+    match resolve(&range, len) {
+        Resolved::Full => Response::from_parts(parts, Body::from(bytes)),
+        Resolved::Partial(range) => {
+            parts.status = StatusCode::PARTIAL_CONTENT;
+            let content_range = format!("bytes {}-{}/{len}", range.start, range.end - 1);
+            parts
+                .headers
+                .insert(CONTENT_RANGE, HeaderValue::from_str(&content_range).expect("digits"));
+            parts.headers.remove(CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(bytes.slice(range)))
+        }
+        Resolved::Unsatisfiable => {
+            parts.status = StatusCode::RANGE_NOT_SATISFIABLE;
+            parts
+                .headers
+                .insert(CONTENT_RANGE, HeaderValue::from_str(&format!("bytes */{len}")).expect("digits"));
+            parts.headers.remove(CONTENT_LENGTH);
+            Response::from_parts(parts, Body::empty())
+        }
+    }
+}
+
+pub const MAX_IF_RANGE_BYTES: usize = 1024 * 1024;
+/// `/if-range/{n}` bodies never change, so neither does their `Last-Modified`.
+pub const IF_RANGE_LAST_MODIFIED: &str = "Thu, 01 Jan 2026 00:00:00 GMT";
+
+/// `min(n, 1MiB)` bytes of `abc…z` repeated, with a strong `ETag` and a fixed `Last-Modified` to send back in
+/// `If-Range`, for resuming a download with a matching or a stale validator.
+pub async fn if_range(Path(n): Path<usize>) -> Response {
+    let body: Vec<u8> = (b'a'..=b'z').cycle().take(n.min(MAX_IF_RANGE_BYTES)).collect();
+    (
+        [(CONTENT_TYPE, "application/octet-stream"), (LAST_MODIFIED, IF_RANGE_LAST_MODIFIED)],
+        body,
+    )
+        .into_response()
+}
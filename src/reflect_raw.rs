@@ -0,0 +1,171 @@
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    time::Instant,
+};
+
+use axum::{
+    Extension,
+    body::HttpBody as _,
+    extract::{ConnectInfo, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_client_ip::InsecureClientIp;
+use axum_extra::response::ErasedJson;
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    data::{ErrorDetail, PeerCred, TlsInfo},
+    header_order::{self, RawHeads},
+    pretty::Pretty,
+    serve::ConnectionReuse,
+};
+
+#[derive(Serialize)]
+struct Reflected {
+    method: String,
+    /// the request target as received, `origin-form` unless the client used another form
+    target: String,
+    target_form: &'static str,
+    protocol: String,
+    /// `[name, value]` pairs, see [`Reflected::headers_preserved`]
+    headers: Vec<(String, String)>,
+    /// `false` when `headers` are in `HeaderMap` order and casing rather than those of the wire, as for `/header-order`
+    headers_preserved: bool,
+    trailers: BTreeMap<String, Vec<String>>,
+    body: ReflectedBody,
+    client: Client,
+    connection: Option<Connection>,
+    /// `null` unless served over HTTP/3, TCP and Unix socket connections are plain text
+    tls: Option<TlsInfo>,
+    timing: Timing,
+}
+
+#[derive(Serialize)]
+struct ReflectedBody {
+    bytes: usize,
+    /// `utf-8`, or `base64` for a body that is not UTF-8
+    encoding: &'static str,
+    data: String,
+}
+
+#[derive(Serialize)]
+struct Client {
+    /// the client address, taken from forwarding headers such as `X-Forwarded-For` when present
+    origin: IpAddr,
+    /// the address of the socket peer, `null` over a Unix domain socket
+    peer: Option<SocketAddr>,
+    /// the credentials of a Unix domain socket peer
+    unix_peer: Option<PeerCred>,
+}
+
+#[derive(Serialize)]
+struct Connection {
+    id: u64,
+    /// the requests served on the connection so far, this one included
+    request: u64,
+}
+
+#[derive(Serialize)]
+struct Timing {
+    /// when the handler started, after the head was parsed
+    received_at: jiff::Timestamp,
+    /// how long reading the body and its trailers took
+    body_ms: f64,
+}
+
+/// Everything known about a request in one object: the target as sent, the headers in wire order, the body and
+/// its trailers, the client and its connection, TLS and timing. The body is read to its end, up to
+/// `--max-body-bytes`, without decoding any `Content-Encoding`.
+pub async fn reflect_raw(
+    pretty: Pretty,
+    InsecureClientIp(origin): InsecureClientIp,
+    Extension(config): Extension<Arc<Config>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    unix_peer: Option<Extension<PeerCred>>,
+    raw_heads: Option<Extension<RawHeads>>,
+    reuse: Option<Extension<ConnectionReuse>>,
+    tls: Option<Extension<TlsInfo>>,
+    request: Request,
+) -> Response {
+    let received_at = jiff::Timestamp::now();
+    let connection = reuse.map(|Extension(reuse)| Connection {
+        id: reuse.connection_id,
+        request: reuse.request,
+    });
+    let (parts, mut body) = request.into_parts();
+    let (headers_preserved, headers) = header_order::ordered(parts.version, &parts.headers, raw_heads, reuse);
+
+    let started = Instant::now();
+    let mut data = Vec::new();
+    let mut trailers = BTreeMap::<String, Vec<String>>::new();
+    while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", e.to_string())),
+                )
+                    .into_response();
+            }
+        };
+        match frame.into_data() {
+            Ok(chunk) => data.extend_from_slice(&chunk),
+            Err(frame) => {
+                for (name, value) in frame.into_trailers().iter().flatten() {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    trailers.entry(name.to_string()).or_default().push(value);
+                }
+            }
+        }
+        if data.len() > config.max_body_bytes {
+            let detail = format!("request body is larger than {} bytes", config.max_body_bytes);
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErasedJson::pretty(ErrorDetail::new(413, "Payload Too Large", detail)),
+            )
+                .into_response();
+        }
+    }
+    let body_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    let body = match String::from_utf8(data) {
+        Ok(text) => ReflectedBody {
+            bytes: text.len(),
+            encoding: "utf-8",
+            data: text,
+        },
+        Err(e) => ReflectedBody {
+            bytes: e.as_bytes().len(),
+            encoding: "base64",
+            data: BASE64_STANDARD.encode(e.as_bytes()),
+        },
+    };
+
+    pretty
+        .json(Reflected {
+            method: parts.method.to_string(),
+            target: parts.uri.to_string(),
+            target_form: crate::target_form(&parts.uri),
+            protocol: format!("{:?}", parts.version),
+            headers,
+            headers_preserved,
+            trailers,
+            body,
+            client: Client {
+                origin,
+                peer: connect_info.map(|Extension(ConnectInfo(peer))| peer),
+                unix_peer: unix_peer.map(|Extension(peer)| peer),
+            },
+            connection,
+            tls: tls.map(|Extension(tls)| tls),
+            timing: Timing { received_at, body_ms },
+        })
+        .into_response()
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, LazyLock},
+};
+
+use axum::{
+    Extension, Json,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{admin, config::Config, data::ErrorDetail};
+
+pub const MAX_RESPONSES: usize = 100;
+
+/// The configured responses and the index of the one `/rotate` serves next.
+static ROTATION: LazyLock<Arc<Mutex<Rotation>>> = LazyLock::new(Default::default);
+
+#[derive(Default)]
+struct Rotation {
+    responses: Vec<Canned>,
+    next: usize,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Canned {
+    #[serde(default = "ok")]
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+fn ok() -> u16 {
+    200
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RotateSpec {
+    pub responses: Vec<Canned>,
+}
+
+#[derive(Serialize)]
+struct Configured {
+    responses: Vec<Canned>,
+    next: usize,
+}
+
+fn validate(canned: &Canned) -> Result<(), String> {
+    StatusCode::from_u16(canned.status).map_err(|e| format!("invalid status {}: {e}", canned.status))?;
+    for (name, value) in &canned.headers {
+        HeaderName::try_from(name.as_str()).map_err(|e| format!("invalid header name `{name}`: {e}"))?;
+        HeaderValue::try_from(value.as_str()).map_err(|e| format!("invalid header value `{value}`: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Replaces the responses `/rotate` cycles through, starting over from the first.
+pub async fn configure(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap, Json(spec): Json<RotateSpec>) -> Response {
+    if let Err(response) = admin::authorize(&config, &header_map) {
+        return response;
+    }
+    if spec.responses.is_empty() || spec.responses.len() > MAX_RESPONSES {
+        return ErrorDetail::response(
+            StatusCode::BAD_REQUEST,
+            format!("`responses` must have 1 to {MAX_RESPONSES} entries"),
+        );
+    }
+    for (index, canned) in spec.responses.iter().enumerate() {
+        if let Err(detail) = validate(canned) {
+            return ErrorDetail::response(StatusCode::BAD_REQUEST, format!("response {index}: {detail}"));
+        }
+    }
+    let mut rotation = ROTATION.lock().await;
+    *rotation = Rotation {
+        responses: spec.responses,
+        next: 0,
+    };
+    ErasedJson::pretty(Configured {
+        responses: rotation.responses.clone(),
+        next: 0,
+    })
+    .into_response()
+}
+
+/// Starts the rotation over from the first response, keeping the responses.
+pub async fn reset(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap) -> Response {
+    if let Err(response) = admin::authorize(&config, &header_map) {
+        return response;
+    }
+    let mut rotation = ROTATION.lock().await;
+    rotation.next = 0;
+    ErasedJson::pretty(Configured {
+        responses: rotation.responses.clone(),
+        next: 0,
+    })
+    .into_response()
+}
+
+/// Serves the configured responses one after the other, going back to the first after the last, with the
+/// index of the one served in `X-Rotate-Index`. 404 until `PUT /admin/rotate` configured some.
+pub async fn rotate() -> Response {
+    let (index, canned) = {
+        let mut rotation = ROTATION.lock().await;
+        if rotation.responses.is_empty() {
+            let detail = "no responses configured, `PUT /admin/rotate` first";
+            return (
+                StatusCode::NOT_FOUND,
+                ErasedJson::pretty(ErrorDetail::new(404, "Not Found", detail)),
+            )
+                .into_response();
+        }
+        let index = rotation.next;
+        rotation.next = (index + 1) % rotation.responses.len();
+        (index, rotation.responses[index].clone())
+    };
+
+    let mut response = (StatusCode::from_u16(canned.status).unwrap_or(StatusCode::OK), canned.body).into_response();
+    for (name, value) in &canned.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name.as_str()), HeaderValue::try_from(value.as_str())) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+        .headers_mut()
+        .insert(HeaderName::from_static("x-rotate-index"), HeaderValue::from(index));
+    response
+}
@@ -0,0 +1,302 @@
+use std::{
+    convert::Infallible,
+    io,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll, ready},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    body::Body,
+    extract::ConnectInfo,
+    http::{Extensions, HeaderName, HeaderValue, Request, Version, header::CONNECTION},
+};
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::conn::auto,
+    service::TowerToHyperService,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::watch,
+};
+use tower::ServiceExt as _;
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Counts the requests served on one connection, shared by all of them.
+#[derive(Clone)]
+pub struct ConnectionCounter {
+    id: u64,
+    requests: Arc<AtomicU64>,
+}
+
+/// Which connection a request arrived on and how many came before it, the first request is `1`.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionReuse {
+    pub connection_id: u64,
+    pub request: u64,
+}
+
+impl Default for ConnectionCounter {
+    fn default() -> Self {
+        ConnectionCounter {
+            id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            requests: Arc::default(),
+        }
+    }
+}
+
+impl ConnectionCounter {
+    pub fn next(&self) -> ConnectionReuse {
+        ConnectionReuse {
+            connection_id: self.id,
+            request: self.requests.fetch_add(1, Ordering::Relaxed) + 1,
+        }
+    }
+}
+
+/// When the connection a request arrived on was accepted.
+#[derive(Debug, Clone, Copy)]
+pub struct Accepted(pub Instant);
+
+/// Per-connection data added to the extensions of every request served on the connection.
+pub trait ConnectionExtensions {
+    fn extensions(&self) -> Extensions {
+        Extensions::new()
+    }
+}
+
+#[cfg(unix)]
+impl ConnectionExtensions for tokio::net::UnixStream {}
+
+/// Connection level settings `axum::serve` has no knobs for.
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub header_read_timeout: Duration,
+    /// closes connections idle for this long between requests
+    pub keepalive_timeout: Option<Duration>,
+    /// closes HTTP/1 connections after this many requests
+    pub keepalive_max_requests: Option<u64>,
+}
+
+/// When a connection last read or wrote, and how many of its requests are being handled.
+#[derive(Clone)]
+struct Activity(Arc<ActivityState>);
+
+struct ActivityState {
+    started: Instant,
+    last_ms: AtomicU64,
+    in_flight: AtomicU64,
+}
+
+/// A request being handled, until its response head is returned, a streamed body keeps the connection active
+/// by writing.
+struct InFlight(Activity);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.touch();
+        self.0.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Activity {
+    fn new() -> Self {
+        Activity(Arc::new(ActivityState {
+            started: Instant::now(),
+            last_ms: AtomicU64::new(0),
+            in_flight: AtomicU64::new(0),
+        }))
+    }
+
+    fn touch(&self) {
+        let elapsed = self.0.started.elapsed().as_millis() as u64;
+        self.0.last_ms.fetch_max(elapsed, Ordering::Relaxed);
+    }
+
+    fn begin(&self) -> InFlight {
+        self.0.in_flight.fetch_add(1, Ordering::Relaxed);
+        self.touch();
+        InFlight(self.clone())
+    }
+
+    /// Resolves once the connection has no request in flight and did not read or write for `timeout`.
+    async fn idle(&self, timeout: Option<Duration>) {
+        let Some(timeout) = timeout else {
+            return std::future::pending().await;
+        };
+        loop {
+            let last = self.0.started + Duration::from_millis(self.0.last_ms.load(Ordering::Relaxed));
+            if last.elapsed() >= timeout && self.0.in_flight.load(Ordering::Relaxed) == 0 {
+                return;
+            }
+            match last + timeout {
+                deadline if deadline > Instant::now() => tokio::time::sleep_until(deadline.into()).await,
+                // idle for long enough but a request is in flight, it touches the activity once handled
+                _ => tokio::time::sleep(timeout).await,
+            }
+        }
+    }
+}
+
+/// The IO of a connection, recording its reads and writes in its [`Activity`].
+struct Tracked<I> {
+    io: I,
+    activity: Activity,
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for Tracked<I> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        ready!(Pin::new(&mut self.io).poll_read(cx, buf))?;
+        self.activity.touch();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for Tracked<I> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let written = ready!(Pin::new(&mut self.io).poll_write(cx, buf))?;
+        self.activity.touch();
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(mut self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        let written = ready!(Pin::new(&mut self.io).poll_write_vectored(cx, bufs))?;
+        self.activity.touch();
+        Poll::Ready(Ok(written))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+}
+
+/// Announces the keep-alive limits to HTTP/1 clients, and closes the connection with its last allowed request.
+fn keep_alive(response: &mut axum::response::Response, version: Version, request: u64, options: Options) {
+    if !matches!(version, Version::HTTP_10 | Version::HTTP_11) {
+        return;
+    }
+    let mut params = Vec::new();
+    if let Some(timeout) = options.keepalive_timeout {
+        params.push(format!("timeout={}", timeout.as_secs()));
+    }
+    if let Some(max) = options.keepalive_max_requests {
+        if request >= max {
+            response.headers_mut().insert(CONNECTION, HeaderValue::from_static("close"));
+            return;
+        }
+        params.push(format!("max={}", max - request));
+    }
+    if params.is_empty() {
+        return;
+    }
+    if let Ok(value) = HeaderValue::from_str(&params.join(", ")) {
+        response.headers_mut().insert(HeaderName::from_static("keep-alive"), value);
+    }
+}
+
+/// Serves `router` on every connection of `listener` until `shutdown`, then waits for the open connections to
+/// finish their in-flight requests. `connect_info` becomes the `ConnectInfo` extension of the requests.
+pub async fn serve<L, C>(
+    mut listener: L,
+    router: Router,
+    options: Options,
+    shutdown: impl Future<Output = ()>,
+    connect_info: impl Fn(&L::Io, &L::Addr) -> C,
+) where
+    L: axum::serve::Listener,
+    L::Io: ConnectionExtensions,
+    C: Clone + Send + Sync + 'static,
+{
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    // hyper only enforces the header read timeout with a timer
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .header_read_timeout(options.header_read_timeout);
+    builder.http2().timer(TokioTimer::new());
+
+    let (signal_tx, signal_rx) = watch::channel(());
+    let (close_tx, close_rx) = watch::channel(());
+    tokio::pin!(shutdown);
+
+    loop {
+        let (io, addr) = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => break,
+        };
+        let info = connect_info(&io, &addr);
+        let extensions = io.extensions();
+        let counter = ConnectionCounter::default();
+        let activity = Activity::new();
+        let io = Tracked {
+            io,
+            activity: activity.clone(),
+        };
+        let router = router.clone();
+        let connection_activity = activity.clone();
+        let service = tower::service_fn(move |mut request: Request<Incoming>| {
+            let reuse = counter.next();
+            let version = request.version();
+            request.extensions_mut().extend(extensions.clone());
+            request.extensions_mut().insert(ConnectInfo(info.clone()));
+            request.extensions_mut().insert(reuse);
+            request.extensions_mut().insert(Accepted(connection_activity.0.started));
+            let in_flight = connection_activity.begin();
+            let response = router.clone().oneshot(request.map(Body::new));
+            async move {
+                let Ok(mut response) = response.await;
+                keep_alive(&mut response, version, reuse.request, options);
+                drop(in_flight);
+                Ok::<_, Infallible>(response)
+            }
+        });
+        let builder = builder.clone();
+        let signal_rx = signal_rx.clone();
+        let close_rx = close_rx.clone();
+
+        tokio::spawn(async move {
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(io), TowerToHyperService::new(service));
+            tokio::pin!(connection);
+            let mut signal_rx = signal_rx;
+            tokio::select! {
+                result = connection.as_mut() => log(result),
+                _ = signal_rx.changed() => {
+                    connection.as_mut().graceful_shutdown();
+                    log(connection.await);
+                }
+                _ = activity.idle(options.keepalive_timeout) => {
+                    connection.as_mut().graceful_shutdown();
+                    log(connection.await);
+                }
+            }
+            drop(close_rx);
+        });
+    }
+
+    drop(listener);
+    signal_tx.send_replace(());
+    drop(close_rx);
+    close_tx.closed().await;
+}
+
+fn log(result: Result<(), Box<dyn std::error::Error + Send + Sync>>) {
+    if let Err(e) = result {
+        tracing::debug!("connection closed: {e}");
+    }
+}
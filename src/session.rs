@@ -0,0 +1,82 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+    time::Instant,
+};
+
+use axum::{Extension, http::HeaderMap, response::IntoResponse};
+use axum_extra::{
+    extract::cookie::{Cookie, Key, SignedCookieJar},
+    response::ErasedJson,
+};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::config::Config;
+
+pub const COOKIE: &str = "httpbin-session";
+
+/// Used without `--session-secret`, sessions then do not survive a restart.
+static KEY: LazyLock<Key> = LazyLock::new(Key::generate);
+
+static SESSIONS: LazyLock<Arc<Mutex<HashMap<Uuid, Session>>>> = LazyLock::new(Default::default);
+
+struct Session {
+    count: u64,
+    last_seen: Instant,
+}
+
+#[derive(Serialize)]
+struct Visit {
+    session: Uuid,
+    count: u64,
+    new: bool,
+    ttl: u64,
+}
+
+fn key(config: &Config) -> Key {
+    match &config.session_secret {
+        Some(secret) => Key::derive_from(secret.as_bytes()),
+        None => KEY.clone(),
+    }
+}
+
+/// Counts visits per signed session cookie, starting a new session when the cookie is missing, tampered with,
+/// or its session expired.
+pub async fn cookie_jar(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap) -> impl IntoResponse {
+    let jar = SignedCookieJar::from_headers(&header_map, key(&config));
+    let now = Instant::now();
+
+    let mut sessions = SESSIONS.lock().await;
+    sessions.retain(|_, session| now.duration_since(session.last_seen) < config.session_ttl);
+
+    let existing = jar
+        .get(COOKIE)
+        .and_then(|cookie| cookie.value().parse::<Uuid>().ok())
+        .filter(|id| sessions.contains_key(id));
+    let new = existing.is_none();
+    let id = existing.unwrap_or_else(Uuid::new_v4);
+
+    let session = sessions.entry(id).or_insert(Session { count: 0, last_seen: now });
+    session.count += 1;
+    session.last_seen = now;
+    let count = session.count;
+    drop(sessions);
+
+    // refreshed on every visit, the expiry slides like the server side one
+    let cookie = Cookie::build((COOKIE, id.to_string()))
+        .path("/")
+        .http_only(true)
+        .max_age(config.session_ttl.try_into().unwrap_or_default())
+        .build();
+    (
+        jar.add(cookie),
+        ErasedJson::pretty(Visit {
+            session: id,
+            count,
+            new,
+            ttl: config.session_ttl.as_secs(),
+        }),
+    )
+}
@@ -0,0 +1,105 @@
+use axum::{
+    body::Bytes,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use axum_valid::Garde;
+use base64::{
+    Engine,
+    prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD},
+};
+use garde::Validate;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+
+use crate::data::ErrorDetail;
+
+/// Largest payload `/sign` signs, the route answers larger bodies 413 before buffering them.
+pub const MAX_PAYLOAD_BYTES: usize = 64 * 1024;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default)]
+pub enum Algorithm {
+    #[default]
+    HS256,
+    HS384,
+    HS512,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Jwt,
+    Hmac,
+}
+
+#[derive(Deserialize, Validate, Debug)]
+pub struct SignParam {
+    #[serde(default)]
+    #[garde(skip)]
+    alg: Algorithm,
+    #[garde(length(min = 1))]
+    secret: String,
+    #[serde(default)]
+    #[garde(skip)]
+    mode: Mode,
+}
+
+#[derive(Serialize)]
+struct Jwt {
+    alg: Algorithm,
+    token: String,
+}
+
+#[derive(Serialize)]
+struct Signature {
+    alg: Algorithm,
+    bytes: usize,
+    hex: String,
+    base64: String,
+}
+
+fn hmac(alg: Algorithm, secret: &[u8], data: &[u8]) -> Vec<u8> {
+    fn sign<M: Mac + hmac::digest::KeyInit>(secret: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mac = <M as Mac>::new_from_slice(secret).expect("HMAC takes keys of any size");
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+    match alg {
+        Algorithm::HS256 => sign::<Hmac<sha2::Sha256>>(secret, data),
+        Algorithm::HS384 => sign::<Hmac<sha2::Sha384>>(secret, data),
+        Algorithm::HS512 => sign::<Hmac<sha2::Sha512>>(secret, data),
+    }
+}
+
+/// A test utility signing the body with the secret from the query: a JWT with the body as its claims, or with
+/// `mode=hmac` the bare HMAC of the body. The secret travels in the URL and ends up in logs, never use a real one.
+pub async fn sign(Garde(Query(p)): Garde<Query<SignParam>>, body: Bytes) -> Response {
+    match p.mode {
+        Mode::Jwt => {
+            if !matches!(serde_json::from_slice(&body), Ok(serde_json::Value::Object(_))) {
+                return ErrorDetail::response(StatusCode::BAD_REQUEST, "the JWT claims must be a JSON object");
+            }
+            let header = serde_json::json!({"alg": p.alg, "typ": "JWT"});
+            let signing_input = format!(
+                "{}.{}",
+                BASE64_URL_SAFE_NO_PAD.encode(header.to_string()),
+                BASE64_URL_SAFE_NO_PAD.encode(&body)
+            );
+            let signature = hmac(p.alg, p.secret.as_bytes(), signing_input.as_bytes());
+            let token = format!("{signing_input}.{}", BASE64_URL_SAFE_NO_PAD.encode(signature));
+            ErasedJson::pretty(Jwt { alg: p.alg, token }).into_response()
+        }
+        Mode::Hmac => {
+            let signature = hmac(p.alg, p.secret.as_bytes(), &body);
+            ErasedJson::pretty(Signature {
+                alg: p.alg,
+                bytes: body.len(),
+                hex: signature.iter().map(|byte| format!("{byte:02x}")).collect(),
+                base64: BASE64_STANDARD.encode(&signature),
+            })
+            .into_response()
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use axum::{
+    extract::Request,
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use futures_util::StreamExt as _;
+use serde::Serialize;
+
+use crate::{data::ErrorDetail, pretty::Pretty};
+
+/// Bytes of the body looked at, the resource header of the WHATWG MIME Sniffing standard.
+pub const SNIFF_LEN: usize = 1445;
+
+/// `(type, pattern, mask)`, an empty mask compares every byte, a `0x00` mask byte matches anything.
+const SIGNATURES: &[(&str, &[u8], &[u8])] = &[
+    ("image/x-icon", b"\x00\x00\x01\x00", b""),
+    ("image/x-icon", b"\x00\x00\x02\x00", b""),
+    ("image/bmp", b"BM", b""),
+    ("image/gif", b"GIF87a", b""),
+    ("image/gif", b"GIF89a", b""),
+    (
+        "image/webp",
+        b"RIFF\0\0\0\0WEBPVP",
+        b"\xFF\xFF\xFF\xFF\0\0\0\0\xFF\xFF\xFF\xFF\xFF\xFF",
+    ),
+    ("image/png", b"\x89PNG\r\n\x1A\n", b""),
+    ("image/jpeg", b"\xFF\xD8\xFF", b""),
+    ("audio/aiff", b"FORM\0\0\0\0AIFF", b"\xFF\xFF\xFF\xFF\0\0\0\0\xFF\xFF\xFF\xFF"),
+    ("audio/mpeg", b"ID3", b""),
+    ("application/ogg", b"OggS\0", b""),
+    ("audio/midi", b"MThd\0\0\0\x06", b""),
+    ("video/avi", b"RIFF\0\0\0\0AVI ", b"\xFF\xFF\xFF\xFF\0\0\0\0\xFF\xFF\xFF\xFF"),
+    ("audio/wave", b"RIFF\0\0\0\0WAVE", b"\xFF\xFF\xFF\xFF\0\0\0\0\xFF\xFF\xFF\xFF"),
+    ("video/mp4", b"\0\0\0\0ftyp", b"\0\0\0\0\xFF\xFF\xFF\xFF"),
+    ("video/webm", b"\x1A\x45\xDF\xA3", b""),
+    ("font/woff", b"wOFF", b""),
+    ("font/woff2", b"wOF2", b""),
+    ("application/x-gzip", b"\x1F\x8B\x08", b""),
+    ("application/zip", b"PK\x03\x04", b""),
+    ("application/x-rar-compressed", b"Rar!\x1A\x07\x00", b""),
+];
+
+/// Tags that make a document `text/html` when it starts with one, after whitespace and before a space or `>`.
+const HTML_TAGS: &[&[u8]] = &[
+    b"<!DOCTYPE HTML",
+    b"<HTML",
+    b"<HEAD",
+    b"<SCRIPT",
+    b"<IFRAME",
+    b"<H1",
+    b"<DIV",
+    b"<FONT",
+    b"<TABLE",
+    b"<A",
+    b"<STYLE",
+    b"<TITLE",
+    b"<B",
+    b"<BODY",
+    b"<BR",
+    b"<P",
+    b"<!--",
+];
+
+#[derive(Serialize)]
+struct Sniffed {
+    /// the essence of the `Content-Type` sent, without its parameters
+    declared: Option<String>,
+    detected: &'static str,
+    /// which rule decided
+    rule: String,
+    /// whether markup or a signature detected another type than the declared one, text and binary without
+    /// either are not compared
+    mismatch: bool,
+    /// whether browsers swap the declared type for the detected one, when it is missing or unknown
+    browsers_sniff: bool,
+    /// HTML, XML and PDF run script or markup, a risk when uploads are served back
+    scriptable: bool,
+    /// bytes sniffed, the body is read no further than `SNIFF_LEN`
+    bytes: usize,
+}
+
+fn masked_match(data: &[u8], pattern: &[u8], mask: &[u8]) -> bool {
+    data.len() >= pattern.len()
+        && pattern
+            .iter()
+            .enumerate()
+            .all(|(index, byte)| data[index] & mask.get(index).copied().unwrap_or(0xFF) == *byte)
+}
+
+fn is_whitespace(byte: &u8) -> bool {
+    matches!(byte, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+/// The bytes a text resource never holds.
+fn is_binary_data(byte: &u8) -> bool {
+    matches!(byte, 0x00..=0x08 | 0x0B | 0x0E..=0x1A | 0x1C..=0x1F)
+}
+
+/// A simplified MIME type sniffing algorithm for resources of unknown type: scriptable markup, then BOMs, then
+/// the binary signatures, then whether any binary data byte is there.
+pub fn sniff(data: &[u8]) -> (&'static str, String) {
+    let data = &data[..data.len().min(SNIFF_LEN)];
+    let markup = &data[data.iter().position(|byte| !is_whitespace(byte)).unwrap_or(data.len())..];
+    for tag in HTML_TAGS {
+        let terminated = markup.get(tag.len()).is_some_and(|byte| matches!(byte, b' ' | b'>'));
+        if terminated && markup[..tag.len()].eq_ignore_ascii_case(tag) {
+            return ("text/html", format!("starts with `{}`", String::from_utf8_lossy(tag)));
+        }
+    }
+    if markup.starts_with(b"<?xml") {
+        return ("text/xml", "starts with `<?xml`".to_string());
+    }
+    if data.starts_with(b"%PDF-") {
+        return ("application/pdf", "starts with `%PDF-`".to_string());
+    }
+    if data.starts_with(b"%!PS-Adobe-") {
+        return ("application/postscript", "starts with `%!PS-Adobe-`".to_string());
+    }
+    for (bom, name) in [
+        (&b"\xFE\xFF"[..], "UTF-16BE"),
+        (b"\xFF\xFE", "UTF-16LE"),
+        (b"\xEF\xBB\xBF", "UTF-8"),
+    ] {
+        if data.starts_with(bom) {
+            return ("text/plain", format!("{name} byte order mark"));
+        }
+    }
+    if let Some((mime, _, _)) = SIGNATURES.iter().find(|(_, pattern, mask)| masked_match(data, pattern, mask)) {
+        return (*mime, format!("{mime} signature"));
+    }
+    match data.iter().position(is_binary_data) {
+        Some(offset) => (
+            "application/octet-stream",
+            format!("binary data byte 0x{:02X} at {offset}", data[offset]),
+        ),
+        None => ("text/plain", "no binary data bytes".to_string()),
+    }
+}
+
+/// Sniffs the body like a browser sniffs a resource of unknown type, and compares the result with the declared
+/// `Content-Type`, to find uploads a browser would render as something else than they claim.
+pub async fn sniff_body(pretty: Pretty, request: Request) -> Response {
+    let declared = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|it| it.to_str().ok())
+        .map(|it| it.split(';').next().unwrap_or_default().trim().to_ascii_lowercase())
+        .filter(|it| !it.is_empty());
+    let mut stream = request.into_body().into_data_stream();
+    let mut body = Vec::with_capacity(SNIFF_LEN);
+    while body.len() < SNIFF_LEN {
+        match stream.next().await {
+            Some(Ok(frame)) => body.extend_from_slice(&frame[..frame.len().min(SNIFF_LEN - body.len())]),
+            Some(Err(e)) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", e.to_string())),
+                )
+                    .into_response();
+            }
+            None => break,
+        }
+    }
+    // the rest of the body is dropped unread with the stream
+    drop(stream);
+    let (detected, rule) = sniff(&body);
+    let fallback = matches!(detected, "text/plain" | "application/octet-stream");
+    pretty
+        .json(Sniffed {
+            mismatch: declared.as_deref().is_some_and(|declared| !fallback && declared != detected),
+            browsers_sniff: declared
+                .as_deref()
+                .is_none_or(|declared| matches!(declared, "unknown/unknown" | "application/unknown" | "*/*")),
+            scriptable: matches!(detected, "text/html" | "text/xml" | "application/pdf"),
+            declared,
+            detected,
+            rule,
+            bytes: body.len(),
+        })
+        .into_response()
+}
@@ -0,0 +1,148 @@
+use std::{path::PathBuf, str::FromStr, sync::Arc};
+
+use axum::{
+    Extension,
+    extract::Request,
+    http::{
+        HeaderMap, HeaderValue, Method, StatusCode,
+        header::{ALLOW, CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use jiff::Timestamp;
+
+use crate::{
+    config::Config,
+    data::ErrorDetail,
+    etag::{etag_for, none_match},
+};
+
+/// `(extension, type)`, files with another extension are sniffed.
+const TYPES: &[(&str, &str)] = &[
+    ("html", "text/html; charset=utf-8"),
+    ("htm", "text/html; charset=utf-8"),
+    ("css", "text/css; charset=utf-8"),
+    ("js", "text/javascript; charset=utf-8"),
+    ("mjs", "text/javascript; charset=utf-8"),
+    ("json", "application/json"),
+    ("txt", "text/plain; charset=utf-8"),
+    ("csv", "text/csv; charset=utf-8"),
+    ("md", "text/markdown; charset=utf-8"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("ico", "image/x-icon"),
+    ("wasm", "application/wasm"),
+    ("pdf", "application/pdf"),
+    ("woff2", "font/woff2"),
+    ("mp4", "video/mp4"),
+];
+
+/// A file served at a route, from `--serve-file PATH@ROUTE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServeFile {
+    pub path: PathBuf,
+    pub route: String,
+}
+
+impl FromStr for ServeFile {
+    type Err = String;
+
+    /// Splits on the last `@`, file names can hold one but routes cannot.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (path, route) = value
+            .rsplit_once('@')
+            .ok_or_else(|| format!("invalid file `{value}`, expected PATH@ROUTE"))?;
+        if !route.starts_with('/') || route.contains(['?', '#', '{', '}']) {
+            return Err(format!("invalid route `{route}`, expected a path starting with `/`"));
+        }
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            return Err(format!("cannot serve `{}`: not a readable file", path.display()));
+        }
+        Ok(ServeFile {
+            path,
+            route: route.to_string(),
+        })
+    }
+}
+
+fn content_type(file: &ServeFile, bytes: &[u8]) -> &'static str {
+    let extension = file.path.extension().and_then(|it| it.to_str()).map(str::to_ascii_lowercase);
+    match TYPES.iter().find(|(it, _)| Some(*it) == extension.as_deref()) {
+        Some((_, mime)) => mime,
+        None => crate::sniff::sniff(bytes).0,
+    }
+}
+
+/// `If-None-Match` wins over `If-Modified-Since`, as RFC 9110 asks, and dates compare with a one second precision.
+fn not_modified(headers: &HeaderMap, etag: &str, modified: Option<Timestamp>) -> bool {
+    if headers.contains_key(IF_NONE_MATCH) {
+        return none_match(headers, etag);
+    }
+    let since = headers
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|it| it.to_str().ok())
+        .and_then(|it| crate::http_date::parse(it, Timestamp::now()).ok());
+    match (since, modified) {
+        (Some((_, since)), Some(modified)) => modified.as_second() <= since.as_second(),
+        _ => false,
+    }
+}
+
+async fn serve_file(file: &ServeFile, method: &Method, headers: &HeaderMap) -> Response {
+    if method != Method::GET && method != Method::HEAD {
+        let detail = format!("`{}` only answers GET and HEAD", file.route);
+        let error = ErasedJson::pretty(ErrorDetail::new(405, "Method Not Allowed", detail));
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            [(ALLOW, HeaderValue::from_static("GET, HEAD"))],
+            error,
+        )
+            .into_response();
+    }
+    // read on every request, edits to the file show up without a restart
+    let (bytes, metadata) = match tokio::try_join!(tokio::fs::read(&file.path), tokio::fs::metadata(&file.path)) {
+        Ok(it) => it,
+        Err(e) => {
+            tracing::warn!(path = %file.path.display(), error = %e, "cannot read served file");
+            let detail = format!("cannot read the file of `{}`: {e}", file.route);
+            return (
+                StatusCode::NOT_FOUND,
+                ErasedJson::pretty(ErrorDetail::new(404, "Not Found", detail)),
+            )
+                .into_response();
+        }
+    };
+    let etag = etag_for(&bytes);
+    let modified = metadata.modified().ok().and_then(|it| Timestamp::try_from(it).ok());
+
+    let mut validators = HeaderMap::new();
+    validators.insert(ETAG, HeaderValue::from_str(&etag).expect("a hash"));
+    if let Some(modified) = modified {
+        let http_date = modified.strftime("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        validators.insert(LAST_MODIFIED, HeaderValue::from_str(&http_date).expect("a date"));
+    }
+    // cached but revalidated on every use, the file may change under the running server
+    validators.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+    if not_modified(headers, &etag, modified) {
+        return (StatusCode::NOT_MODIFIED, validators).into_response();
+    }
+    let content_type = HeaderValue::from_static(content_type(file, &bytes));
+    (validators, [(CONTENT_TYPE, content_type)], bytes).into_response()
+}
+
+/// Serves the files of `--serve-file` at their routes, before the router so they can shadow an endpoint.
+pub async fn serve(Extension(config): Extension<Arc<Config>>, request: Request, next: Next) -> Response {
+    match config.serve_files.iter().find(|file| file.route == request.uri().path()) {
+        Some(file) => serve_file(file, request.method(), request.headers()).await,
+        None => next.run(request).await,
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use axum::{
+    Extension,
+    extract::{MatchedPath, Request},
+    http::{HeaderMap, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+
+use crate::{admin, config::Config};
+
+/// The route of requests no route matched, counted together so unknown paths cannot grow the table.
+pub const UNMATCHED: &str = "<unmatched>";
+/// The method of requests with a non-standard one, counted together for the same reason.
+pub const OTHER_METHOD: &str = "<other>";
+
+/// A std mutex held for a hash lookup and an increment per request, cheaper than an async one.
+static COUNTS: LazyLock<Mutex<Counts>> = LazyLock::new(|| {
+    Mutex::new(Counts {
+        since: jiff::Timestamp::now(),
+        routes: HashMap::new(),
+    })
+});
+
+struct Counts {
+    since: jiff::Timestamp,
+    /// by route template, then method and status, `None` for a non-standard method
+    routes: HashMap<String, HashMap<(Option<Method>, u16), u64>>,
+}
+
+#[derive(Serialize, Default)]
+struct Breakdown {
+    total: u64,
+    methods: BTreeMap<String, u64>,
+    statuses: BTreeMap<u16, u64>,
+}
+
+impl Breakdown {
+    fn add(&mut self, method: Option<&Method>, status: u16, count: u64) {
+        self.total += count;
+        let method = method.map_or(OTHER_METHOD, Method::as_str);
+        *self.methods.entry(method.to_string()).or_default() += count;
+        *self.statuses.entry(status).or_default() += count;
+    }
+}
+
+#[derive(Serialize)]
+struct Stats {
+    since: jiff::Timestamp,
+    #[serde(flatten)]
+    all: Breakdown,
+    routes: BTreeMap<String, Breakdown>,
+}
+
+#[derive(Serialize)]
+struct Cleared {
+    cleared: u64,
+}
+
+/// Counts every response by the route template that matched, `/status/{codes}` rather than `/status/418`, its
+/// method and status. `/stats` itself is left out, reading the counts does not change them.
+pub async fn count(request: Request, next: Next) -> Response {
+    let route = request.extensions().get::<MatchedPath>().cloned();
+    let route = route.as_ref().map_or(UNMATCHED, MatchedPath::as_str);
+    if route == "/stats" {
+        return next.run(request).await;
+    }
+    let method = Some(request.method().clone()).filter(|it| crate::method::STANDARD.contains(it));
+    let response = next.run(request).await;
+    let mut counts = COUNTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // the route is only allocated the first time it is counted
+    if !counts.routes.contains_key(route) {
+        counts.routes.insert(route.to_string(), HashMap::new());
+    }
+    let statuses = counts.routes.get_mut(route).expect("inserted");
+    *statuses.entry((method, response.status().as_u16())).or_default() += 1;
+    response
+}
+
+/// The requests counted since the server started or the counts were reset, in total and by route, each with its
+/// methods and statuses.
+pub async fn stats() -> impl IntoResponse {
+    let counts = COUNTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut all = Breakdown::default();
+    let mut routes = BTreeMap::new();
+    for (route, statuses) in &counts.routes {
+        let breakdown: &mut Breakdown = routes.entry(route.clone()).or_default();
+        for ((method, status), count) in statuses {
+            breakdown.add(method.as_ref(), *status, *count);
+            all.add(method.as_ref(), *status, *count);
+        }
+    }
+    ErasedJson::pretty(Stats {
+        since: counts.since,
+        all,
+        routes,
+    })
+}
+
+/// Resets the counts, requires the admin token.
+pub async fn reset(Extension(config): Extension<Arc<Config>>, header_map: HeaderMap) -> Response {
+    if let Err(response) = admin::authorize(&config, &header_map) {
+        return response;
+    }
+    let mut counts = COUNTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let cleared = counts.routes.values().flat_map(HashMap::values).sum();
+    counts.routes.clear();
+    counts.since = jiff::Timestamp::now();
+    ErasedJson::pretty(Cleared { cleared }).into_response()
+}
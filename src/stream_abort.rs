@@ -0,0 +1,148 @@
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderName, HeaderValue, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use axum_extra::{extract::Query, response::ErasedJson};
+use axum_valid::Garde;
+use garde::Validate;
+use serde::{Deserialize, Serialize};
+use tokio_stream::StreamExt as _;
+
+/// Finished streams kept in the log, older ones are dropped first.
+pub const CAPACITY: usize = 100;
+
+pub static COMPLETED: AtomicU64 = AtomicU64::new(0);
+pub static ABORTED: AtomicU64 = AtomicU64::new(0);
+pub static ACTIVE: AtomicU64 = AtomicU64::new(0);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static X_STREAM_ID: HeaderName = HeaderName::from_static("x-stream-id");
+
+/// A std mutex, the log is written when a stream is dropped, outside of any task.
+static LOG: LazyLock<Mutex<VecDeque<Record>>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct StreamAbortParam {
+    /// lines to send, 10 by default
+    #[garde(range(min = 1, max = 1000))]
+    pub chunks: Option<u32>,
+    /// pause between lines, 500 by default
+    #[garde(range(min = 0, max = 10_000))]
+    pub interval_ms: Option<u64>,
+}
+
+#[derive(Serialize, Clone)]
+struct Record {
+    id: u64,
+    started_at: jiff::Timestamp,
+    /// `completed` or `aborted`
+    outcome: &'static str,
+    sent: u32,
+    chunks: u32,
+    elapsed_ms: u64,
+}
+
+#[derive(Serialize)]
+struct LogResponse {
+    completed: u64,
+    aborted: u64,
+    active: u64,
+    capacity: usize,
+    streams: Vec<Record>,
+}
+
+/// The work of one stream, recorded when hyper drops the body: after the last line, or as soon as the client
+/// goes away before it.
+struct Tracked {
+    id: u64,
+    started_at: jiff::Timestamp,
+    started: Instant,
+    sent: u32,
+    chunks: u32,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        ACTIVE.fetch_sub(1, Ordering::Relaxed);
+        let outcome = match self.sent < self.chunks {
+            true => {
+                ABORTED.fetch_add(1, Ordering::Relaxed);
+                tracing::info!(id = self.id, sent = self.sent, chunks = self.chunks, "stream aborted by the client");
+                "aborted"
+            }
+            false => {
+                COMPLETED.fetch_add(1, Ordering::Relaxed);
+                "completed"
+            }
+        };
+        let mut log = LOG.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if log.len() >= CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(Record {
+            id: self.id,
+            started_at: self.started_at,
+            outcome,
+            sent: self.sent,
+            chunks: self.chunks,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+        });
+    }
+}
+
+/// Streams `chunks` JSON lines `interval_ms` apart, numbered by `seq`, and stops producing them as soon as the
+/// client disconnects. Each stream is named by `X-Stream-Id` and its outcome lands in `/stream-abort/log`.
+pub async fn stream_abort(Garde(Query(p)): Garde<Query<StreamAbortParam>>) -> Response {
+    let chunks = p.chunks.unwrap_or(10);
+    let interval = Duration::from_millis(p.interval_ms.unwrap_or(500));
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    ACTIVE.fetch_add(1, Ordering::Relaxed);
+    let mut tracked = Tracked {
+        id,
+        started_at: jiff::Timestamp::now(),
+        started: Instant::now(),
+        sent: 0,
+        chunks,
+    };
+
+    let lines = tokio_stream::iter(1..=chunks).throttle(interval).map(move |seq| {
+        tracked.sent = seq;
+        Ok::<_, Infallible>(Bytes::from(format!("{{\"id\":{id},\"seq\":{seq},\"of\":{chunks}}}\n")))
+    });
+    (
+        [
+            (CONTENT_TYPE, HeaderValue::from_static("application/x-ndjson")),
+            (X_STREAM_ID.clone(), HeaderValue::from(id)),
+        ],
+        Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+/// The last finished streams, newest last, with the counters since the server started.
+pub async fn stream_abort_log() -> impl IntoResponse {
+    let streams = LOG
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .cloned()
+        .collect();
+    ErasedJson::pretty(LogResponse {
+        completed: COMPLETED.load(Ordering::Relaxed),
+        aborted: ABORTED.load(Ordering::Relaxed),
+        active: ACTIVE.load(Ordering::Relaxed),
+        capacity: CAPACITY,
+        streams,
+    })
+}
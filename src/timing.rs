@@ -0,0 +1,94 @@
+use std::{pin::Pin, time::Instant};
+
+use axum::{
+    Extension,
+    body::HttpBody as _,
+    extract::Request,
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+
+use crate::{data::ErrorDetail, pretty::Pretty, serve::Accepted};
+
+/// When the outermost layer saw the request, its head parsed but its body not read yet.
+#[derive(Debug, Clone, Copy)]
+pub struct Received(pub Instant);
+
+/// Stamps every request with [`Received`], as the first layer of the router.
+pub async fn stamp(mut request: Request, next: Next) -> Response {
+    request.extensions_mut().insert(Received(Instant::now()));
+    next.run(request).await
+}
+
+#[derive(Serialize)]
+struct Timing {
+    /// when the handler started
+    received_at: jiff::Timestamp,
+    /// from accepting the connection to the request head, `null` when the connection is unknown
+    connection_age_ms: Option<f64>,
+    /// from the request head to the handler, spent in the middlewares
+    queued_ms: f64,
+    /// from the request head to the first body byte, `null` for an empty body
+    first_body_byte_ms: Option<f64>,
+    /// from the request head to the end of the body
+    body_complete_ms: f64,
+    /// in the handler, reading the body included
+    handler_ms: f64,
+    /// from the request head to the response
+    total_ms: f64,
+    body_bytes: usize,
+}
+
+fn ms(from: Instant, to: Instant) -> f64 {
+    to.duration_since(from).as_secs_f64() * 1000.0
+}
+
+/// The server's view of how long the request took, from the instants stamped by [`stamp`] and the connection
+/// accept, to cross-check client side timing. The body is read and counted, not kept.
+pub async fn timing(
+    pretty: Pretty,
+    received: Option<Extension<Received>>,
+    accepted: Option<Extension<Accepted>>,
+    request: Request,
+) -> Response {
+    let started = Instant::now();
+    let received_at = jiff::Timestamp::now();
+    let received = received.map_or(started, |Extension(Received(at))| at);
+
+    let mut body = request.into_body();
+    let mut first_byte = None;
+    let mut body_bytes = 0;
+    while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", e.to_string())),
+                )
+                    .into_response();
+            }
+        };
+        if let Some(data) = frame.data_ref().filter(|data| !data.is_empty()) {
+            first_byte.get_or_insert_with(Instant::now);
+            body_bytes += data.len();
+        }
+    }
+    let complete = Instant::now();
+
+    pretty
+        .json(Timing {
+            received_at,
+            connection_age_ms: accepted.map(|Extension(Accepted(at))| ms(at, received)),
+            queued_ms: ms(received, started),
+            first_body_byte_ms: first_byte.map(|at| ms(received, at)),
+            body_complete_ms: ms(received, complete),
+            handler_ms: ms(started, Instant::now()),
+            total_ms: ms(received, Instant::now()),
+            body_bytes,
+        })
+        .into_response()
+}
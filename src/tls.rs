@@ -0,0 +1,393 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use axum::{Extension, http::StatusCode, response::IntoResponse};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+use sha2::{Digest as _, Sha256};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt as _, AsyncWrite, ReadBuf},
+    net::TcpStream,
+};
+use tokio_rustls::{TlsAcceptor, server::TlsStream};
+
+use crate::data::{ErrorDetail, TlsInfo};
+
+/// Connections that have not finished the TLS handshake by then are dropped.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A ClientHello spans a few records at the most, post-quantum key shares included.
+const MAX_CLIENT_HELLO: usize = 64 * 1024;
+const MAX_RECORD: usize = 16 * 1024 + 256;
+
+const SERVER_NAME: u16 = 0x0000;
+const SUPPORTED_GROUPS: u16 = 0x000a;
+const EC_POINT_FORMATS: u16 = 0x000b;
+const SIGNATURE_ALGORITHMS: u16 = 0x000d;
+const ALPN: u16 = 0x0010;
+const SUPPORTED_VERSIONS: u16 = 0x002b;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The TLS configuration of `--tls`, a self-signed certificate for `localhost` generated at startup, TLS 1.2 and
+/// 1.3, and ALPN `h2` or `http/1.1`.
+pub fn acceptor() -> io::Result<TlsAcceptor> {
+    let rcgen::CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).map_err(io::Error::other)?;
+    let key = rustls::pki_types::PrivateKeyDer::Pkcs8(key_pair.serialize_der().into());
+    let mut tls = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(io::Error::other)?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.der().clone()], key)
+        .map_err(io::Error::other)?;
+    tls.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(tls)))
+}
+
+/// Reads the ClientHello off `stream` before rustls sees it, then replays it to rustls for the handshake. rustls
+/// only exposes a few fields of the ClientHello it parsed, not the order of its extensions the fingerprints need.
+pub async fn accept(acceptor: &TlsAcceptor, mut stream: TcpStream) -> io::Result<(TlsStream<Replay>, ClientHello, TlsInfo)> {
+    let (records, message) = read_client_hello(&mut stream).await?;
+    let hello = ClientHello::parse(&message).ok_or_else(|| invalid("malformed ClientHello"))?;
+    let stream = acceptor
+        .accept(Replay {
+            read: records,
+            offset: 0,
+            stream,
+        })
+        .await?;
+    let (_, connection) = stream.get_ref();
+    let info = TlsInfo {
+        version: match connection.protocol_version() {
+            Some(rustls::ProtocolVersion::TLSv1_3) => "TLSv1.3",
+            _ => "TLSv1.2",
+        },
+        alpn: connection.alpn_protocol().map(|it| String::from_utf8_lossy(it).into_owned()),
+        server_name: connection.server_name().map(str::to_string),
+    };
+    Ok((stream, hello, info))
+}
+
+/// Reads whole handshake records until they hold the ClientHello message, and returns the records as read and the
+/// message, its 4 bytes header included.
+async fn read_client_hello(stream: &mut TcpStream) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let (mut records, mut message) = (Vec::new(), Vec::new());
+    loop {
+        let mut header = [0; 5];
+        stream.read_exact(&mut header).await?;
+        if header[0] != 0x16 {
+            return Err(invalid("not a TLS handshake"));
+        }
+        let len = usize::from(u16::from_be_bytes([header[3], header[4]]));
+        if len > MAX_RECORD {
+            return Err(invalid("TLS record too large"));
+        }
+        let mut fragment = vec![0; len];
+        stream.read_exact(&mut fragment).await?;
+        records.extend_from_slice(&header);
+        records.extend_from_slice(&fragment);
+        message.extend_from_slice(&fragment);
+        if message.len() < 4 {
+            continue;
+        }
+        if message[0] != 0x01 {
+            return Err(invalid("not a ClientHello"));
+        }
+        let total = 4 + u32::from_be_bytes([0, message[1], message[2], message[3]]) as usize;
+        if total > MAX_CLIENT_HELLO {
+            return Err(invalid("ClientHello too large"));
+        }
+        if message.len() >= total {
+            message.truncate(total);
+            return Ok((records, message));
+        }
+    }
+}
+
+/// The connection handed to rustls, reading the ClientHello records already read from the socket first.
+pub struct Replay {
+    read: Vec<u8>,
+    offset: usize,
+    stream: TcpStream,
+}
+
+impl AsyncRead for Replay {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        if self.offset < self.read.len() {
+            let len = buf.remaining().min(self.read.len() - self.offset);
+            buf.put_slice(&self.read[self.offset..self.offset + len]);
+            self.offset += len;
+            if self.offset == self.read.len() {
+                self.read = Vec::new();
+                self.offset = 0;
+            }
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Replay {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+/// Big-endian reads off a slice, `None` once it runs out.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (taken, rest) = (self.0.get(..len)?, &self.0[len..]);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.take(2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// A vector of `u16` behind a length of `len_bytes` bytes.
+    fn u16s(&mut self, len_bytes: usize) -> Option<Vec<u16>> {
+        let len = match len_bytes {
+            1 => usize::from(self.u8()?),
+            _ => usize::from(self.u16()?),
+        };
+        let mut items = Reader(self.take(len)?);
+        Some(std::iter::from_fn(|| items.u16()).collect())
+    }
+}
+
+/// The fields of a ClientHello the fingerprints are computed from, in the order the client sent them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientHello {
+    pub legacy_version: u16,
+    pub cipher_suites: Vec<u16>,
+    /// the extension types
+    pub extensions: Vec<u16>,
+    pub server_name: Option<String>,
+    pub supported_groups: Vec<u16>,
+    pub ec_point_formats: Vec<u8>,
+    pub signature_algorithms: Vec<u16>,
+    pub alpn: Vec<String>,
+    pub supported_versions: Vec<u16>,
+}
+
+impl ClientHello {
+    /// Parses a ClientHello handshake message, from its 4 bytes header on.
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        let mut reader = Reader(message);
+        if reader.u8()? != 0x01 {
+            return None;
+        }
+        reader.take(3)?;
+        let mut hello = ClientHello {
+            legacy_version: reader.u16()?,
+            ..Default::default()
+        };
+        reader.take(32)?;
+        let session_id = reader.u8()?;
+        reader.take(session_id.into())?;
+        hello.cipher_suites = reader.u16s(2)?;
+        let compression_methods = reader.u8()?;
+        reader.take(compression_methods.into())?;
+        // a ClientHello of SSL 3.0 may have no extensions
+        if reader.0.is_empty() {
+            return Some(hello);
+        }
+        let len = reader.u16()?;
+        let mut extensions = Reader(reader.take(len.into())?);
+        while !extensions.0.is_empty() {
+            let kind = extensions.u16()?;
+            let len = extensions.u16()?;
+            let mut data = Reader(extensions.take(len.into())?);
+            hello.extensions.push(kind);
+            match kind {
+                SERVER_NAME => {
+                    let len = data.u16()?;
+                    let mut names = Reader(data.take(len.into())?);
+                    while !names.0.is_empty() {
+                        let name_type = names.u8()?;
+                        let len = names.u16()?;
+                        let name = names.take(len.into())?;
+                        if name_type == 0 {
+                            hello.server_name = Some(String::from_utf8_lossy(name).into_owned());
+                        }
+                    }
+                }
+                SUPPORTED_GROUPS => hello.supported_groups = data.u16s(2)?,
+                EC_POINT_FORMATS => {
+                    let len = data.u8()?;
+                    hello.ec_point_formats = data.take(len.into())?.to_vec();
+                }
+                SIGNATURE_ALGORITHMS => hello.signature_algorithms = data.u16s(2)?,
+                ALPN => {
+                    let len = data.u16()?;
+                    let mut protocols = Reader(data.take(len.into())?);
+                    while !protocols.0.is_empty() {
+                        let len = protocols.u8()?;
+                        let protocol = protocols.take(len.into())?;
+                        hello.alpn.push(String::from_utf8_lossy(protocol).into_owned());
+                    }
+                }
+                SUPPORTED_VERSIONS => hello.supported_versions = data.u16s(1)?,
+                _ => {}
+            }
+        }
+        Some(hello)
+    }
+}
+
+/// GREASE values (RFC 8701), `0x0a0a`, `0x1a1a` up to `0xfafa`, left out of both fingerprints.
+fn is_grease(value: u16) -> bool {
+    value & 0x0f0f == 0x0a0a && value >> 8 == value & 0xff
+}
+
+fn join<T: ToString>(values: impl IntoIterator<Item = T>, separator: &str) -> String {
+    values.into_iter().map(|it| it.to_string()).collect::<Vec<_>>().join(separator)
+}
+
+/// The first 12 hex digits of the SHA-256 of `value`, or 12 zeros for an empty list.
+fn truncated_sha256(value: &str) -> String {
+    if value.is_empty() {
+        return "000000000000".to_string();
+    }
+    format!("{:x}", Sha256::digest(value))[..12].to_string()
+}
+
+/// `SSLVersion,Ciphers,Extensions,EllipticCurves,EllipticCurvePointFormats`, decimal values joined by `-`, GREASE
+/// left out.
+pub fn ja3(hello: &ClientHello) -> String {
+    let list = |values: &[u16]| join(values.iter().filter(|it| !is_grease(**it)), "-");
+    format!(
+        "{},{},{},{},{}",
+        hello.legacy_version,
+        list(&hello.cipher_suites),
+        list(&hello.extensions),
+        list(&hello.supported_groups),
+        join(&hello.ec_point_formats, "-"),
+    )
+}
+
+/// `(ja4, ja4_r)`, the JA4 fingerprint and the raw lists it hashes.
+pub fn ja4(hello: &ClientHello) -> (String, String) {
+    let ciphers: Vec<u16> = hello.cipher_suites.iter().copied().filter(|it| !is_grease(*it)).collect();
+    let extensions: Vec<u16> = hello.extensions.iter().copied().filter(|it| !is_grease(*it)).collect();
+    let version = hello
+        .supported_versions
+        .iter()
+        .copied()
+        .filter(|it| !is_grease(*it))
+        .max()
+        .unwrap_or(hello.legacy_version);
+    let version = match version {
+        0x0304 => "13",
+        0x0303 => "12",
+        0x0302 => "11",
+        0x0301 => "10",
+        0x0300 => "s3",
+        0x0200 => "s2",
+        0x0100 => "s1",
+        _ => "00",
+    };
+    let sni = if extensions.contains(&SERVER_NAME) { 'd' } else { 'i' };
+    let alpn = match hello.alpn.first().map(String::as_bytes) {
+        None | Some([]) => "00".to_string(),
+        Some(alpn) if alpn[0].is_ascii_alphanumeric() && alpn[alpn.len() - 1].is_ascii_alphanumeric() => {
+            format!("{}{}", alpn[0] as char, alpn[alpn.len() - 1] as char)
+        }
+        Some(alpn) => {
+            let hex = join(alpn.iter().map(|it| format!("{it:02x}")), "");
+            format!("{}{}", &hex[..1], &hex[hex.len() - 1..])
+        }
+    };
+    let a = format!("t{version}{sni}{:02}{:02}{alpn}", ciphers.len().min(99), extensions.len().min(99));
+
+    let hex = |values: &[u16]| join(values.iter().map(|it| format!("{it:04x}")), ",");
+    let mut sorted_ciphers = ciphers.clone();
+    sorted_ciphers.sort_unstable();
+    let mut sorted_extensions: Vec<u16> = extensions.iter().copied().filter(|it| *it != SERVER_NAME && *it != ALPN).collect();
+    sorted_extensions.sort_unstable();
+    let b = hex(&sorted_ciphers);
+    let c = match hex(&hello.signature_algorithms) {
+        _ if sorted_extensions.is_empty() => String::new(),
+        signatures if signatures.is_empty() => hex(&sorted_extensions),
+        signatures => format!("{}_{signatures}", hex(&sorted_extensions)),
+    };
+    (
+        format!("{a}_{}_{}", truncated_sha256(&b), truncated_sha256(&c)),
+        format!("{a}_{b}_{c}"),
+    )
+}
+
+#[derive(Serialize)]
+struct Fingerprint<'a> {
+    ja3: String,
+    ja3_hash: String,
+    ja4: String,
+    ja4_r: String,
+    server_name: Option<&'a str>,
+    alpn: &'a [String],
+    legacy_version: u16,
+    supported_versions: &'a [u16],
+    cipher_suites: &'a [u16],
+    extensions: &'a [u16],
+    supported_groups: &'a [u16],
+    ec_point_formats: &'a [u8],
+    signature_algorithms: &'a [u16],
+}
+
+/// The JA3 and JA4 fingerprints of the ClientHello of the connection, with the fields they are computed from.
+pub async fn tls_fingerprint(hello: Option<Extension<Arc<ClientHello>>>) -> impl IntoResponse {
+    let Some(Extension(hello)) = hello else {
+        return (
+            StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(ErrorDetail::new(
+                400,
+                "Bad Request",
+                "not a TLS connection, the server terminates TLS with `--tls`",
+            )),
+        );
+    };
+    let ja3 = ja3(&hello);
+    let (ja4, ja4_r) = ja4(&hello);
+    (
+        StatusCode::OK,
+        ErasedJson::pretty(Fingerprint {
+            ja3_hash: format!("{:x}", md5::Md5::digest(&ja3)),
+            ja3,
+            ja4,
+            ja4_r,
+            server_name: hello.server_name.as_deref(),
+            alpn: &hello.alpn,
+            legacy_version: hello.legacy_version,
+            supported_versions: &hello.supported_versions,
+            cipher_suites: &hello.cipher_suites,
+            extensions: &hello.extensions,
+            supported_groups: &hello.supported_groups,
+            ec_point_formats: &hello.ec_point_formats,
+            signature_algorithms: &hello.signature_algorithms,
+        }),
+    )
+}
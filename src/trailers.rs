@@ -0,0 +1,79 @@
+use std::{collections::BTreeMap, pin::Pin};
+
+use axum::{
+    body::HttpBody as _,
+    extract::Request,
+    http::{
+        StatusCode,
+        header::{TRAILER, TRANSFER_ENCODING},
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+
+use crate::data::ErrorDetail;
+
+#[derive(Serialize)]
+struct RequestTrailers {
+    transfer_encoding: Option<String>,
+    /// the field names announced in the `Trailer` header, clients are not required to send it
+    declared: Vec<String>,
+    trailers: BTreeMap<String, Vec<String>>,
+    bytes: usize,
+}
+
+/// Reads the body to its end and reflects the trailer fields that came after it.
+///
+/// hyper hands over HTTP/1.1 trailers of chunked bodies and HTTP/2 trailing HEADERS frames; bodies with a
+/// `Content-Length` cannot carry any, and the HTTP/3 server buffers the body without them.
+pub async fn request_trailers(request: Request) -> Response {
+    let header = |name| {
+        request
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|it| String::from_utf8_lossy(it.as_bytes()).into_owned())
+            .collect::<Vec<_>>()
+    };
+    let transfer_encoding = header(TRANSFER_ENCODING).first().cloned();
+    let declared = header(TRAILER)
+        .iter()
+        .flat_map(|it| it.split(','))
+        .map(|it| it.trim().to_ascii_lowercase())
+        .filter(|it| !it.is_empty())
+        .collect();
+
+    let mut body = request.into_body();
+    let mut bytes = 0;
+    let mut trailers = BTreeMap::<String, Vec<String>>::new();
+    while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErasedJson::pretty(ErrorDetail::new(400, "Bad Request", e.to_string())),
+                )
+                    .into_response();
+            }
+        };
+        match frame.into_data() {
+            Ok(data) => bytes += data.len(),
+            Err(frame) => {
+                for (name, value) in frame.into_trailers().iter().flatten() {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    trailers.entry(name.to_string()).or_default().push(value);
+                }
+            }
+        }
+    }
+
+    ErasedJson::pretty(RequestTrailers {
+        transfer_encoding,
+        declared,
+        trailers,
+        bytes,
+    })
+    .into_response()
+}
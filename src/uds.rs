@@ -0,0 +1,40 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    middleware::Next,
+    response::Response,
+};
+use tokio::net::UnixStream;
+
+use crate::data::PeerCred;
+
+/// Connection info of a Unix domain socket peer, the credentials are unavailable on some platforms.
+#[derive(Clone, Debug)]
+pub struct UdsConnectInfo {
+    peer_cred: Option<PeerCred>,
+}
+
+impl UdsConnectInfo {
+    pub fn new(stream: &UnixStream) -> Self {
+        let peer_cred = stream.peer_cred().ok().map(|cred| PeerCred {
+            pid: cred.pid(),
+            uid: cred.uid(),
+            gid: cred.gid(),
+        });
+        UdsConnectInfo { peer_cred }
+    }
+}
+
+/// UDS peers have no IP address, they are reported as loopback so the reflection endpoints keep working,
+/// along with their credentials.
+pub async fn connect_info(mut request: Request, next: Next) -> Response {
+    let info = request.extensions().get::<ConnectInfo<UdsConnectInfo>>().cloned();
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))));
+    if let Some(peer_cred) = info.and_then(|ConnectInfo(info)| info.peer_cred) {
+        request.extensions_mut().insert(peer_cred);
+    }
+    next.run(request).await
+}
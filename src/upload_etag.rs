@@ -0,0 +1,137 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, LazyLock},
+};
+
+use axum::{
+    body::Bytes,
+    extract::Path,
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, LOCATION},
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    data::ErrorDetail,
+    etag::{etag_for, if_match, none_match},
+};
+
+/// Objects kept in memory, the oldest upload is dropped first.
+pub const CAPACITY: usize = 100;
+
+/// Maximum total size of the stored bodies, the oldest uploads are dropped first to make room.
+pub const MAX_BYTES: usize = 64 * 1024 * 1024;
+
+static OBJECTS: LazyLock<Arc<Mutex<Objects>>> = LazyLock::new(Default::default);
+
+#[derive(Default)]
+struct Objects {
+    bytes: usize,
+    objects: VecDeque<Object>,
+}
+
+struct Object {
+    id: String,
+    etag: String,
+    content_type: Option<HeaderValue>,
+    body: Bytes,
+}
+
+#[derive(Serialize)]
+struct Stored {
+    id: String,
+    etag: String,
+    size: usize,
+}
+
+/// `PUT /upload-etag` stores the body under a new id.
+pub async fn create(header_map: HeaderMap, body: Bytes) -> Response {
+    put(Uuid::new_v4().simple().to_string(), header_map, body).await
+}
+
+/// `PUT /upload-etag/{id}` creates or replaces the object, subject to `If-Match` and `If-None-Match: *`
+/// like the optimistic concurrency of object stores.
+pub async fn update(Path(id): Path<String>, header_map: HeaderMap, body: Bytes) -> Response {
+    put(id, header_map, body).await
+}
+
+/// The body is already limited by `--max-body-bytes` on the routes, this only keeps a single upload from
+/// evicting the whole store.
+async fn put(id: String, header_map: HeaderMap, body: Bytes) -> Response {
+    if body.len() > MAX_BYTES {
+        let detail = format!("uploads are limited to {MAX_BYTES} bytes");
+        return ErrorDetail::response(StatusCode::PAYLOAD_TOO_LARGE, detail);
+    }
+
+    let mut store = OBJECTS.lock().await;
+    let existing = store.objects.iter().position(|object| object.id == id);
+    let current = existing.map(|index| store.objects[index].etag.as_str());
+    match current {
+        None if header_map.contains_key(IF_MATCH) => {
+            return ErrorDetail::response(StatusCode::PRECONDITION_FAILED, format!("`{id}` does not exist"));
+        }
+        Some(etag) if header_map.contains_key(IF_MATCH) && !if_match(&header_map, etag) => {
+            return ErrorDetail::response(StatusCode::PRECONDITION_FAILED, format!("`{id}` is at {etag}"));
+        }
+        Some(etag) if none_match(&header_map, etag) => {
+            return ErrorDetail::response(StatusCode::PRECONDITION_FAILED, format!("`{id}` already exists at {etag}"));
+        }
+        _ => {}
+    }
+
+    let etag = etag_for(&body);
+    let object = Object {
+        id: id.clone(),
+        etag: etag.clone(),
+        content_type: header_map.get(CONTENT_TYPE).cloned(),
+        body: body.clone(),
+    };
+    let status = match existing.and_then(|index| store.objects.remove(index)) {
+        Some(replaced) => {
+            store.bytes -= replaced.body.len();
+            StatusCode::OK
+        }
+        None => StatusCode::CREATED,
+    };
+    while store.objects.len() >= CAPACITY || store.bytes + body.len() > MAX_BYTES {
+        let Some(oldest) = store.objects.pop_front() else { break };
+        store.bytes -= oldest.body.len();
+    }
+    store.bytes += body.len();
+    store.objects.push_back(object);
+    drop(store);
+
+    (
+        status,
+        [(ETAG, etag.clone()), (LOCATION, format!("/upload-etag/{id}"))],
+        ErasedJson::pretty(Stored {
+            id,
+            etag,
+            size: body.len(),
+        }),
+    )
+        .into_response()
+}
+
+/// `GET /upload-etag/{id}` returns the stored body with its ETag, 304 on a matching `If-None-Match`.
+pub async fn get(Path(id): Path<String>, header_map: HeaderMap) -> Response {
+    let store = OBJECTS.lock().await;
+    let Some(object) = store.objects.iter().find(|object| object.id == id) else {
+        return ErrorDetail::response(StatusCode::NOT_FOUND, format!("no upload `{id}`"));
+    };
+    let etag = [(ETAG, object.etag.clone())];
+    if header_map.contains_key(IF_NONE_MATCH) && none_match(&header_map, &object.etag) {
+        return (StatusCode::NOT_MODIFIED, etag).into_response();
+    }
+    let mut response = (etag, object.body.clone()).into_response();
+    if let Some(content_type) = &object.content_type {
+        response.headers_mut().insert(CONTENT_TYPE, content_type.clone());
+    }
+    response
+}
@@ -0,0 +1,168 @@
+use std::{fmt, str::FromStr, sync::Arc};
+
+use axum::{
+    Extension,
+    extract::Request,
+    http::{
+        HeaderName, HeaderValue, StatusCode,
+        header::{HOST, LOCATION},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use serde::Serialize;
+
+use crate::config::{self, Config};
+
+/// The vhost reported for hosts no `--vhost` lists, answered by `--vhost-default`.
+pub const DEFAULT: &str = "<default>";
+
+/// What a virtual host answers, from the `BEHAVIOR` of `--vhost HOST=BEHAVIOR`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Behavior {
+    /// the httpbin routes, as without vhosts
+    #[default]
+    Httpbin,
+    /// `status:CODE`, this status on every path
+    Status(StatusCode),
+    /// `text:BODY`, 200 with this plain text on every path
+    Text(String),
+    /// `redirect:URL`, 302 to this URL on every path
+    Redirect(String),
+}
+
+impl FromStr for Behavior {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| format!("invalid vhost behavior `{value}`: {reason}");
+        match value.split_once(':') {
+            None if value == "httpbin" => Ok(Behavior::Httpbin),
+            Some(("status", code)) => code
+                .parse::<u16>()
+                .ok()
+                .filter(|code| (200..=599).contains(code))
+                .and_then(|code| StatusCode::from_u16(code).ok())
+                .map(Behavior::Status)
+                .ok_or_else(|| invalid(format!("`{code}` is not a status code from 200 to 599"))),
+            Some(("text", text)) => Ok(Behavior::Text(text.to_string())),
+            Some(("redirect", url)) => match HeaderValue::from_str(url) {
+                Ok(_) if !url.is_empty() => Ok(Behavior::Redirect(url.to_string())),
+                _ => Err(invalid(format!("`{url}` is not a valid Location"))),
+            },
+            _ => Err(invalid(
+                "expected `httpbin`, `status:CODE`, `text:BODY` or `redirect:URL`".to_string(),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Behavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Behavior::Httpbin => write!(f, "httpbin"),
+            Behavior::Status(status) => write!(f, "status:{}", status.as_u16()),
+            Behavior::Text(text) => write!(f, "text:{text}"),
+            Behavior::Redirect(url) => write!(f, "redirect:{url}"),
+        }
+    }
+}
+
+/// Hosts answered alike, from `--vhost HOST[,HOST...]=BEHAVIOR`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vhost {
+    /// lowercase, `*.example.com` for the subdomains of `example.com`, each a valid header value
+    pub hosts: Vec<String>,
+    pub behavior: Behavior,
+}
+
+impl FromStr for Vhost {
+    type Err = String;
+
+    /// Splits on the first `=`, hosts cannot hold one but a text or a redirect URL can.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (hosts, behavior) = value
+            .split_once('=')
+            .ok_or_else(|| format!("invalid vhost `{value}`, expected HOST=BEHAVIOR"))?;
+        let hosts = config::parse_hosts("--vhost", hosts)?;
+        // sent back in `X-Vhost`
+        if let Some(host) = hosts.iter().find(|it| HeaderValue::from_str(it).is_err()) {
+            return Err(format!("invalid host `{host}` for `--vhost`, not a valid header value"));
+        }
+        Ok(Vhost {
+            hosts,
+            behavior: behavior.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for Vhost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.hosts.join(","), self.behavior)
+    }
+}
+
+/// The `--vhost` host a request matched, or [`DEFAULT`], for the reflection routes to report.
+#[derive(Debug, Clone)]
+pub struct Matched(pub String);
+
+#[derive(Serialize)]
+struct Answered<'a> {
+    vhost: &'a str,
+    host: Option<&'a str>,
+    status_code: u16,
+}
+
+/// The `:authority` of HTTP/2 or an absolute-form target, falling back to `Host`, without its port or a final
+/// dot and lowercase.
+fn request_host(request: &Request) -> Option<String> {
+    let host = match request.uri().authority() {
+        Some(authority) => authority.host(),
+        None => request.headers().get(HOST)?.to_str().ok()?,
+    };
+    let host = match host.strip_prefix('[') {
+        // an IPv6 literal, its colons are not a port
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.rsplit_once(':').map_or(host, |(host, _)| host),
+    };
+    Some(host.trim_end_matches('.').to_ascii_lowercase())
+}
+
+/// Answers each request as the `--vhost` of its host does, or as `--vhost-default` when none lists it, and says
+/// which one in `X-Vhost`. Without `--vhost` every request goes to the routes untouched.
+pub async fn route(Extension(config): Extension<Arc<Config>>, mut request: Request, next: Next) -> Response {
+    if config.vhosts.is_empty() {
+        return next.run(request).await;
+    }
+    let host = request_host(&request);
+    let matched = host.as_deref().and_then(|host| {
+        config.vhosts.iter().find_map(|vhost| {
+            let pattern = vhost
+                .hosts
+                .iter()
+                .find(|it| config::host_allowed(std::slice::from_ref(*it), host))?;
+            Some((pattern.as_str(), &vhost.behavior))
+        })
+    });
+    let (name, behavior) = matched.unwrap_or((DEFAULT, &config.vhost_default));
+    let answered = |status: StatusCode| Answered {
+        vhost: name,
+        host: host.as_deref(),
+        status_code: status.as_u16(),
+    };
+
+    let mut response = match behavior {
+        Behavior::Httpbin => {
+            request.extensions_mut().insert(Matched(name.to_string()));
+            next.run(request).await
+        }
+        Behavior::Status(status) => (*status, ErasedJson::pretty(answered(*status))).into_response(),
+        Behavior::Text(text) => text.clone().into_response(),
+        Behavior::Redirect(url) => (StatusCode::FOUND, [(LOCATION, url.clone())]).into_response(),
+    };
+    if let Ok(name) = HeaderValue::from_str(name) {
+        response.headers_mut().insert(HeaderName::from_static("x-vhost"), name);
+    }
+    response
+}
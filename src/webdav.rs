@@ -0,0 +1,234 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{Arc, LazyLock},
+};
+
+use axum::{
+    Extension,
+    body::Bytes,
+    http::{
+        HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri,
+        header::{ALLOW, CONTENT_LENGTH, CONTENT_TYPE, ETAG, LAST_MODIFIED},
+    },
+    response::{IntoResponse, Response},
+};
+use axum_extra::response::ErasedJson;
+use tokio::sync::Mutex;
+
+use crate::{config::Config, data::ErrorDetail, etag::etag_for};
+
+pub const PREFIX: &str = "/dav";
+/// Bounds of the in-memory tree, past them `PUT` and `MKCOL` answer 507 Insufficient Storage.
+pub const MAX_ENTRIES: usize = 1000;
+pub const MAX_BYTES: usize = 16 * 1024 * 1024;
+
+const ALLOWED: &str = "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, MKCOL";
+
+/// Paths below `/dav` without the trailing slash, the root collection is `""` and always exists.
+static TREE: LazyLock<Arc<Mutex<BTreeMap<String, Entry>>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(BTreeMap::from([(String::new(), Entry::collection())]))));
+
+struct Entry {
+    /// `None` for collections
+    body: Option<Bytes>,
+    content_type: Option<HeaderValue>,
+    modified: jiff::Timestamp,
+}
+
+impl Entry {
+    fn collection() -> Self {
+        Entry {
+            body: None,
+            content_type: None,
+            modified: jiff::Timestamp::now(),
+        }
+    }
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn http_date(timestamp: jiff::Timestamp) -> String {
+    timestamp.strftime("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn propfind_response(xml: &mut String, path: &str, entry: &Entry) {
+    let href = match &entry.body {
+        None => format!("{PREFIX}{path}/"),
+        Some(_) => format!("{PREFIX}{path}"),
+    };
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let _ = write!(
+        xml,
+        "  <D:response>\n    <D:href>{}</D:href>\n    <D:propstat>\n      <D:prop>\n        <D:displayname>{}</D:displayname>\n",
+        escape(&href),
+        escape(name)
+    );
+    match &entry.body {
+        None => xml.push_str("        <D:resourcetype><D:collection/></D:resourcetype>\n"),
+        Some(body) => {
+            let content_type = entry
+                .content_type
+                .as_ref()
+                .and_then(|it| it.to_str().ok())
+                .unwrap_or("application/octet-stream");
+            let _ = write!(
+                xml,
+                "        <D:resourcetype/>\n        <D:getcontentlength>{}</D:getcontentlength>\n        <D:getcontenttype>{}</D:getcontenttype>\n        <D:getetag>{}</D:getetag>\n",
+                body.len(),
+                escape(content_type),
+                escape(&etag_for(body))
+            );
+        }
+    }
+    let _ = write!(
+        xml,
+        "        <D:getlastmodified>{}</D:getlastmodified>\n      </D:prop>\n      <D:status>HTTP/1.1 200 OK</D:status>\n    </D:propstat>\n  </D:response>\n",
+        http_date(entry.modified)
+    );
+}
+
+/// Answers the `allprop` properties of the resource and, with `Depth: 1` (the default here) or `infinity`,
+/// of its members; the request body is not looked at.
+fn propfind(tree: &BTreeMap<String, Entry>, path: &str, header_map: &HeaderMap) -> Response {
+    let Some(entry) = tree.get(path) else {
+        return ErrorDetail::response(StatusCode::NOT_FOUND, format!("{PREFIX}{path} does not exist"));
+    };
+    let depth = header_map.get("depth").and_then(|it| it.to_str().ok()).unwrap_or("1");
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">\n");
+    propfind_response(&mut xml, path, entry);
+    if entry.body.is_none() && depth != "0" {
+        let prefix = format!("{path}/");
+        for (member, entry) in tree.range(prefix.clone()..).take_while(|(member, _)| member.starts_with(&prefix)) {
+            if depth.eq_ignore_ascii_case("infinity") || !member[prefix.len()..].contains('/') {
+                propfind_response(&mut xml, member, entry);
+            }
+        }
+    }
+    xml.push_str("</D:multistatus>\n");
+
+    (StatusCode::MULTI_STATUS, [(CONTENT_TYPE, "application/xml; charset=utf-8")], xml).into_response()
+}
+
+fn stored_bytes(tree: &BTreeMap<String, Entry>) -> usize {
+    tree.values().filter_map(|entry| entry.body.as_ref()).map(Bytes::len).sum()
+}
+
+/// A WebDAV class 1 subset on `/dav/*` backed by an in-memory tree, without locking or `PROPPATCH`.
+pub async fn dav(Extension(config): Extension<Arc<Config>>, method: Method, uri: Uri, header_map: HeaderMap, body: Bytes) -> Response {
+    let path = uri
+        .path()
+        .strip_prefix(PREFIX)
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .to_string();
+    if path
+        .split('/')
+        .skip(1)
+        .any(|segment| segment.is_empty() || segment == "." || segment == "..")
+    {
+        return ErrorDetail::response(StatusCode::BAD_REQUEST, "empty and dot segments are not allowed");
+    }
+
+    let mut tree = TREE.lock().await;
+    match method.as_str() {
+        "OPTIONS" => (
+            StatusCode::OK,
+            [
+                (ALLOW, HeaderValue::from_static(ALLOWED)),
+                (HeaderName::from_static("dav"), HeaderValue::from_static("1")),
+            ],
+        )
+            .into_response(),
+        "PROPFIND" => propfind(&tree, &path, &header_map),
+        "GET" | "HEAD" => match tree.get(&path) {
+            None => ErrorDetail::response(StatusCode::NOT_FOUND, format!("{PREFIX}{path} does not exist")),
+            Some(Entry { body: None, .. }) => ErrorDetail::response(StatusCode::METHOD_NOT_ALLOWED, "use PROPFIND to list a collection"),
+            Some(Entry {
+                body: Some(body),
+                content_type,
+                modified,
+            }) => {
+                let mut response = ([(ETAG, etag_for(body)), (LAST_MODIFIED, http_date(*modified))], body.clone()).into_response();
+                if let Some(content_type) = content_type {
+                    response.headers_mut().insert(CONTENT_TYPE, content_type.clone());
+                }
+                if method == Method::HEAD {
+                    response.headers_mut().insert(CONTENT_LENGTH, body.len().into());
+                }
+                response
+            }
+        },
+        "MKCOL" => {
+            if !body.is_empty() {
+                return ErrorDetail::response(StatusCode::UNSUPPORTED_MEDIA_TYPE, "MKCOL does not take a body");
+            }
+            if tree.contains_key(&path) {
+                return ErrorDetail::response(StatusCode::METHOD_NOT_ALLOWED, format!("{PREFIX}{path} already exists"));
+            }
+            if !matches!(tree.get(parent(&path)), Some(Entry { body: None, .. })) {
+                return ErrorDetail::response(StatusCode::CONFLICT, "the parent collection does not exist");
+            }
+            if tree.len() >= MAX_ENTRIES {
+                return ErrorDetail::response(
+                    StatusCode::INSUFFICIENT_STORAGE,
+                    format!("the tree is limited to {MAX_ENTRIES} entries"),
+                );
+            }
+            tree.insert(path, Entry::collection());
+            StatusCode::CREATED.into_response()
+        }
+        "PUT" => {
+            if path.is_empty() || matches!(tree.get(&path), Some(Entry { body: None, .. })) {
+                return ErrorDetail::response(StatusCode::METHOD_NOT_ALLOWED, "cannot PUT over a collection");
+            }
+            if !matches!(tree.get(parent(&path)), Some(Entry { body: None, .. })) {
+                return ErrorDetail::response(StatusCode::CONFLICT, "the parent collection does not exist");
+            }
+            let replaced = tree.get(&path).and_then(|entry| entry.body.as_ref()).map_or(0, Bytes::len);
+            let limit = MAX_BYTES.min(config.max_body_bytes);
+            if stored_bytes(&tree) - replaced + body.len() > limit || (replaced == 0 && tree.len() >= MAX_ENTRIES) {
+                return ErrorDetail::response(
+                    StatusCode::INSUFFICIENT_STORAGE,
+                    format!("the tree is limited to {MAX_ENTRIES} entries and {limit} bytes"),
+                );
+            }
+            let entry = Entry {
+                body: Some(body),
+                content_type: header_map.get(CONTENT_TYPE).cloned(),
+                modified: jiff::Timestamp::now(),
+            };
+            match tree.insert(path, entry) {
+                Some(_) => StatusCode::NO_CONTENT.into_response(),
+                None => StatusCode::CREATED.into_response(),
+            }
+        }
+        "DELETE" => {
+            if path.is_empty() {
+                return ErrorDetail::response(StatusCode::FORBIDDEN, "the root collection cannot be deleted");
+            }
+            if tree.remove(&path).is_none() {
+                return ErrorDetail::response(StatusCode::NOT_FOUND, format!("{PREFIX}{path} does not exist"));
+            }
+            let prefix = format!("{path}/");
+            tree.retain(|member, _| !member.starts_with(&prefix));
+            StatusCode::NO_CONTENT.into_response()
+        }
+        _ => (
+            StatusCode::METHOD_NOT_ALLOWED,
+            [(ALLOW, ALLOWED)],
+            ErasedJson::pretty(ErrorDetail::new(405, "Method Not Allowed", "")),
+        )
+            .into_response(),
+    }
+}
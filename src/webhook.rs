@@ -0,0 +1,121 @@
+use std::{
+    collections::VecDeque,
+    net::IpAddr,
+    sync::{Arc, LazyLock},
+};
+
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use axum_client_ip::InsecureClientIp;
+use axum_extra::response::ErasedJson;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::data::{ErrorDetail, Headers};
+
+/// Maximum number of webhook deliveries kept in memory, older ones are dropped first.
+pub const CAPACITY: usize = 100;
+
+/// Maximum total size of the stored webhook bodies, older ones are dropped first to make room.
+pub const MAX_BYTES: usize = 32 * 1024 * 1024;
+
+static STATE: LazyLock<Arc<Mutex<Log>>> = LazyLock::new(|| Arc::new(Mutex::new(Log::default())));
+
+#[derive(Default)]
+struct Log {
+    next_id: u64,
+    bytes: usize,
+    records: VecDeque<Record>,
+}
+
+#[derive(Serialize, Clone)]
+struct Record {
+    id: u64,
+    timestamp: jiff::Timestamp,
+    method: String,
+    uri: String,
+    #[serde(flatten)]
+    headers: Headers,
+    origin: IpAddr,
+    data: String,
+    json: Option<serde_json::Value>,
+    #[serde(skip)]
+    size: usize,
+}
+
+#[derive(Serialize)]
+struct Stored {
+    id: u64,
+    stored: bool,
+}
+
+#[derive(Serialize)]
+struct LogResponse {
+    capacity: usize,
+    count: usize,
+    max_bytes: usize,
+    bytes: usize,
+    requests: Vec<Record>,
+}
+
+#[derive(Serialize)]
+struct Cleared {
+    cleared: usize,
+}
+
+pub async fn webhook(method: Method, uri: Uri, header_map: HeaderMap, InsecureClientIp(origin): InsecureClientIp, body: Bytes) -> Response {
+    if body.len() > MAX_BYTES {
+        let detail = format!("webhook bodies are limited to {MAX_BYTES} bytes");
+        return ErrorDetail::response(StatusCode::PAYLOAD_TOO_LARGE, detail);
+    }
+
+    let data = match std::str::from_utf8(&body) {
+        Ok(body) => body.into(),
+        Err(_) => BASE64_STANDARD.encode(&body),
+    };
+
+    let mut log = STATE.lock().await;
+    log.next_id += 1;
+    let id = log.next_id;
+    while log.records.len() >= CAPACITY || log.bytes + body.len() > MAX_BYTES {
+        let Some(oldest) = log.records.pop_front() else { break };
+        log.bytes -= oldest.size;
+    }
+    log.bytes += body.len();
+    log.records.push_back(Record {
+        id,
+        timestamp: jiff::Timestamp::now(),
+        method: method.to_string(),
+        uri: uri.to_string(),
+        headers: crate::get_headers(&header_map),
+        origin,
+        data,
+        json: serde_json::from_slice(&body).ok(),
+        size: body.len(),
+    });
+
+    ErasedJson::pretty(Stored { id, stored: true }).into_response()
+}
+
+pub async fn webhook_log() -> impl IntoResponse {
+    let log = STATE.lock().await;
+    ErasedJson::pretty(LogResponse {
+        capacity: CAPACITY,
+        count: log.records.len(),
+        max_bytes: MAX_BYTES,
+        bytes: log.bytes,
+        requests: log.records.iter().cloned().collect(),
+    })
+}
+
+pub async fn webhook_clear() -> impl IntoResponse {
+    let mut log = STATE.lock().await;
+    let cleared = log.records.len();
+    log.records.clear();
+    log.bytes = 0;
+    ErasedJson::pretty(Cleared { cleared })
+}
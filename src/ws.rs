@@ -1,16 +1,47 @@
-use std::{net::SocketAddr, ops::ControlFlow, time::Duration};
+use std::{
+    net::SocketAddr,
+    ops::ControlFlow,
+    time::{Duration, Instant},
+};
 
 use axum::{
     extract::{
+        Request,
         connect_info::ConnectInfo,
         ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, rejection::WebSocketUpgradeRejection},
     },
-    http::StatusCode,
-    response::IntoResponse,
+    http::{
+        HeaderMap, HeaderName, Method, StatusCode,
+        header::{CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_PROTOCOL, SEC_WEBSOCKET_VERSION, UPGRADE},
+    },
+    response::{IntoResponse, Response},
 };
-use axum_extra::response::ErasedJson;
+use axum_extra::{extract::Query, response::ErasedJson};
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use hyper::upgrade::OnUpgrade;
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest as _, Sha1};
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
 use tracing::info;
 
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn rejection(e: WebSocketUpgradeRejection) -> Response {
+    match e {
+        WebSocketUpgradeRejection::InvalidUpgradeHeader(_) => (
+            StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(crate::data::ErrorDetail::new(
+                400,
+                "Bad Request",
+                "missing required `Upgrade: websocket` header",
+            )),
+        )
+            .into_response(),
+        e => e.into_response(),
+    }
+}
+
 pub async fn ws_handler(
     version: axum::http::Version,
     ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
@@ -21,17 +52,401 @@ pub async fn ws_handler(
             tracing::debug!("accepted a WebSocket using {version:?}");
             ws.on_upgrade(move |socket| handle_socket(socket, addr))
         }
-        Err(WebSocketUpgradeRejection::InvalidUpgradeHeader(_)) => (
+        Err(e) => rejection(e),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BinaryEchoParam {
+    /// answers every message with its type and size instead of echoing it
+    #[serde(default)]
+    sizes: bool,
+}
+
+pub async fn binary_echo(ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>, Query(p): Query<BinaryEchoParam>) -> Response {
+    match ws {
+        Ok(ws) => ws.on_upgrade(move |socket| echo_exactly(socket, p.sizes)),
+        Err(e) => rejection(e),
+    }
+}
+
+/// Echoes binary and text messages unchanged, in the type they were sent with. Fragmented messages are
+/// reassembled first, so `sizes` reports the size of whole messages.
+async fn echo_exactly(mut socket: WebSocket, sizes: bool) {
+    while let Ok(Some(Ok(message))) = tokio::time::timeout(IDLE_TIMEOUT, socket.recv()).await {
+        let reply = match message {
+            Message::Binary(data) if sizes => format!(r#"{{"type": "binary", "bytes": {}}}"#, data.len()),
+            Message::Text(text) if sizes => format!(r#"{{"type": "text", "bytes": {}}}"#, text.len()),
+            Message::Binary(_) | Message::Text(_) => {
+                if socket.send(message).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            // pings are answered by the WebSocket implementation
+            Message::Ping(_) | Message::Pong(_) => continue,
+            Message::Close(_) => return,
+        };
+        if socket.send(Message::Text(reply.into())).await.is_err() {
+            return;
+        }
+    }
+    socket.send(Message::Close(None)).await.ok();
+}
+
+/// Subprotocols of `/websocket/subprotocol` without `?supported=`, in decreasing order of preference.
+pub const SUBPROTOCOLS: &str = "echo.v2,echo.v1";
+
+#[derive(Deserialize)]
+pub struct SubprotocolParam {
+    /// the subprotocols the server supports, comma separated, the first the client offers is chosen
+    supported: Option<String>,
+}
+
+/// Echoes messages tagged with the subprotocol negotiated from the client's `Sec-WebSocket-Protocol` offer,
+/// which the handshake response names. A client offering none of the supported subprotocols gets a 400 instead
+/// of the handshake.
+pub async fn subprotocol(
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+    Query(p): Query<SubprotocolParam>,
+    header_map: HeaderMap,
+) -> Response {
+    let ws = match ws {
+        Ok(ws) => ws,
+        Err(e) => return rejection(e),
+    };
+    let supported: Vec<String> = p
+        .supported
+        .as_deref()
+        .unwrap_or(SUBPROTOCOLS)
+        .split(',')
+        .map(|it| it.trim().to_string())
+        .filter(|it| !it.is_empty())
+        .collect();
+    let ws = ws.protocols(supported.clone());
+    let Some(protocol) = ws.selected_protocol().and_then(|it| it.to_str().ok()).map(str::to_string) else {
+        let offered: Vec<_> = header_map
+            .get_all(SEC_WEBSOCKET_PROTOCOL)
+            .iter()
+            .filter_map(|it| it.to_str().ok())
+            .collect();
+        let detail = format!(
+            "none of the offered subprotocols `{}` is supported, expected one of {}",
+            offered.join(", "),
+            supported.join(", ")
+        );
+        return (
+            StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(crate::data::ErrorDetail::new(400, "Bad Request", detail)),
+        )
+            .into_response();
+    };
+    ws.on_upgrade(move |socket| echo_tagged(socket, protocol))
+}
+
+/// Echoes text messages as `[protocol] text` and binary ones with the same prefix bytes.
+async fn echo_tagged(mut socket: WebSocket, protocol: String) {
+    while let Ok(Some(Ok(message))) = tokio::time::timeout(IDLE_TIMEOUT, socket.recv()).await {
+        let reply = match message {
+            Message::Text(text) => Message::Text(format!("[{protocol}] {text}").into()),
+            Message::Binary(data) => Message::Binary([format!("[{protocol}] ").as_bytes(), &data].concat().into()),
+            Message::Ping(_) | Message::Pong(_) => continue,
+            Message::Close(_) => return,
+        };
+        if socket.send(reply).await.is_err() {
+            return;
+        }
+    }
+    socket.send(Message::Close(None)).await.ok();
+}
+
+#[derive(Deserialize)]
+pub struct CloseParam {
+    /// 1000 by default, 1006 drops the connection instead
+    code: Option<u16>,
+    #[serde(default)]
+    reason: String,
+    /// messages to echo before closing
+    #[serde(default)]
+    frames: usize,
+    /// pause before closing, after the echoed messages
+    #[serde(default)]
+    delay: Option<jiff::SignedDuration>,
+}
+
+/// Echoes `frames` messages, then closes with `code` and `reason` after `delay`.
+///
+/// 1006 (abnormal closure) is never sent in a close frame, it is what a client reports when the connection ends
+/// without one, so for 1006 the connection is dropped instead. 1005 and 1015 are just as reserved and
+/// answered 400, like codes outside 1000..=4999, and reasons over the 123 bytes a close frame has room for.
+pub async fn close(ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>, Query(p): Query<CloseParam>) -> Response {
+    let ws = match ws {
+        Ok(ws) => ws,
+        Err(e) => return rejection(e),
+    };
+    let code = p.code.unwrap_or(1000);
+    let invalid = if !(1000..=4999).contains(&code) || matches!(code, 1005 | 1015) {
+        Some(format!("close code {code} cannot be sent, 1000..=4999 except 1005 and 1015 can"))
+    } else if p.reason.len() > 123 {
+        Some("the close reason must be at most 123 bytes".to_string())
+    } else {
+        None
+    };
+    if let Some(detail) = invalid {
+        return (
             StatusCode::BAD_REQUEST,
+            ErasedJson::pretty(crate::data::ErrorDetail::new(400, "Bad Request", detail)),
+        )
+            .into_response();
+    }
+    let delay = p.delay.unwrap_or_default().unsigned_abs();
+    ws.on_upgrade(move |mut socket| async move {
+        let mut echoed = 0;
+        while echoed < p.frames {
+            match tokio::time::timeout(IDLE_TIMEOUT, socket.recv()).await {
+                Ok(Some(Ok(message @ (Message::Text(_) | Message::Binary(_))))) => {
+                    if socket.send(message).await.is_err() {
+                        return;
+                    }
+                    echoed += 1;
+                }
+                Ok(Some(Ok(Message::Ping(_) | Message::Pong(_)))) => {}
+                _ => return,
+            }
+        }
+        tokio::time::sleep(delay).await;
+        if code == 1006 {
+            // dropping the socket closes the TCP connection without a close frame
+            return;
+        }
+        let frame = CloseFrame {
+            code,
+            reason: p.reason.into(),
+        };
+        socket.send(Message::Close(Some(frame))).await.ok();
+    })
+}
+
+#[derive(Deserialize)]
+pub struct MirrorParam {
+    /// ends the session after this many messages
+    frames: Option<u64>,
+}
+
+#[derive(Serialize, Default)]
+struct MirrorStats {
+    /// whole messages, fragments are reassembled first
+    frames: u64,
+    text_frames: u64,
+    binary_frames: u64,
+    pings: u64,
+    bytes: u64,
+    max_frame_bytes: usize,
+    duration_ms: u64,
+    /// `close`, `frames` or `idle`
+    ended_by: &'static str,
+}
+
+impl MirrorStats {
+    fn count(&mut self, len: usize) {
+        self.frames += 1;
+        self.bytes += len as u64;
+        self.max_frame_bytes = self.max_frame_bytes.max(len);
+    }
+}
+
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// The largest message the mirror takes, the 64MiB axum's `WebSocket` takes by default.
+const MAX_MESSAGE_BYTES: usize = 64 << 20;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Echoes every message and counts what it received, then ends the session with the stats as a JSON text message
+/// followed by the close frame. The session ends on a close frame from the client, after `frames` messages, or when
+/// idle. The WebSocket implementation of axum answers a close frame as soon as it reads it, and sends nothing after,
+/// so the mirror speaks the WebSocket framing (RFC 6455) over the upgraded connection itself, to put the stats
+/// before its answer. It takes HTTP/1.1 upgrades only.
+pub async fn mirror(Query(p): Query<MirrorParam>, mut request: Request) -> Response {
+    let headers = request.headers();
+    let has_token = |name: HeaderName, token: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|it| it.to_str().ok())
+            .any(|it| it.split(',').any(|it| it.trim().eq_ignore_ascii_case(token)))
+    };
+    if request.method() != Method::GET || !has_token(CONNECTION, "upgrade") || !has_token(UPGRADE, "websocket") {
+        return crate::data::ErrorDetail::response(StatusCode::BAD_REQUEST, "missing required `Upgrade: websocket` header");
+    }
+    if headers.get(SEC_WEBSOCKET_VERSION).is_none_or(|it| it != "13") {
+        return crate::data::ErrorDetail::response(StatusCode::BAD_REQUEST, "`Sec-WebSocket-Version` header did not include '13'");
+    }
+    let Some(key) = headers.get(SEC_WEBSOCKET_KEY) else {
+        return crate::data::ErrorDetail::response(StatusCode::BAD_REQUEST, "`Sec-WebSocket-Key` header missing");
+    };
+    let accept = BASE64_STANDARD.encode(Sha1::digest([key.as_bytes(), WEBSOCKET_GUID.as_bytes()].concat()));
+    let Some(on_upgrade) = request.extensions_mut().remove::<OnUpgrade>() else {
+        return (
+            StatusCode::UPGRADE_REQUIRED,
             ErasedJson::pretty(crate::data::ErrorDetail::new(
-                400,
-                "Bad Request",
-                "missing required `Upgrade: websocket` header",
+                426,
+                "Upgrade Required",
+                "WebSocket request couldn't be upgraded since no upgrade state was present",
             )),
         )
-            .into_response(),
-        Err(e) => e.into_response(),
+            .into_response();
+    };
+    tokio::spawn(async move {
+        match on_upgrade.await {
+            Ok(upgraded) => mirror_socket(TokioIo::new(upgraded), p.frames).await,
+            Err(e) => info!("mirror upgrade failed: {e}"),
+        }
+    });
+    (
+        StatusCode::SWITCHING_PROTOCOLS,
+        [
+            (CONNECTION, "upgrade".to_string()),
+            (UPGRADE, "websocket".to_string()),
+            (SEC_WEBSOCKET_ACCEPT, accept),
+        ],
+    )
+        .into_response()
+}
+
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// Reads a frame, unmasking it, clients mask every frame they send.
+async fn read_frame(io: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Frame> {
+    let invalid = |detail| std::io::Error::new(std::io::ErrorKind::InvalidData, detail);
+    let mut head = [0; 2];
+    io.read_exact(&mut head).await?;
+    let len = match head[1] & 0x7F {
+        126 => io.read_u16().await?.into(),
+        127 => io.read_u64().await?,
+        len => len.into(),
+    };
+    if head[1] & 0x80 == 0 {
+        return Err(invalid("unmasked frame from the client"));
+    }
+    if len > MAX_MESSAGE_BYTES as u64 {
+        return Err(invalid("frame too large"));
+    }
+    let mut mask = [0; 4];
+    io.read_exact(&mut mask).await?;
+    let mut payload = vec![0; len as usize];
+    io.read_exact(&mut payload).await?;
+    payload.iter_mut().zip(mask.iter().cycle()).for_each(|(byte, mask)| *byte ^= mask);
+    Ok(Frame {
+        fin: head[0] & 0x80 != 0,
+        opcode: head[0] & 0x0F,
+        payload,
+    })
+}
+
+/// Writes a whole unmasked frame, servers never mask theirs.
+async fn write_frame(io: &mut (impl AsyncWrite + Unpin), opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    io.write_all(&frame).await?;
+    io.flush().await
+}
+
+async fn mirror_socket(mut io: impl AsyncRead + AsyncWrite + Unpin, frames: Option<u64>) {
+    let started = Instant::now();
+    let mut stats = MirrorStats::default();
+    // the opcode and the data so far of a fragmented message
+    let mut message: Option<(u8, Vec<u8>)> = None;
+    // the status code of the close frame of the client
+    let mut close = None;
+    stats.ended_by = loop {
+        if frames.is_some_and(|frames| stats.frames >= frames) {
+            break "frames";
+        }
+        let frame = match tokio::time::timeout(IDLE_TIMEOUT, read_frame(&mut io)).await {
+            Err(_) => break "idle",
+            Ok(Ok(frame)) => frame,
+            Ok(Err(e)) => {
+                info!(
+                    frames = stats.frames,
+                    bytes = stats.bytes,
+                    "mirror ended without a close frame: {e}"
+                );
+                return;
+            }
+        };
+        match (frame.opcode, &mut message) {
+            (OP_CLOSE, _) => {
+                close = Some(frame.payload.get(..2).unwrap_or_default().to_vec());
+                break "close";
+            }
+            (OP_PING, _) => {
+                stats.pings += 1;
+                if write_frame(&mut io, OP_PONG, &frame.payload).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            (OP_PONG, _) => continue,
+            (OP_TEXT | OP_BINARY, None) => message = Some((frame.opcode, frame.payload)),
+            (OP_CONTINUATION, Some((_, data))) if data.len() + frame.payload.len() <= MAX_MESSAGE_BYTES => {
+                data.extend_from_slice(&frame.payload);
+            }
+            _ => {
+                info!(opcode = frame.opcode, "mirror ended on a protocol error");
+                write_frame(&mut io, OP_CLOSE, &1002u16.to_be_bytes()).await.ok();
+                return;
+            }
+        }
+        if !frame.fin {
+            continue;
+        }
+        let Some((opcode, data)) = message.take() else { continue };
+        if opcode == OP_TEXT {
+            stats.text_frames += 1;
+        } else {
+            stats.binary_frames += 1;
+        }
+        stats.count(data.len());
+        if write_frame(&mut io, opcode, &data).await.is_err() {
+            return;
+        }
+    };
+    let ended_by = stats.ended_by;
+    stats.duration_ms = started.elapsed().as_millis() as u64;
+    let stats = serde_json::to_vec(&stats).expect("serializable");
+    if write_frame(&mut io, OP_TEXT, &stats).await.is_err() {
+        return;
+    }
+    // the close frame of the client is answered with its status code, the mirror ends its own with `done`
+    let reply = close.unwrap_or_else(|| [&1000u16.to_be_bytes()[..], b"done"].concat());
+    if write_frame(&mut io, OP_CLOSE, &reply).await.is_err() {
+        return;
+    }
+    if ended_by != "close" {
+        let closed = async { while read_frame(&mut io).await.is_ok_and(|frame| frame.opcode != OP_CLOSE) {} };
+        tokio::time::timeout(IDLE_TIMEOUT, closed).await.ok();
     }
+    io.shutdown().await.ok();
 }
 
 enum Either<L, R> {
@@ -42,7 +457,7 @@ enum Either<L, R> {
 async fn handle_socket(mut socket: WebSocket, who: SocketAddr) {
     loop {
         let res = tokio::select! {
-          _ = tokio::time::sleep(Duration::from_secs(10)) => Either::Left(()),
+          _ = tokio::time::sleep(IDLE_TIMEOUT) => Either::Left(()),
           msg = socket.recv() => Either::Right(msg),
         };
 
@@ -85,6 +500,7 @@ fn process_message(msg: Message, who: SocketAddr) -> ControlFlow<(), Option<Mess
         }
         Message::Binary(d) => {
             info!(">>> {} sent {} bytes: {:?}", who, d.len(), d);
+            return ControlFlow::Continue(Some(Message::Binary(d)));
         }
         Message::Close(c) => {
             if let Some(CloseFrame { code, reason }) = c {